@@ -1,4 +1,10 @@
-use crate::{env::Env, error::Result, traits::Innerable};
+use std::{collections::BTreeMap, ops::Bound};
+
+use crate::{
+    env::Env,
+    error::{Error, Result},
+    traits::Innerable,
+};
 
 /// Database backend trait.
 pub trait DatabaseBackend: Innerable + Sized + Send + Sync {
@@ -7,6 +13,74 @@ pub trait DatabaseBackend: Innerable + Sized + Send + Sync {
 
     /// Create or open a database.
     fn create_or_open(env: Env<Self>, db: &str) -> Result<Self::Column>;
+
+    /// Returns the names of all databases in the backend, in no particular order.
+    ///
+    /// Backends that can't store empty databases (Redis, where a database is a key prefix)
+    /// or can't delete them (heed, where dropping clears the database) don't list empty
+    /// databases, even if they were opened.
+    fn list_databases(&self) -> Result<Vec<String>> {
+        Err(unsupported("listing databases"))
+    }
+
+    /// Returns `true` if a database with the given name exists.
+    ///
+    /// Like [`list_databases`](Self::list_databases), some backends report an empty
+    /// database as missing.
+    fn exists(&self, db: &str) -> Result<bool> {
+        Ok(self.list_databases()?.iter().any(|name| name == db))
+    }
+
+    /// Delete a database and all of its entries.
+    ///
+    /// Use [`Env::drop_database`], which checks that the database exists first.
+    fn drop_database(&self, db: &str) -> Result<()> {
+        let _ = db;
+        Err(unsupported("dropping databases"))
+    }
+
+    /// Rename a database.
+    ///
+    /// Use [`Env::rename_database`], which checks that `old` exists and `new` doesn't first.
+    fn rename_database(&self, old: &str, new: &str) -> Result<()> {
+        let _ = (old, new);
+        Err(unsupported("renaming databases"))
+    }
+
+    /// Compact the files of all databases, e.g. to reclaim space after many deletes.
+    ///
+    /// Use [`Env::compact`].
+    fn compact(&self) -> Result<()> {
+        Err(unsupported("compaction"))
+    }
+
+    /// Check the files of all databases for corruption.
+    ///
+    /// Returns `true` if no problems were found and `false` if problems were found and
+    /// repaired. Problems that can't be repaired are returned as errors.
+    fn check_integrity(&self) -> Result<bool> {
+        Err(unsupported("integrity checks"))
+    }
+}
+
+/// Database backends that can be configured per database.
+pub trait DatabaseBackendOptions: DatabaseBackend {
+    /// The options of a database.
+    type ColumnOptions;
+
+    /// Create a database with the given options, or open it if it exists.
+    ///
+    /// Backends persist the options, so the options of an existing database are not
+    /// changed.
+    fn create_or_open_with(
+        env: Env<Self>,
+        db: &str,
+        options: Self::ColumnOptions,
+    ) -> Result<Self::Column>;
+}
+
+fn unsupported(what: &str) -> Error {
+    Error::Unknown(format!("{what} is not supported by this backend"))
 }
 
 /// Database column trait.
@@ -54,6 +128,59 @@ pub trait DBColumnDelete: DBColumn {
     fn delete_db(&self) -> Result<()>;
 }
 
+/// Database trait for compacting a range of keys.
+pub trait DBColumnCompact {
+    /// Compact the files that contain keys in `range`, backends without range compaction
+    /// compact all files.
+    fn compact_range(&self, range: KeyRange) -> Result<()>;
+}
+
+/// Database column trait for deleting ranges of keys.
+pub trait DBColumnDeleteRange: DBColumn {
+    /// Delete the keys from `start` (inclusive) to `end` (exclusive), or up to the last key
+    /// if `end` is `None`.
+    /// Returns the number of deleted keys if the backend can report it.
+    fn delete_range(&self, start: &[u8], end: Option<&[u8]>) -> Result<Option<u64>>;
+
+    /// Delete the keys that start with `prefix`.
+    /// Returns the number of deleted keys if the backend can report it.
+    fn delete_prefix(&self, prefix: impl AsRef<[u8]>) -> Result<Option<u64>> {
+        let prefix = prefix.as_ref();
+        self.delete_range(prefix, prefix_end(prefix).as_deref())
+    }
+}
+
+/// Returns the first key after all keys that start with `prefix`,
+/// or `None` if there is no such key (e.g. for an empty prefix).
+pub fn prefix_end(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut end = prefix.to_vec();
+    while let Some(last) = end.pop() {
+        if last < u8::MAX {
+            end.push(last + 1);
+            return Some(end);
+        }
+    }
+    None
+}
+
+/// Database column trait for writing many key-value pairs at once.
+pub trait DBColumnBatch: DBColumn {
+    /// Set key-value pairs in batch, in a single write batch or transaction of the backend.
+    fn set_multi<I, K, V>(&self, entries: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>;
+
+    /// Set (`Some`) or delete (`None`) keys in batch, in a single write batch or transaction
+    /// of the backend. Later writes to the same key win.
+    fn write_multi<I, K, V>(&self, writes: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (K, Option<V>)>,
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>;
+}
+
 /// Database column trait that returns references.
 pub trait DBColumnRef<'c>: DBColumn {
     /// The type of the 'column', this is a reference to a database.
@@ -96,6 +223,172 @@ pub trait DBTransaction: DBColumn {
 pub trait DBColumnIterator {
     /// Create a new iterator.
     fn iter(&self) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>>;
+
+    /// Create an iterator over the keys.
+    /// Backends override this to avoid reading and copying the values.
+    fn keys(&self) -> Result<impl Iterator<Item = Result<Vec<u8>>>> {
+        Ok(self.iter()?.map(|item| item.map(|(key, _)| key)))
+    }
+
+    /// Count the entries.
+    fn count(&self) -> Result<u64> {
+        self.keys()?
+            .try_fold(0, |count, key| key.map(|_| count + 1))
+    }
+}
+
+/// Database trait that visits entries without copying them.
+pub trait DBColumnIteratorRef {
+    /// Call `f` with the key and value of every entry whose key starts with `prefix`, in key
+    /// order on ordered backends. Stops when `f` returns `false` or an error.
+    ///
+    /// The slices point into the backend's own buffers and are only valid during the call.
+    fn for_each_ref(
+        &self,
+        prefix: &[u8],
+        f: impl FnMut(&[u8], &[u8]) -> Result<bool>,
+    ) -> Result<()>;
+}
+
+/// A range of keys from `start` (inclusive) to `end` (exclusive), or up to the last key if
+/// `end` is `None`. The default range contains all keys.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct KeyRange {
+    /// The first key of the range.
+    pub start: Vec<u8>,
+    /// The first key after the range.
+    pub end: Option<Vec<u8>>,
+}
+
+impl KeyRange {
+    /// Returns `true` if `key` is in the range.
+    pub fn contains(&self, key: &[u8]) -> bool {
+        key >= self.start.as_slice() && self.end.as_deref().map_or(true, |end| key < end)
+    }
+}
+
+/// Split all keys into consecutive ranges at the given keys, which must be sorted.
+pub fn split_at_keys(keys: impl IntoIterator<Item = Vec<u8>>) -> Vec<KeyRange> {
+    let mut ranges = Vec::new();
+    let mut start = Vec::new();
+    for key in keys {
+        if key <= start {
+            continue;
+        }
+        let end = key.clone();
+        ranges.push(KeyRange {
+            start: std::mem::replace(&mut start, key),
+            end: Some(end),
+        });
+    }
+    ranges.push(KeyRange { start, end: None });
+    ranges
+}
+
+/// Database trait for scanning separate ranges of keys, e.g. in parallel.
+pub trait DBColumnIteratorRange: DBColumnIterator {
+    /// Create an iterator over the entries in `range`, in key order.
+    fn iter_range(
+        &self,
+        range: KeyRange,
+    ) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>>;
+
+    /// Split the keys into at most `n` consecutive ranges with roughly the same size,
+    /// together the ranges contain all keys.
+    ///
+    /// By default, the keys are counted and read once to find the boundaries, which
+    /// assumes that [`DBColumnIterator::keys`] is in key order. Backends override this with
+    /// cheaper estimates where they have them.
+    fn split_ranges(&self, n: usize) -> Result<Vec<KeyRange>> {
+        if n <= 1 {
+            return Ok(vec![KeyRange::default()]);
+        }
+        let step = self.count()?.div_ceil(n as u64).max(1);
+
+        let mut bounds = Vec::new();
+        for (i, key) in self.keys()?.enumerate() {
+            let key = key?;
+            if i > 0 && i as u64 % step == 0 {
+                bounds.push(key);
+            }
+        }
+        Ok(split_at_keys(bounds))
+    }
+}
+
+/// Database trait for scans that are split into pages.
+pub trait DBColumnPage {
+    /// Get up to `limit` entries whose key starts with `prefix` in key order, starting after
+    /// the key `after`, or with the first key if `after` is `None`.
+    fn page(
+        &self,
+        after: Option<&[u8]>,
+        limit: usize,
+        prefix: &[u8],
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>>;
+}
+
+/// Returns the bound a page starts at: after the key `after`, but not before `prefix`.
+pub fn page_start<'a>(after: Option<&'a [u8]>, prefix: &'a [u8]) -> Bound<&'a [u8]> {
+    match after {
+        Some(after) if after >= prefix => Bound::Excluded(after),
+        _ => Bound::Included(prefix),
+    }
+}
+
+/// A number of keys, either counted or estimated by the backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyCount {
+    /// The exact number of keys.
+    Exact(u64),
+    /// An estimate, which may be off after many writes or deletes.
+    Estimate(u64),
+}
+
+impl KeyCount {
+    /// The number of keys, exact or estimated.
+    pub fn get(self) -> u64 {
+        match self {
+            Self::Exact(n) | Self::Estimate(n) => n,
+        }
+    }
+
+    /// Returns `true` if the count is exact.
+    pub fn is_exact(self) -> bool {
+        matches!(self, Self::Exact(_))
+    }
+}
+
+/// Statistics of a database, see [`DBColumnStats`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnStats {
+    /// The number of keys.
+    pub keys: KeyCount,
+    /// Approximate bytes used on disk, `None` if the backend doesn't store to disk or
+    /// can't tell.
+    pub disk_bytes: Option<u64>,
+    /// Approximate bytes used in memory, `None` if unknown.
+    pub memory_bytes: Option<u64>,
+    /// Backend specific values, e.g. RocksDB properties or redb tree stats.
+    pub properties: BTreeMap<String, String>,
+}
+
+impl ColumnStats {
+    /// Statistics with only a key count.
+    pub fn new(keys: KeyCount) -> Self {
+        Self {
+            keys,
+            disk_bytes: None,
+            memory_bytes: None,
+            properties: BTreeMap::new(),
+        }
+    }
+}
+
+/// Database trait for size and backend statistics.
+pub trait DBColumnStats {
+    /// Get the statistics of the database.
+    fn stats(&self) -> Result<ColumnStats>;
 }
 
 /// Database Prefix Iterator trait.
@@ -105,4 +398,27 @@ pub trait DBColumnIteratorPrefix {
         &self,
         prefix: impl AsRef<[u8]>,
     ) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>>;
+
+    /// Create an iterator over the keys that start with `prefix`.
+    /// Backends override this to avoid reading and copying the values.
+    fn keys_prefix(
+        &self,
+        prefix: impl AsRef<[u8]>,
+    ) -> Result<impl Iterator<Item = Result<Vec<u8>>>> {
+        let prefix = prefix.as_ref().to_vec();
+        let keys = self
+            .iter_prefix(prefix.clone())?
+            .map(|item| item.map(|(key, _)| key));
+        // some backends continue after the last key with the prefix
+        Ok(keys.take_while(move |key| match key {
+            Ok(key) => key.starts_with(&prefix),
+            Err(_) => true,
+        }))
+    }
+
+    /// Count the entries whose key starts with `prefix`.
+    fn count_prefix(&self, prefix: impl AsRef<[u8]>) -> Result<u64> {
+        self.keys_prefix(prefix)?
+            .try_fold(0, |count, key| key.map(|_| count + 1))
+    }
 }
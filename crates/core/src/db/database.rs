@@ -1,15 +1,21 @@
 use crate::db::transactions::DatabaseTransaction;
 use crate::env::Env;
 use crate::error::Result;
-use crate::{backend::*, traits::*, types::RefValue};
+use crate::{
+    backend::*,
+    traits::*,
+    types::{RefEntry, RefValue},
+};
 
 use inherent::inherent;
 use std::marker::PhantomData;
+use std::ops::Range;
 use std::sync::Arc;
 
 /// A collection of key-value pairs
 #[derive(Clone)]
 pub struct Database<K, V, D: DatabaseBackend> {
+    env: Env<D>,
     name: String,
     pub(super) column: Arc<D::Column>,
     marker: PhantomData<(K, V)>,
@@ -17,12 +23,17 @@ pub struct Database<K, V, D: DatabaseBackend> {
 
 impl<K, V, D: DatabaseBackend> Database<K, V, D> {
     pub(crate) fn new(env: Env<D>, name: &str) -> Result<Self> {
-        let column = D::create_or_open(env, name)?;
-        Ok(Self {
+        let column = D::create_or_open(env.clone(), name)?;
+        Ok(Self::with_column(env, name, column))
+    }
+
+    pub(crate) fn with_column(env: Env<D>, name: &str, column: D::Column) -> Self {
+        Self {
+            env,
             name: name.to_string(),
             column: Arc::new(column),
             marker: PhantomData,
-        })
+        }
     }
 
     /// Returns the name of the database.
@@ -30,9 +41,10 @@ impl<K, V, D: DatabaseBackend> Database<K, V, D> {
         &self.name
     }
 
-    /// Returns a new atomic reference to the database with different types.
-    pub fn as_type<K2, V2>(self) -> Database<K2, V2, D> {
+    /// Change the types without checking the schema registry.
+    fn cast<K2, V2>(self) -> Database<K2, V2, D> {
         Database {
+            env: self.env,
             name: self.name,
             column: self.column,
             marker: PhantomData,
@@ -40,6 +52,32 @@ impl<K, V, D: DatabaseBackend> Database<K, V, D> {
     }
 }
 
+impl<K, V, D: DatabaseBackend> Database<K, V, D>
+where
+    D::Column: DBColumn,
+{
+    /// Returns a new atomic reference to the database with different types.
+    ///
+    /// The types are checked against the [schema registry](crate::schema) like in
+    /// [`Env::open`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use okv::{Env, Error, backend::memory::MemDB};
+    /// let env = Env::with_schema_registry(MemDB::new());
+    /// let db = env.open::<&str, u64>("counters").unwrap();
+    ///
+    /// let db = db.as_type::<String, u64>().unwrap();
+    /// let res = db.as_type::<&str, &str>();
+    /// assert!(matches!(res, Err(Error::SchemaMismatch { .. })));
+    /// ```
+    pub fn as_type<K2: CodecId, V2: CodecId>(self) -> Result<Database<K2, V2, D>> {
+        self.env.check_schema::<K2, V2>(&self.name)?;
+        Ok(self.cast())
+    }
+}
+
 // All databases
 #[inherent]
 impl<Key, Val, D: DatabaseBackend> crate::traits::DBCommon<Key, Val> for Database<Key, Val, D>
@@ -137,6 +175,47 @@ where
     }
 }
 
+#[inherent]
+impl<Key, Val, D, C: DBColumnDeleteRange> DBCommonDeleteRange<Key> for Database<Key, Val, D>
+where
+    D: DatabaseBackend<Column = C>,
+{
+    /// Delete the keys in `range`.
+    /// Keys are compared by their encoded bytes, so this only works as expected with key
+    /// types whose encoding keeps their order, like strings or big-endian integers.
+    /// Returns the number of deleted keys if the backend can report it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use okv::{Env, backend::memory::MemDB};
+    /// let env = Env::new(MemDB::new());
+    /// let db = env.open::<&str, u64>("test").unwrap();
+    /// for key in ["a", "b", "c", "tenant/1", "tenant/2"] {
+    ///     db.set(key, &1).unwrap();
+    /// }
+    ///
+    /// assert_eq!(db.delete_range("a".."c").unwrap(), Some(2));
+    /// assert_eq!(db.delete_prefix("tenant/").unwrap(), Some(2));
+    /// assert_eq!(db.count().unwrap(), 1);
+    /// ```
+    pub fn delete_range<'k>(&self, range: Range<&'k Key::EItem>) -> Result<Option<u64>>
+    where
+        Key: BytesEncode<'k>;
+
+    /// Delete the keys from the bytes `start` (inclusive) to `end` (exclusive), or up to the
+    /// last key if `end` is `None`.
+    pub fn delete_range_raw(&self, start: &[u8], end: Option<&[u8]>) -> Result<Option<u64>> {
+        self.column.delete_range(start, end)
+    }
+
+    /// Delete the keys that start with the bytes `prefix`.
+    /// Returns the number of deleted keys if the backend can report it.
+    pub fn delete_prefix(&self, prefix: impl AsRef<[u8]>) -> Result<Option<u64>> {
+        self.column.delete_prefix(prefix)
+    }
+}
+
 #[inherent]
 impl<'a, Key, Val, D, C> DBCommonRef<'a, Key, Val, C::Ref> for Database<Key, Val, D>
 where
@@ -146,10 +225,20 @@ where
     /// Get the serialized `val` from the database by `key`.
     ///
     /// See [`get_ref`](crate::traits::DBCommonRef::get_ref) for more information.
-    pub fn get_ref<'k>(
-        &'a self,
-        key: &'k Key::EItem,
-    ) -> Result<Option<RefValue<C::Ref, Val::DItem>>>
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use okv::{Env, backend::memory::MemDB};
+    /// let env = Env::new(MemDB::new());
+    /// let db = env.open::<&str, &str>("test").unwrap();
+    /// db.set("hello", "world").unwrap();
+    ///
+    /// // decoded as a `&str` pointing into the stored value
+    /// let val = db.get_ref("hello").unwrap().expect("value exists");
+    /// assert_eq!(val.deserialize().unwrap(), "world");
+    /// ```
+    pub fn get_ref<'k>(&'a self, key: &'k Key::EItem) -> Result<Option<RefValue<C::Ref, Val>>>
     where
         Key: BytesEncode<'k>,
         Val: BytesDecode<'a>,
@@ -177,10 +266,7 @@ where
     ///
     /// See [`get_multi_ref`](crate::traits::DBCommonRefBatch::get_multi_ref) for more information.
     #[allow(clippy::type_complexity)] // this isn't that complex
-    pub fn get_multi_ref<'k, I>(
-        &'a self,
-        keys: I,
-    ) -> Result<Vec<Option<RefValue<C::Ref, Val::DItem>>>>
+    pub fn get_multi_ref<'k, I>(&'a self, keys: I) -> Result<Vec<Option<RefValue<C::Ref, Val>>>>
     where
         Key: BytesEncode<'k>,
         Val: BytesDecode<'a>,
@@ -241,6 +327,170 @@ where
     }
 }
 
+// Databases that support compaction
+impl<K, V, D, C: DBColumnCompact> Database<K, V, D>
+where
+    D: DatabaseBackend<Column = C>,
+{
+    /// Compact the files that contain keys in `range`, e.g. after deleting many entries.
+    /// Use [`KeyRange::default`] to compact the whole database.
+    ///
+    /// Backends without range compaction compact all databases, see [`Env::compact`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use okv::{Env, KeyRange, backend::memory::MemDB};
+    /// let env = Env::new(MemDB::new());
+    /// let db = env.open::<&str, &str>("test").unwrap();
+    /// db.compact_range(KeyRange::default()).unwrap();
+    /// ```
+    pub fn compact_range(&self, range: KeyRange) -> Result<()> {
+        self.column.compact_range(range)
+    }
+}
+
+// Databases with statistics
+impl<K, V, D, C: DBColumnStats> Database<K, V, D>
+where
+    D: DatabaseBackend<Column = C>,
+{
+    /// Get the number of keys and the approximate size of the database.
+    ///
+    /// Depending on the backend the key count is exact or an estimate, see
+    /// [`KeyCount`]. Backend specific values are in [`ColumnStats::properties`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use okv::{Env, backend::memory::MemDB};
+    /// let env = Env::new(MemDB::new());
+    /// let db = env.open::<&str, &str>("test").unwrap();
+    /// db.set("hello", "world").unwrap();
+    ///
+    /// let stats = db.stats().unwrap();
+    /// assert_eq!(stats.keys.get(), 1);
+    /// assert!(stats.keys.is_exact());
+    /// ```
+    pub fn stats(&self) -> Result<ColumnStats> {
+        self.column.stats()
+    }
+}
+
+// Databases that support exporting and importing
+impl<K, V, D, C> Database<K, V, D>
+where
+    C: DBColumn + DBColumnIterator,
+    D: DatabaseBackend<Column = C>,
+{
+    /// Export the database in the [dump format](crate::dump).
+    pub fn export(&self, writer: impl std::io::Write) -> Result<crate::dump::DumpStats> {
+        let mut dump = crate::dump::DumpWriter::new(writer)?;
+        dump.write_column(&self.name, self.column.as_ref())?;
+        dump.finish()
+    }
+}
+
+impl<K, V, D, C> Database<K, V, D>
+where
+    C: DBColumnBatch,
+    D: DatabaseBackend<Column = C>,
+{
+    /// Import a dump of a single database, regardless of its name.
+    ///
+    /// Existing keys are overwritten. Entries are written in batches while the dump is
+    /// read, so the database may be partially imported if an error is returned.
+    pub fn import(&self, reader: impl std::io::Read) -> Result<crate::dump::DumpStats> {
+        crate::dump::import_column(self.column.as_ref(), reader)
+    }
+}
+
+/// The number of values [`Database::into_versioned`] rewrites at a time.
+#[cfg(feature = "serde")]
+const VERSIONED_BATCH_SIZE: usize = 1000;
+
+/// Page through all values of a column and write the values returned by `rewrite`,
+/// `batch_size` values at a time. Returns the number of rewritten values.
+#[cfg(feature = "serde")]
+fn rewrite_values<C: DBColumnPage + DBColumnBatch>(
+    column: &C,
+    batch_size: usize,
+    rewrite: impl Fn(&[u8]) -> Result<Option<Vec<u8>>>,
+) -> Result<u64> {
+    let mut rewritten = 0;
+    let mut after = None;
+    loop {
+        let page = column.page(after.as_deref(), batch_size.max(1), &[])?;
+        let Some((last, _)) = page.last() else {
+            return Ok(rewritten);
+        };
+        after = Some(last.clone());
+
+        let mut writes = Vec::new();
+        for (key, val) in page {
+            if let Some(val) = rewrite(&val)? {
+                writes.push((key, val));
+            }
+        }
+        if !writes.is_empty() {
+            rewritten += writes.len() as u64;
+            column.set_multi(writes)?;
+        }
+    }
+}
+
+// Databases with versioned values
+#[cfg(feature = "serde")]
+impl<K, C, D> Database<K, crate::types::serde::Versioned<C>, D>
+where
+    C: crate::types::serde::SerdeCodec,
+    C::Item: crate::types::serde::Migrate + serde::Serialize,
+    D: DatabaseBackend,
+    D::Column: DBColumnPage + DBColumnBatch,
+{
+    /// Rewrite all values of older versions in the latest version, `batch_size` values at a
+    /// time. Returns the number of rewritten values.
+    ///
+    /// The values are read a page at a time in key order, and the values of older versions
+    /// in a page are written in one batch. Values that are already in the latest version
+    /// are skipped, so a migration that returned an error can be run again.
+    pub fn migrate_all(&self, batch_size: usize) -> Result<u64> {
+        rewrite_values(self.column.as_ref(), batch_size, |val| {
+            crate::types::serde::Versioned::<C>::upgrade(val)
+        })
+    }
+}
+
+// Databases with serde values
+#[cfg(feature = "serde")]
+impl<K, C, D> Database<K, C, D>
+where
+    C: crate::types::serde::SerdeCodec,
+    C::Item: crate::types::serde::Migrate,
+    D: DatabaseBackend,
+    D::Column: DBColumnPage + DBColumnBatch,
+{
+    /// Adopt values written without [`Versioned`](crate::types::serde::Versioned) as the
+    /// version of `C::Item`, by prefixing every value with its version tag, and returns the
+    /// database with versioned values. The recorded [schema](crate::schema) is updated too.
+    ///
+    /// It is not atomic, if an error is returned, some values may be tagged. Values that
+    /// only decode with a version tag are skipped, so it can be called again.
+    pub fn into_versioned(self) -> Result<Database<K, crate::types::serde::Versioned<C>, D>> {
+        rewrite_values(self.column.as_ref(), VERSIONED_BATCH_SIZE, |val| {
+            Ok(crate::types::serde::Versioned::<C>::adopt(val))
+        })?;
+
+        if let Some(registry) = self.env.registry()? {
+            if let Some(mut schema) = registry.get(&self.name)? {
+                schema.value = crate::types::serde::versioned_codec_id(&schema.value);
+                registry.set(&self.name, &schema)?;
+            }
+        }
+        Ok(self.cast())
+    }
+}
+
 // Databases that support iterating
 #[inherent]
 impl<K: BytesDecodeOwned, V: BytesDecodeOwned, D, C> DBCommonIter<K, V> for Database<K, V, D>
@@ -256,6 +506,90 @@ where
     pub fn iter_raw(&self) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>> + '_> {
         self.column.iter()
     }
+
+    /// Get an iterator over the keys of the database, without decoding the values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use okv::{Env, backend::memory::MemDB};
+    /// let env = Env::new(MemDB::new());
+    /// let db = env.open::<&str, u64>("test").unwrap();
+    /// db.set("a", &1).unwrap();
+    /// db.set("b", &2).unwrap();
+    ///
+    /// let mut keys = db.keys().unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+    /// keys.sort();
+    /// assert_eq!(keys, ["a", "b"]);
+    /// assert_eq!(db.values().unwrap().sum::<Result<u64, _>>().unwrap(), 3);
+    /// assert_eq!(db.count().unwrap(), 2);
+    /// ```
+    pub fn keys(&self) -> Result<impl Iterator<Item = Result<K::DItem>> + '_>;
+
+    /// Get an iterator over the values of the database, without decoding the keys.
+    pub fn values(&self) -> Result<impl Iterator<Item = Result<V::DItem>> + '_>;
+
+    /// Get a raw iterator over the keys of the database.
+    #[allow(refining_impl_trait)] // we need to specify the lifetime
+    pub fn keys_raw(&self) -> Result<impl Iterator<Item = Result<Vec<u8>>> + '_> {
+        self.column.keys()
+    }
+
+    /// Count the entries of the database.
+    pub fn count(&self) -> Result<u64> {
+        self.column.count()
+    }
+}
+
+// Databases that support iterating without copying
+impl<K, V, D, C> Database<K, V, D>
+where
+    C: DBColumnIteratorRef,
+    D: DatabaseBackend<Column = C>,
+{
+    /// Call `f` with every entry, without copying the keys and values out of the backend.
+    /// Return `false` from `f` to stop early.
+    ///
+    /// Entries are visited while the backend's iterator or locks are held, so don't write
+    /// to the database from `f`. On some backends this deadlocks, e.g. the in-memory
+    /// backend holds a read lock on the entry's shard while `f` runs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use okv::{Env, backend::memory::MemDB};
+    /// let env = Env::new(MemDB::new());
+    /// let db = env.open::<&str, &str>("test").unwrap();
+    /// db.set("hello", "world").unwrap();
+    ///
+    /// let mut len = 0;
+    /// db.for_each_ref(|entry| {
+    ///     let val: &str = entry.value()?;
+    ///     len += val.len();
+    ///     Ok(true)
+    /// })
+    /// .unwrap();
+    /// assert_eq!(len, 5);
+    /// ```
+    pub fn for_each_ref(&self, f: impl FnMut(RefEntry<'_, K, V>) -> Result<bool>) -> Result<()> {
+        self.for_each_prefix_ref([], f)
+    }
+
+    /// Call `f` with every entry whose key starts with the bytes `prefix`, without copying
+    /// them. See [`Database::for_each_ref`].
+    pub fn for_each_prefix_ref(
+        &self,
+        prefix: impl AsRef<[u8]>,
+        mut f: impl FnMut(RefEntry<'_, K, V>) -> Result<bool>,
+    ) -> Result<()> {
+        self.column.for_each_ref(prefix.as_ref(), |key, val| {
+            f(RefEntry {
+                key,
+                val,
+                marker: PhantomData,
+            })
+        })
+    }
 }
 
 // Databases that support iterating with a prefix
@@ -272,4 +606,17 @@ where
     ) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>> {
         self.column.iter_prefix(prefix)
     }
+
+    /// Iterate over the keys that start with the given byte prefix.
+    fn keys_prefix_raw(
+        &'a self,
+        prefix: impl AsRef<[u8]>,
+    ) -> Result<impl Iterator<Item = Result<Vec<u8>>>> {
+        self.column.keys_prefix(prefix)
+    }
+
+    /// Count the entries whose key starts with the given byte prefix.
+    fn count_prefix(&'a self, prefix: impl AsRef<[u8]>) -> Result<u64> {
+        self.column.count_prefix(prefix)
+    }
 }
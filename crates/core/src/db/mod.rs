@@ -1,6 +1,9 @@
 mod database;
 mod database_async;
+mod page;
+mod range;
 mod transactions;
 
 pub use self::database::Database;
+pub use self::page::{Cursor, Page};
 pub use self::transactions::DatabaseTransaction;
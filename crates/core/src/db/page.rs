@@ -0,0 +1,142 @@
+use std::{fmt, str::FromStr};
+
+use super::Database;
+use crate::{
+    backend::{DBColumnPage, DatabaseBackend},
+    error::{DecodeError, Error, Result},
+    traits::BytesDecodeOwned,
+};
+
+/// Where a [`Page`] ends, used to request the next page.
+///
+/// The cursor contains the last key of the page. It is formatted as a hex string, so it
+/// can be passed to clients and parsed again with [`str::parse`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Cursor(Vec<u8>);
+
+impl Cursor {
+    /// Create a cursor that continues after the given key.
+    pub fn after(key: impl Into<Vec<u8>>) -> Self {
+        Self(key.into())
+    }
+
+    /// The key the next page starts after.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|b| write!(f, "{b:02x}"))
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = DecodeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() % 2 != 0 || !s.is_ascii() {
+            return Err(DecodeError::InvalidCursor);
+        }
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|_| DecodeError::InvalidCursor))
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Cursor {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Cursor {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// A page of entries, see [`Database::page`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<K, V> {
+    /// The entries of the page, in key order.
+    pub items: Vec<(K, V)>,
+    /// The cursor of the next page, `None` if this is the last page.
+    pub next: Option<Cursor>,
+}
+
+impl<K, V, D, C> Database<K, V, D>
+where
+    C: DBColumnPage,
+    D: DatabaseBackend<Column = C>,
+{
+    /// Get up to `limit` entries whose key starts with the bytes `prefix`, in the order of
+    /// their encoded keys. Pass the [`Page::next`] cursor of a page to get the next page,
+    /// or `None` to start with the first key.
+    ///
+    /// Every page is read separately, so entries written between two pages show up in
+    /// later pages if their key is after the cursor.
+    ///
+    /// Returns [`Error::ZeroPageLimit`] if `limit` is 0, an empty page has no cursor to
+    /// continue from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use okv::{Env, backend::memory::MemDB};
+    /// let env = Env::new(MemDB::new());
+    /// let db = env.open::<&str, u64>("test").unwrap();
+    /// for (i, key) in ["a", "b", "c"].into_iter().enumerate() {
+    ///     db.set(key, &(i as u64)).unwrap();
+    /// }
+    ///
+    /// let page = db.page(None, 2, []).unwrap();
+    /// assert_eq!(page.items, [("a".to_string(), 0), ("b".to_string(), 1)]);
+    ///
+    /// // the cursor can be sent to clients as a string
+    /// let cursor = page.next.unwrap().to_string().parse().unwrap();
+    /// let page = db.page(Some(cursor), 2, []).unwrap();
+    /// assert_eq!(page.items, [("c".to_string(), 2)]);
+    /// assert_eq!(page.next, None);
+    /// ```
+    pub fn page(
+        &self,
+        after: Option<Cursor>,
+        limit: usize,
+        prefix: impl AsRef<[u8]>,
+    ) -> Result<Page<K::DItem, V::DItem>>
+    where
+        K: BytesDecodeOwned,
+        V: BytesDecodeOwned,
+    {
+        if limit == 0 {
+            return Err(Error::ZeroPageLimit);
+        }
+
+        let after = after.as_ref().map(Cursor::as_bytes);
+        // read one more entry to know if there is a next page
+        let mut entries = self
+            .column
+            .page(after, limit.saturating_add(1), prefix.as_ref())?;
+
+        let next = match entries.len() > limit {
+            true => {
+                entries.truncate(limit);
+                entries.last().map(|(key, _)| Cursor::after(key.as_slice()))
+            }
+            false => None,
+        };
+
+        let items = entries
+            .into_iter()
+            .map(|(key, val)| Ok((K::bytes_decode_owned(&key)?, V::bytes_decode_owned(&val)?)))
+            .collect::<Result<_>>()?;
+        Ok(Page { items, next })
+    }
+}
@@ -0,0 +1,98 @@
+use super::Database;
+use crate::{
+    backend::{DBColumnIteratorRange, DatabaseBackend, KeyRange},
+    error::Result,
+    traits::BytesDecodeOwned,
+};
+
+impl<K, V, D, C> Database<K, V, D>
+where
+    C: DBColumnIteratorRange + 'static,
+    D: DatabaseBackend<Column = C>,
+{
+    /// Split the database into at most `n` ranges of keys with roughly the same size, which
+    /// can be scanned separately with [`Database::iter_range`]. Together the ranges contain
+    /// all keys.
+    ///
+    /// The sizes are estimated by the backend, RocksDB uses the sizes of its files without
+    /// reading any keys, other backends count the keys and read them once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use okv::{Env, backend::memory::MemDB};
+    /// let env = Env::new(MemDB::new());
+    /// let db = env.open::<u64, u64>("test").unwrap();
+    /// for i in 0..100 {
+    ///     db.set(&i, &i).unwrap();
+    /// }
+    ///
+    /// let ranges = db.split_ranges(4).unwrap();
+    /// assert_eq!(ranges.len(), 4);
+    ///
+    /// let mut sum = 0u64;
+    /// for range in ranges {
+    ///     for item in db.iter_range(range).unwrap() {
+    ///         sum += item.unwrap().1;
+    ///     }
+    /// }
+    /// assert_eq!(sum, (0..100).sum::<u64>());
+    /// ```
+    pub fn split_ranges(&self, n: usize) -> Result<Vec<KeyRange>> {
+        self.column.split_ranges(n)
+    }
+
+    /// Get an iterator over the entries in `range`, transforming raw bytes to `K` and `V`.
+    pub fn iter_range(
+        &self,
+        range: KeyRange,
+    ) -> Result<impl Iterator<Item = Result<(K::DItem, V::DItem)>> + '_>
+    where
+        K: BytesDecodeOwned,
+        V: BytesDecodeOwned,
+    {
+        let raw_iterator = self.iter_range_raw(range)?;
+        Ok(raw_iterator.map(|item| {
+            let (key_bytes, val_bytes) = item?;
+            let key = K::bytes_decode_owned(&key_bytes)?;
+            let val = V::bytes_decode_owned(&val_bytes)?;
+            Ok((key, val))
+        }))
+    }
+
+    /// Get a raw iterator over the entries in `range`.
+    pub fn iter_range_raw(
+        &self,
+        range: KeyRange,
+    ) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>> + '_> {
+        self.column.iter_range(range)
+    }
+
+    /// Get a parallel iterator over the database (requires `rayon` feature).
+    ///
+    /// The database is split into a few ranges per thread of the rayon thread pool with
+    /// [`Database::split_ranges`], which are scanned concurrently. Entries are not in order.
+    #[cfg(feature = "rayon")]
+    pub fn par_iter(
+        &self,
+    ) -> Result<impl rayon::iter::ParallelIterator<Item = Result<(K::DItem, V::DItem)>> + '_>
+    where
+        Self: Sync,
+        K: BytesDecodeOwned,
+        V: BytesDecodeOwned,
+        K::DItem: Send,
+        V::DItem: Send,
+    {
+        use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+        // more ranges than threads, so threads that finish early can take over
+        let ranges = self.split_ranges(rayon::current_num_threads() * 4)?;
+        Ok(ranges.into_par_iter().flat_map_iter(move |range| {
+            let (iter, err) = match self.iter_range(range) {
+                Ok(iter) => (Some(iter), None),
+                Err(e) => (None, Some(Err(e))),
+            };
+            err.into_iter().chain(iter.into_iter().flatten())
+        }))
+    }
+}
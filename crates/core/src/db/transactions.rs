@@ -1,6 +1,6 @@
 use crate::{backend::*, error::Result, traits::*};
 use inherent::inherent;
-use std::marker::PhantomData;
+use std::{marker::PhantomData, ops::Range};
 
 /// A temporary write-transaction
 pub struct DatabaseTransaction<'a, K, V, C>
@@ -112,6 +112,29 @@ where
     }
 }
 
+#[inherent]
+impl<'a, Key, Val, C> DBCommonDeleteRange<Key> for DatabaseTransaction<'a, Key, Val, C>
+where
+    C: DBColumnTransaction<'a>,
+    C::Txn: DBColumnDeleteRange,
+{
+    /// Delete the keys in `range`, see [`Database::delete_range`](crate::db::Database::delete_range).
+    pub fn delete_range<'k>(&self, range: Range<&'k Key::EItem>) -> Result<Option<u64>>
+    where
+        Key: BytesEncode<'k>;
+
+    /// Delete the keys from the bytes `start` (inclusive) to `end` (exclusive), or up to the
+    /// last key if `end` is `None`.
+    pub fn delete_range_raw(&self, start: &[u8], end: Option<&[u8]>) -> Result<Option<u64>> {
+        self.column.delete_range(start, end)
+    }
+
+    /// Delete the keys that start with the bytes `prefix`.
+    pub fn delete_prefix(&self, prefix: impl AsRef<[u8]>) -> Result<Option<u64>> {
+        self.column.delete_prefix(prefix)
+    }
+}
+
 impl<'tx, K: BytesDecodeOwned, V: BytesDecodeOwned, C: DBColumnTransaction<'tx>> DBCommonIter<K, V>
     for DatabaseTransaction<'tx, K, V, C>
 where
@@ -121,6 +144,16 @@ where
     fn iter_raw(&self) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>> {
         self.column.iter()
     }
+
+    /// Iterate over all keys in the database.
+    fn keys_raw(&self) -> Result<impl Iterator<Item = Result<Vec<u8>>>> {
+        self.column.keys()
+    }
+
+    /// Count the entries in the database.
+    fn count(&self) -> Result<u64> {
+        self.column.count()
+    }
 }
 
 impl<'a, K: BytesDecodeOwned, V: BytesDecodeOwned, C> DBCommonIterPrefix<'a, K, V>
@@ -136,4 +169,17 @@ where
     ) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>> {
         self.column.iter_prefix(prefix)
     }
+
+    /// Iterate over the keys that start with the given byte prefix.
+    fn keys_prefix_raw(
+        &'a self,
+        prefix: impl AsRef<[u8]>,
+    ) -> Result<impl Iterator<Item = Result<Vec<u8>>>> {
+        self.column.keys_prefix(prefix)
+    }
+
+    /// Count the entries whose key starts with the given byte prefix.
+    fn count_prefix(&'a self, prefix: impl AsRef<[u8]>) -> Result<u64> {
+        self.column.count_prefix(prefix)
+    }
 }
@@ -0,0 +1,345 @@
+//! A backend independent dump format, used to move data between backends.
+//!
+//! Use [`Env::export`](crate::env::Env::export) and [`Env::import`](crate::env::Env::import)
+//! to dump and restore all databases of an environment, or
+//! [`Database::export`](crate::db::Database::export) and
+//! [`Database::import`](crate::db::Database::import) for a single database.
+//!
+//! # Format
+//!
+//! All integers are little endian, byte strings are prefixed with their length as `u32`.
+//!
+//! * Header: the magic bytes `OKVDUMP` followed by the version (`u8`, currently `1`).
+//! * Database: `1`, the name of the database.
+//! * Entry: `2`, key and value. Entries belong to the last database.
+//! * End of database: `3`, the number of entries in the database (`u64`).
+//! * End of dump: `0`, the number of databases (`u32`) and a CRC32 checksum (`u32`) of
+//!   everything before the checksum.
+
+use std::io::{self, BufReader, BufWriter, Read, Write};
+
+use crate::{
+    backend::{DBColumnBatch, DBColumnIterator},
+    error::{DecodeError, EncodeError, Result},
+};
+
+/// The magic bytes at the start of every dump.
+pub const DUMP_MAGIC: &[u8; 7] = b"OKVDUMP";
+
+/// The current version of the dump format.
+pub const DUMP_VERSION: u8 = 1;
+
+const TAG_END: u8 = 0;
+const TAG_DATABASE: u8 = 1;
+const TAG_ENTRY: u8 = 2;
+const TAG_DATABASE_END: u8 = 3;
+
+/// The number of entries that are imported in a single batch.
+const IMPORT_BATCH_SIZE: usize = 1024;
+
+fn encode_err(e: io::Error) -> crate::error::Error {
+    EncodeError::Io(e).into()
+}
+
+fn decode_err(e: io::Error) -> crate::error::Error {
+    DecodeError::Io(e).into()
+}
+
+fn invalid(msg: &str) -> crate::error::Error {
+    decode_err(io::Error::new(io::ErrorKind::InvalidData, msg))
+}
+
+/// The number of databases and entries in a dump.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DumpStats {
+    /// The number of databases.
+    pub databases: u32,
+    /// The number of entries in all databases.
+    pub entries: u64,
+}
+
+/// Writes a dump.
+pub struct DumpWriter<W: Write> {
+    inner: BufWriter<W>,
+    hasher: crc32fast::Hasher,
+    stats: DumpStats,
+    // the number of entries written to the current database
+    current: Option<u64>,
+}
+
+impl<W: Write> DumpWriter<W> {
+    /// Start a new dump.
+    pub fn new(writer: W) -> Result<Self> {
+        let mut dump = Self {
+            inner: BufWriter::new(writer),
+            hasher: crc32fast::Hasher::new(),
+            stats: DumpStats::default(),
+            current: None,
+        };
+        dump.write(DUMP_MAGIC)?;
+        dump.write(&[DUMP_VERSION])?;
+        Ok(dump)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        self.hasher.update(bytes);
+        self.inner.write_all(bytes).map_err(encode_err)
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        let len = u32::try_from(bytes.len()).map_err(|_| {
+            encode_err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "keys and values in dumps are limited to 4 GiB",
+            ))
+        })?;
+        self.write(&len.to_le_bytes())?;
+        self.write(bytes)
+    }
+
+    /// Start a new database. Ends the current database, if any.
+    pub fn begin_database(&mut self, name: &str) -> Result<()> {
+        self.end_database()?;
+        self.write(&[TAG_DATABASE])?;
+        self.write_bytes(name.as_bytes())?;
+        self.current = Some(0);
+        self.stats.databases += 1;
+        Ok(())
+    }
+
+    /// Write an entry of the current database.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no database has been started.
+    pub fn write_entry(&mut self, key: &[u8], val: &[u8]) -> Result<()> {
+        let count = self
+            .current
+            .as_mut()
+            .expect("entries must be written after `begin_database`");
+        *count += 1;
+
+        self.write(&[TAG_ENTRY])?;
+        self.write_bytes(key)?;
+        self.write_bytes(val)?;
+        self.stats.entries += 1;
+        Ok(())
+    }
+
+    /// End the current database, if any.
+    pub fn end_database(&mut self) -> Result<()> {
+        if let Some(count) = self.current.take() {
+            self.write(&[TAG_DATABASE_END])?;
+            self.write(&count.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Write all entries of a column as a database with the given name.
+    pub fn write_column(&mut self, name: &str, column: &impl DBColumnIterator) -> Result<()> {
+        self.begin_database(name)?;
+        for item in column.iter()? {
+            let (key, val) = item?;
+            self.write_entry(&key, &val)?;
+        }
+        self.end_database()
+    }
+
+    /// Finish the dump and flush the writer.
+    pub fn finish(mut self) -> Result<DumpStats> {
+        self.end_database()?;
+        self.write(&[TAG_END])?;
+        self.write(&self.stats.databases.to_le_bytes())?;
+
+        let checksum = self.hasher.clone().finalize();
+        self.inner
+            .write_all(&checksum.to_le_bytes())
+            .map_err(encode_err)?;
+        self.inner.flush().map_err(encode_err)?;
+        Ok(self.stats)
+    }
+}
+
+/// An item read from a dump.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DumpItem {
+    /// The start of a database with the given name.
+    Database(String),
+    /// An entry of the current database.
+    Entry(Vec<u8>, Vec<u8>),
+    /// The end of the current database.
+    DatabaseEnd,
+}
+
+/// Reads a dump.
+///
+/// The reader is an iterator over the items of the dump. Counts and the checksum are
+/// verified while reading, an error is returned as the last item if they don't match.
+/// Since the checksum covers the whole dump, the dump is only known to be intact once
+/// the iterator returned `None`.
+pub struct DumpReader<R: Read> {
+    inner: BufReader<R>,
+    hasher: crc32fast::Hasher,
+    stats: DumpStats,
+    current: Option<u64>,
+    done: bool,
+}
+
+impl<R: Read> DumpReader<R> {
+    /// Start reading a dump and check its header.
+    pub fn new(reader: R) -> Result<Self> {
+        let mut dump = Self {
+            inner: BufReader::new(reader),
+            hasher: crc32fast::Hasher::new(),
+            stats: DumpStats::default(),
+            current: None,
+            done: false,
+        };
+
+        if dump.read::<7>()? != *DUMP_MAGIC {
+            return Err(invalid("not an okv dump"));
+        }
+        let [version] = dump.read::<1>()?;
+        if version != DUMP_VERSION {
+            return Err(invalid(&format!("unsupported dump version {version}")));
+        }
+        Ok(dump)
+    }
+
+    /// The number of databases and entries read so far.
+    pub fn stats(&self) -> DumpStats {
+        self.stats
+    }
+
+    fn read<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut buf = [0; N];
+        self.inner.read_exact(&mut buf).map_err(decode_err)?;
+        self.hasher.update(&buf);
+        Ok(buf)
+    }
+
+    fn read_bytes(&mut self) -> Result<Vec<u8>> {
+        let len = u32::from_le_bytes(self.read()?) as usize;
+        let mut buf = Vec::new();
+        (&mut self.inner)
+            .take(len as u64)
+            .read_to_end(&mut buf)
+            .map_err(decode_err)?;
+        if buf.len() != len {
+            return Err(decode_err(io::ErrorKind::UnexpectedEof.into()));
+        }
+        self.hasher.update(&buf);
+        Ok(buf)
+    }
+
+    fn read_item(&mut self) -> Result<Option<DumpItem>> {
+        let [tag] = self.read::<1>()?;
+        let item = match tag {
+            TAG_DATABASE => {
+                if self.current.is_some() {
+                    return Err(invalid("database started before the previous one ended"));
+                }
+                let name = String::from_utf8(self.read_bytes()?)
+                    .map_err(|_| invalid("database name is not valid utf-8"))?;
+                self.current = Some(0);
+                self.stats.databases += 1;
+                DumpItem::Database(name)
+            }
+            TAG_ENTRY => {
+                let Some(count) = self.current.as_mut() else {
+                    return Err(invalid("entry outside of a database"));
+                };
+                *count += 1;
+                self.stats.entries += 1;
+                DumpItem::Entry(self.read_bytes()?, self.read_bytes()?)
+            }
+            TAG_DATABASE_END => {
+                let expected = u64::from_le_bytes(self.read()?);
+                if self.current.take() != Some(expected) {
+                    return Err(invalid("number of entries in database doesn't match"));
+                }
+                DumpItem::DatabaseEnd
+            }
+            TAG_END => {
+                let databases = u32::from_le_bytes(self.read()?);
+                if self.current.is_some() || databases != self.stats.databases {
+                    return Err(invalid("number of databases doesn't match"));
+                }
+
+                let expected = self.hasher.clone().finalize();
+                let mut checksum = [0; 4];
+                self.inner.read_exact(&mut checksum).map_err(decode_err)?;
+                if u32::from_le_bytes(checksum) != expected {
+                    return Err(invalid("checksum mismatch"));
+                }
+                return Ok(None);
+            }
+            _ => return Err(invalid(&format!("unknown tag {tag}"))),
+        };
+        Ok(Some(item))
+    }
+}
+
+impl<R: Read> Iterator for DumpReader<R> {
+    type Item = Result<DumpItem>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let item = self.read_item();
+        if !matches!(item, Ok(Some(_))) {
+            self.done = true;
+        }
+        item.transpose()
+    }
+}
+
+/// Collects imported entries and writes them to a column in batches.
+#[derive(Default)]
+pub(crate) struct ImportBatch(Vec<(Vec<u8>, Vec<u8>)>);
+
+impl ImportBatch {
+    /// Add an entry, writing the batch once it is full.
+    pub(crate) fn push(
+        &mut self,
+        column: &impl DBColumnBatch,
+        key: Vec<u8>,
+        val: Vec<u8>,
+    ) -> Result<()> {
+        self.0.push((key, val));
+        match self.0.len() >= IMPORT_BATCH_SIZE {
+            true => self.write(column),
+            false => Ok(()),
+        }
+    }
+
+    /// Write all collected entries.
+    pub(crate) fn write(&mut self, column: &impl DBColumnBatch) -> Result<()> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+        column.set_multi(self.0.drain(..))
+    }
+}
+
+/// Import all entries of a dump of a single database into a column.
+pub(crate) fn import_column(column: &impl DBColumnBatch, reader: impl Read) -> Result<DumpStats> {
+    let mut dump = DumpReader::new(reader)?;
+    let mut databases = 0;
+    let mut batch = ImportBatch::default();
+    for item in dump.by_ref() {
+        match item? {
+            DumpItem::Database(_) => {
+                databases += 1;
+                if databases > 1 {
+                    return Err(invalid("dump contains more than one database"));
+                }
+            }
+            DumpItem::Entry(key, val) => batch.push(column, key, val)?,
+            DumpItem::DatabaseEnd => batch.write(column)?,
+        }
+    }
+    Ok(dump.stats())
+}
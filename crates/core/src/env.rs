@@ -1,5 +1,15 @@
-use crate::{backend::DatabaseBackend, db::Database, error::Result};
-use std::sync::Arc;
+use crate::{
+    backend::{DBColumn, DBColumnBatch, DBColumnIterator, DatabaseBackend, DatabaseBackendOptions},
+    db::Database,
+    dump::{DumpItem, DumpReader, DumpStats, DumpWriter, ImportBatch},
+    error::{Error, Result},
+    schema::{Registry, Schema, SCHEMA_DATABASE},
+    traits::CodecId,
+};
+use std::{
+    io::{Read, Write},
+    sync::Arc,
+};
 
 /// A database environment
 pub struct Env<D: DatabaseBackend>(Arc<EnvInner<D>>);
@@ -10,7 +20,7 @@ impl<D: DatabaseBackend> Clone for Env<D> {
     }
 }
 
-impl<'a, D: DatabaseBackend> Env<D> {
+impl<D: DatabaseBackend> Env<D> {
     pub fn db(&self) -> &D {
         &self.0.db
     }
@@ -23,9 +33,84 @@ impl<'a, D: DatabaseBackend> Env<D> {
 
     /// Create a new environment backed by the given database.
     pub fn new(db: D) -> Self {
-        Self(Arc::new(EnvInner { db }))
+        Self(Arc::new(EnvInner {
+            db,
+            schema_registry: false,
+        }))
     }
 
+    /// Create a new environment that records the codecs of every database in a
+    /// [schema registry](crate::schema), and checks them when a database is opened.
+    pub fn with_schema_registry(db: D) -> Self {
+        Self(Arc::new(EnvInner {
+            db,
+            schema_registry: true,
+        }))
+    }
+
+    /// Returns the names of all databases, sorted by name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use okv::{Env, backend::memory::MemDB};
+    /// let env = Env::new(MemDB::new());
+    /// env.open::<&str, &str>("b").unwrap();
+    /// env.open::<&str, &str>("a").unwrap();
+    /// assert_eq!(env.databases().unwrap(), vec!["a", "b"]);
+    /// ```
+    pub fn databases(&self) -> Result<Vec<String>> {
+        let mut databases = self.db().list_databases()?;
+        databases.sort();
+        Ok(databases)
+    }
+
+    /// Returns `true` if a database with the given name exists.
+    ///
+    /// Some backends report an empty database as missing, see
+    /// [`DatabaseBackend::exists`].
+    pub fn exists(&self, name: &str) -> Result<bool> {
+        self.db().exists(name)
+    }
+
+    /// Compact the files of all databases, e.g. to reclaim space after many deletes.
+    ///
+    /// See [`Database::compact_range`] to compact a single database, where supported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use okv::{Env, backend::memory::MemDB};
+    /// let env = Env::new(MemDB::new());
+    /// env.compact().unwrap();
+    /// assert!(env.check_integrity().unwrap());
+    /// ```
+    pub fn compact(&self) -> Result<()> {
+        self.db().compact()
+    }
+
+    /// Check the files of all databases for corruption.
+    ///
+    /// Returns `true` if no problems were found and `false` if problems were found and
+    /// repaired by the backend. Problems that can't be repaired are returned as errors.
+    pub fn check_integrity(&self) -> Result<bool> {
+        self.db().check_integrity()
+    }
+
+    // TODO: Is this useful now that RocksDB is Sync?
+    #[cfg(feature = "unstable_lasydb")]
+    /// Open or create a database lazily.
+    /// This is useful for sharing the same database across threads.
+    /// Alternatively, you can use [`Env::clone`] to share environments across threads and use [`Env::open`] to open the database.
+    pub fn open_lazy<K, V>(&self, name: &str) -> crate::db::DatabaseLazy<K, V, D> {
+        crate::db::DatabaseLazy::new(self.clone(), name)
+    }
+}
+
+impl<'a, D: DatabaseBackend> Env<D>
+where
+    D::Column: DBColumn,
+{
     /// Open or create a database.
     ///
     /// # Arguments
@@ -37,6 +122,10 @@ impl<'a, D: DatabaseBackend> Env<D> {
     /// * `V` - The value type. This must implement [`crate::traits::BytesEncode`].
     /// * `B` - The value type. This must implement [`crate::traits::BytesEncode`], [`crate::traits::BytesDecode`] and [`crate::traits::BytesDecodeOwned`].
     ///
+    /// With a [schema registry](crate::schema), the codecs are recorded when the database
+    /// is opened for the first time, and [`Error::SchemaMismatch`] is returned if they don't
+    /// match later.
+    ///
     /// # Examples
     ///
     /// ```
@@ -45,27 +134,208 @@ impl<'a, D: DatabaseBackend> Env<D> {
     /// let env = Env::new(backend);
     /// let mut db = env.open::<&str, &str>("test").unwrap();
     /// ```
-    pub fn open<K, V>(&'a self, name: &str) -> Result<Database<K, V, D>> {
+    pub fn open<K: CodecId, V: CodecId>(&'a self, name: &str) -> Result<Database<K, V, D>> {
+        self.check_schema::<K, V>(name)?;
         Database::new(self.clone(), name)
     }
 
+    /// Open or create a database with backend specific options, see
+    /// [`DatabaseBackendOptions`].
+    ///
+    /// The options are only used when the database is created, an existing database keeps
+    /// the options it was created with. The schema registry is checked like in [`Env::open`].
+    pub fn open_with<K: CodecId, V: CodecId>(
+        &'a self,
+        name: &str,
+        options: D::ColumnOptions,
+    ) -> Result<Database<K, V, D>>
+    where
+        D: DatabaseBackendOptions,
+    {
+        self.check_schema::<K, V>(name)?;
+        let column = D::create_or_open_with(self.clone(), name, options)?;
+        Ok(Database::with_column(self.clone(), name, column))
+    }
+
     // TODO: Is this useful now that Database doesn't have a lifetime?
     /// Same as [`Env::open`] but you can specify the type of the key and value using a tuple.
     /// This is useful when you want to reuse the same type for multiple databases.
-    pub fn open_tupel<T: DatabaseType>(
-        &'a self,
-        name: &str,
-    ) -> Result<Database<T::Key, T::Val, D>> {
-        Database::new(self.clone(), name)
+    pub fn open_tupel<T: DatabaseType>(&'a self, name: &str) -> Result<Database<T::Key, T::Val, D>>
+    where
+        T::Key: CodecId,
+        T::Val: CodecId,
+    {
+        self.open(name)
     }
 
-    // TODO: Is this useful now that RocksDB is Sync?
-    #[cfg(feature = "unstable_lasydb")]
-    /// Open or create a database lazily.
-    /// This is useful for sharing the same database across threads.
-    /// Alternatively, you can use [`Env::clone`] to share environments across threads and use [`Env::open`] to open the database.
-    pub fn open_lazy<K, V>(&'a self, name: &str) -> crate::db::DatabaseLazy<K, V, D> {
-        crate::db::DatabaseLazy::new(self.clone(), name)
+    /// Delete a database and all of its entries.
+    ///
+    /// Returns [`Error::DatabaseNotFound`] if the database doesn't exist.
+    /// Databases opened before must not be used afterwards.
+    pub fn drop_database(&self, name: &str) -> Result<()> {
+        if !self.exists(name)? {
+            return Err(Error::DatabaseNotFound { db: name.into() });
+        }
+        self.db().drop_database(name)?;
+        match self.registry()? {
+            Some(registry) => registry.remove(name),
+            None => Ok(()),
+        }
+    }
+
+    /// Rename a database.
+    ///
+    /// Returns [`Error::DatabaseNotFound`] if `old` doesn't exist and [`Error::DatabaseExists`]
+    /// if `new` already exists. Databases opened before must not be used afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use okv::{Env, backend::memory::MemDB};
+    /// let env = Env::new(MemDB::new());
+    /// env.open::<&str, &str>("old").unwrap().set("hello", "world").unwrap();
+    ///
+    /// env.rename_database("old", "new").unwrap();
+    /// assert!(!env.exists("old").unwrap());
+    /// let db = env.open::<&str, String>("new").unwrap();
+    /// assert_eq!(db.get("hello").unwrap(), Some("world".to_string()));
+    /// ```
+    pub fn rename_database(&self, old: &str, new: &str) -> Result<()> {
+        if !self.exists(old)? {
+            return Err(Error::DatabaseNotFound { db: old.into() });
+        }
+        if self.exists(new)? {
+            return Err(Error::DatabaseExists { db: new.into() });
+        }
+        self.db().rename_database(old, new)?;
+
+        let Some(registry) = self.registry()? else {
+            return Ok(());
+        };
+        if let Some(schema) = registry.get(old)? {
+            registry.set(new, &schema)?;
+            registry.remove(old)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the recorded schema of a database, see [`crate::schema`].
+    ///
+    /// Returns `None` if the environment has no schema registry, or the database hasn't
+    /// been opened with typed keys or values yet.
+    pub fn schema(&self, name: &str) -> Result<Option<Schema>> {
+        match self.registry()? {
+            Some(registry) => registry.get(name),
+            None => Ok(None),
+        }
+    }
+
+    /// Set the version of the recorded schema of a database.
+    ///
+    /// Returns [`Error::DatabaseNotFound`] if no schema is recorded for the database.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use okv::{Env, backend::memory::MemDB};
+    /// let env = Env::with_schema_registry(MemDB::new());
+    /// env.open::<&str, u64>("counters").unwrap();
+    /// env.set_schema_version("counters", 2).unwrap();
+    /// assert_eq!(env.schema("counters").unwrap().unwrap().version, 2);
+    /// ```
+    pub fn set_schema_version(&self, name: &str, version: u32) -> Result<()> {
+        let not_found = || Error::DatabaseNotFound { db: name.into() };
+        let registry = self.registry()?.ok_or_else(not_found)?;
+        let mut schema = registry.get(name)?.ok_or_else(not_found)?;
+        schema.version = version;
+        registry.set(name, &schema)
+    }
+
+    pub(crate) fn registry(&self) -> Result<Option<Registry<D::Column>>> {
+        match self.0.schema_registry {
+            true => Ok(Some(Registry::open(self)?)),
+            false => Ok(None),
+        }
+    }
+
+    /// Record the codecs `K` and `V` of a database in the [schema registry](crate::schema)
+    /// if it is opened for the first time, or compare them to the recorded codecs.
+    ///
+    /// Returns [`Error::SchemaMismatch`] if they don't match. Does nothing without a schema
+    /// registry. [`Env::open`] and [`Database::as_type`] call this.
+    pub fn check_schema<K: CodecId, V: CodecId>(&self, name: &str) -> Result<()> {
+        match self.registry()? {
+            Some(registry) if name != SCHEMA_DATABASE => {
+                registry.check(name, &Schema::of::<K, V>())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<D: DatabaseBackend> Env<D>
+where
+    D::Column: DBColumn + DBColumnIterator,
+{
+    /// Export all databases in the [dump format](crate::dump).
+    ///
+    /// The backend must support [`DatabaseBackend::list_databases`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use okv::{Env, backend::memory::MemDB};
+    /// let env = Env::new(MemDB::new());
+    /// let db = env.open::<&str, &str>("test").unwrap();
+    /// db.set("hello", "world").unwrap();
+    ///
+    /// let mut dump = Vec::new();
+    /// env.export(&mut dump).unwrap();
+    ///
+    /// let copy = Env::new(MemDB::new());
+    /// copy.import(dump.as_slice()).unwrap();
+    /// let db = copy.open::<&str, String>("test").unwrap();
+    /// assert_eq!(db.get("hello").unwrap(), Some("world".to_string()));
+    /// ```
+    pub fn export(&self, writer: impl Write) -> Result<DumpStats> {
+        let mut dump = DumpWriter::new(writer)?;
+        for name in self.databases()? {
+            let column = D::create_or_open(self.clone(), &name)?;
+            dump.write_column(&name, &column)?;
+        }
+        dump.finish()
+    }
+}
+
+impl<D: DatabaseBackend> Env<D>
+where
+    D::Column: DBColumnBatch,
+{
+    /// Import all databases of a dump, creating them if they don't exist.
+    ///
+    /// Existing keys are overwritten. Entries are written in batches while the dump is
+    /// read, so databases may be partially imported if an error is returned.
+    pub fn import(&self, reader: impl Read) -> Result<DumpStats> {
+        let mut dump = DumpReader::new(reader)?;
+        let mut column = None;
+        let mut batch = ImportBatch::default();
+        for item in dump.by_ref() {
+            match item? {
+                DumpItem::Database(name) => column = Some(D::create_or_open(self.clone(), &name)?),
+                DumpItem::Entry(key, val) => {
+                    // the reader guarantees that entries belong to a database
+                    if let Some(column) = &column {
+                        batch.push(column, key, val)?;
+                    }
+                }
+                DumpItem::DatabaseEnd => {
+                    if let Some(column) = column.take() {
+                        batch.write(&column)?;
+                    }
+                }
+            }
+        }
+        Ok(dump.stats())
     }
 }
 
@@ -85,4 +355,5 @@ where
 
 struct EnvInner<D: DatabaseBackend> {
     pub(crate) db: D,
+    schema_registry: bool,
 }
@@ -28,6 +28,44 @@ pub enum Error {
         db: String,
     },
 
+    /// A database by the given name already exists.
+    #[error("Database already exists: {db}")]
+    DatabaseExists {
+        /// The database that already exists.
+        db: String,
+    },
+
+    /// A database was opened with different codecs than recorded in the
+    /// [schema registry](crate::schema).
+    #[error("Schema mismatch in database {db}: recorded {stored}, opened with {requested}")]
+    SchemaMismatch {
+        /// The database that was opened.
+        db: String,
+        /// The recorded schema.
+        stored: String,
+        /// The schema of the types the database was opened with.
+        requested: String,
+    },
+
+    /// A migrated database doesn't match its source.
+    #[error("Migrated database doesn't match its source: {db}")]
+    MigrationMismatch {
+        /// The database that doesn't match.
+        db: String,
+    },
+
+    /// A migration was resumed from a checkpoint whose last copied key no longer exists in
+    /// the source.
+    #[error("Checkpoint key no longer exists in database {db}")]
+    CheckpointKeyMissing {
+        /// The database that was resumed.
+        db: String,
+    },
+
+    /// A [page](crate::db::Database::page) was requested with a limit of 0.
+    #[error("Page limit must not be 0")]
+    ZeroPageLimit,
+
     /// Database backend error.
     #[error("Database backend error: {0}")]
     DatabaseBackend(#[from] Box<dyn std::error::Error + Send + Sync>),
@@ -52,6 +90,14 @@ pub enum DecodeError {
     #[error("Size mismatch")]
     SizeMismatch,
 
+    /// A page [cursor](crate::db::Cursor) that is not valid hex.
+    #[error("Invalid cursor")]
+    InvalidCursor,
+
+    /// A versioned value has a version that is not part of the migration chain.
+    #[error("Unknown version: {0}")]
+    UnknownVersion(u32),
+
     /// [`serde_json::Error`]
     #[cfg(feature = "serde_json")]
     #[error("Serde JSON error: {0}")]
@@ -66,6 +112,36 @@ pub enum DecodeError {
     #[cfg(feature = "uuid")]
     #[error("UUID error: {0}")]
     Uuid(#[from] uuid::Error),
+
+    /// [`bincode::Error`]
+    #[cfg(feature = "bincode")]
+    #[error("Bincode error: {0}")]
+    Bincode(#[from] bincode::Error),
+
+    /// [`postcard::Error`]
+    #[cfg(feature = "postcard")]
+    #[error("Postcard error: {0}")]
+    Postcard(#[from] postcard::Error),
+
+    /// [`ciborium::de::Error`]
+    #[cfg(feature = "ciborium")]
+    #[error("CBOR error: {0}")]
+    Cbor(#[from] ciborium::de::Error<std::io::Error>),
+
+    /// [`prost::DecodeError`]
+    #[cfg(feature = "prost")]
+    #[error("Protobuf error: {0}")]
+    Prost(#[from] prost::DecodeError),
+
+    /// An archive that failed validation or deserialization with rkyv.
+    #[cfg(feature = "rkyv")]
+    #[error("rkyv error: {0}")]
+    Rkyv(String),
+
+    /// An error of a compression algorithm.
+    #[cfg(feature = "compression")]
+    #[error("Compression error: {0}")]
+    Compression(String),
 }
 
 /// An error that can occur when encoding a value.
@@ -84,4 +160,29 @@ pub enum EncodeError {
     #[cfg(feature = "rmp-serde")]
     #[error("Serde RMP error: {0}")]
     SerdeRmp(#[from] rmp_serde::encode::Error),
+
+    /// [`bincode::Error`]
+    #[cfg(feature = "bincode")]
+    #[error("Bincode error: {0}")]
+    Bincode(#[from] bincode::Error),
+
+    /// [`postcard::Error`]
+    #[cfg(feature = "postcard")]
+    #[error("Postcard error: {0}")]
+    Postcard(#[from] postcard::Error),
+
+    /// [`ciborium::ser::Error`]
+    #[cfg(feature = "ciborium")]
+    #[error("CBOR error: {0}")]
+    Cbor(#[from] ciborium::ser::Error<std::io::Error>),
+
+    /// An error of the rkyv serializer.
+    #[cfg(feature = "rkyv")]
+    #[error("rkyv error: {0}")]
+    Rkyv(String),
+
+    /// An error of a compression algorithm.
+    #[cfg(feature = "compression")]
+    #[error("Compression error: {0}")]
+    Compression(String),
 }
@@ -1,8 +1,11 @@
 pub mod backend;
 pub mod backend_async;
 pub mod db;
+pub mod dump;
 pub mod env;
 pub mod error;
+pub mod migrate;
+pub mod schema;
 pub mod traits;
 pub mod traits_async;
 pub mod types;
@@ -0,0 +1,238 @@
+//! Copy databases between environments, e.g. to move data to a different backend.
+//!
+//! See [`migrate`] for details.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    backend::{DBColumnBatch, DBColumnIterator, DBColumnIteratorRange, DatabaseBackend, KeyRange},
+    env::Env,
+    error::{Error, Result},
+};
+
+/// The progress of a migration, stored after every batch to resume an interrupted migration.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Checkpoint {
+    /// The progress of every database that has been started.
+    pub databases: BTreeMap<String, DatabaseCheckpoint>,
+}
+
+impl Checkpoint {
+    /// Returns `true` if the database has been copied completely.
+    pub fn is_complete(&self, db: &str) -> bool {
+        self.databases.get(db).is_some_and(|db| db.complete)
+    }
+}
+
+/// The progress of a single database in a migration.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DatabaseCheckpoint {
+    /// The last key that was copied.
+    pub last_key: Option<Vec<u8>>,
+    /// The number of entries copied so far.
+    pub entries: u64,
+    /// Whether the database has been copied (and verified) completely.
+    pub complete: bool,
+}
+
+/// The progress reported to [`MigrateOptions::progress`].
+#[derive(Debug)]
+pub struct MigrateProgress<'a> {
+    /// The database that is being copied.
+    pub database: &'a str,
+    /// The number of entries of this database copied so far.
+    pub entries: u64,
+    /// Whether the database has been copied completely.
+    pub complete: bool,
+    /// The checkpoint of the whole migration, store it to resume the migration later.
+    pub checkpoint: &'a Checkpoint,
+}
+
+/// A callback for the progress of a [`migrate`].
+pub type ProgressCallback<'a> = Box<dyn FnMut(&MigrateProgress<'_>) + 'a>;
+
+/// Options for [`migrate`].
+pub struct MigrateOptions<'a> {
+    /// The number of entries copied between two checkpoints. Defaults to 1000.
+    pub batch_size: usize,
+
+    /// The databases to copy. Defaults to `None`, which copies all databases of the source.
+    pub databases: Option<Vec<String>>,
+
+    /// The checkpoint of a previous migration to resume. Defaults to an empty checkpoint.
+    pub checkpoint: Checkpoint,
+
+    /// Compare the number of entries and a checksum of every database after copying it.
+    /// Defaults to `true`.
+    pub verify: bool,
+
+    /// Called after every batch and after every database. Defaults to `None`.
+    pub progress: Option<ProgressCallback<'a>>,
+}
+
+impl Default for MigrateOptions<'_> {
+    fn default() -> Self {
+        Self {
+            batch_size: 1000,
+            databases: None,
+            checkpoint: Checkpoint::default(),
+            verify: true,
+            progress: None,
+        }
+    }
+}
+
+/// The result of a [`migrate`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MigrateStats {
+    /// The number of databases copied, excluding databases completed in a previous run.
+    pub databases: u32,
+    /// The number of entries copied in this run.
+    pub entries: u64,
+}
+
+/// Copy all databases from one environment to another.
+///
+/// Databases are created in the target if they don't exist, and existing keys are overwritten.
+/// Without [`MigrateOptions::databases`], the source backend must support
+/// [`Env::databases`].
+///
+/// Entries are copied in batches of [`MigrateOptions::batch_size`], every batch is written
+/// with a single write batch or transaction of the target backend. After each batch the
+/// [checkpoint](MigrateProgress::checkpoint) is updated and passed to the progress callback.
+/// Passing a stored checkpoint as [`MigrateOptions::checkpoint`] skips completed databases
+/// and continues after the last copied key. The source is read in key order, so resuming
+/// seeks to that key instead of reading the copied entries again. If the key has been
+/// deleted from the source since, [`Error::CheckpointKeyMissing`] is returned.
+///
+/// With [`MigrateOptions::verify`], the number of entries and an order independent checksum
+/// of every database are compared after copying it, and [`Error::MigrationMismatch`] is
+/// returned if they differ. The source must not be modified during the migration, and the
+/// target database must not contain other keys for the verification to succeed.
+///
+/// # Examples
+///
+/// ```
+/// use okv::{Env, backend::memory::MemDB, migrate::MigrateOptions};
+/// let src = Env::new(MemDB::new());
+/// src.open::<&str, &str>("test").unwrap().set("hello", "world").unwrap();
+///
+/// let dst = Env::new(MemDB::new());
+/// let stats = okv::migrate(&src, &dst, MigrateOptions::default()).unwrap();
+/// assert_eq!(stats.entries, 1);
+/// ```
+pub fn migrate<S, T>(
+    src: &Env<S>,
+    dst: &Env<T>,
+    options: MigrateOptions<'_>,
+) -> Result<MigrateStats>
+where
+    S: DatabaseBackend,
+    S::Column: DBColumnIteratorRange,
+    T: DatabaseBackend,
+    T::Column: DBColumnBatch + DBColumnIterator,
+{
+    let MigrateOptions {
+        batch_size,
+        databases,
+        mut checkpoint,
+        verify,
+        mut progress,
+    } = options;
+    let batch_size = batch_size.max(1);
+
+    let databases = match databases {
+        Some(databases) => databases,
+        None => src.databases()?,
+    };
+
+    let mut stats = MigrateStats::default();
+    for name in databases {
+        if checkpoint.is_complete(&name) {
+            continue;
+        }
+
+        let source = S::create_or_open(src.clone(), &name)?;
+        let target = T::create_or_open(dst.clone(), &name)?;
+        let mut state = checkpoint.databases.get(&name).cloned().unwrap_or_default();
+
+        let range = KeyRange {
+            start: state.last_key.clone().unwrap_or_default(),
+            end: None,
+        };
+        let mut iter = source.iter_range(range)?.peekable();
+        if let Some(last_key) = &state.last_key {
+            // the range starts with the last copied key, which must still exist
+            match iter.next().transpose()? {
+                Some((key, _)) if key == *last_key => {}
+                _ => return Err(Error::CheckpointKeyMissing { db: name.clone() }),
+            }
+        }
+
+        let mut report = |state: &DatabaseCheckpoint| {
+            checkpoint.databases.insert(name.clone(), state.clone());
+            if let Some(progress) = &mut progress {
+                progress(&MigrateProgress {
+                    database: &name,
+                    entries: state.entries,
+                    complete: state.complete,
+                    checkpoint: &checkpoint,
+                });
+            }
+        };
+
+        let mut batch = Vec::with_capacity(batch_size);
+        while let Some(item) = iter.next() {
+            batch.push(item?);
+            if batch.len() < batch_size && iter.peek().is_some() {
+                continue;
+            }
+
+            let entries = batch.len() as u64;
+            state.last_key = batch.last().map(|(key, _)| key.clone());
+            target.set_multi(batch.drain(..))?;
+            state.entries += entries;
+            stats.entries += entries;
+            report(&state);
+        }
+
+        if verify && Summary::of(&source)? != Summary::of(&target)? {
+            return Err(Error::MigrationMismatch { db: name.clone() });
+        }
+
+        state.complete = true;
+        report(&state);
+        stats.databases += 1;
+    }
+
+    Ok(stats)
+}
+
+/// The number of entries and an order independent checksum of a database.
+#[derive(PartialEq, Eq)]
+struct Summary {
+    entries: u64,
+    checksum: u64,
+}
+
+impl Summary {
+    fn of(column: &impl DBColumnIterator) -> Result<Self> {
+        let mut summary = Self {
+            entries: 0,
+            checksum: 0,
+        };
+        for item in column.iter()? {
+            let (key, val) = item?;
+            let mut hasher = crc32fast::Hasher::new();
+            hasher.update(&(key.len() as u64).to_le_bytes());
+            hasher.update(&key);
+            hasher.update(&val);
+
+            summary.entries += 1;
+            summary.checksum = summary.checksum.wrapping_add(u64::from(hasher.finalize()));
+        }
+        Ok(summary)
+    }
+}
@@ -0,0 +1,158 @@
+//! A registry of the key and value codecs of every database.
+//!
+//! Opening a database with different types than it was written with silently decodes
+//! garbage. Environments created with [`Env::with_schema_registry`] record the
+//! [codec identifiers](CodecId) of every database the first time it is opened with
+//! [`Env::open`], which returns [`Error::SchemaMismatch`] if they don't match afterwards.
+//! [`Database::as_type`](crate::db::Database::as_type) and [`Env::check_schema`] check the
+//! registry the same way.
+//!
+//! Only codecs that implement [`CodecId`] can be opened. Codecs that are generic over the
+//! type they encode, like [`SerdeJson`](crate::types::serde::SerdeJson), need the type to
+//! implement [`TypeName`](crate::traits::TypeName).
+//!
+//! Schemas are stored in the database [`SCHEMA_DATABASE`], which is listed, exported and
+//! migrated like any other database. Raw bytes (`&[u8]`, `Vec<u8>`) match every codec, so
+//! databases can always be opened as bytes.
+//!
+//! # Examples
+//!
+//! ```
+//! use okv::{Env, Error, backend::memory::MemDB};
+//! let env = Env::with_schema_registry(MemDB::new());
+//! env.open::<&str, u64>("counters").unwrap();
+//! env.open::<String, u64>("counters").unwrap();
+//!
+//! let res = env.open::<&str, &str>("counters");
+//! assert!(matches!(res, Err(Error::SchemaMismatch { .. })));
+//! ```
+
+use std::fmt;
+
+use byteorder::{LittleEndian, ReadBytesExt};
+
+use crate::{
+    backend::{DBColumn, DatabaseBackend},
+    env::Env,
+    error::{DecodeError, Error, Result},
+    traits::CodecId,
+};
+
+/// The name of the database that stores the schemas.
+pub const SCHEMA_DATABASE: &str = "__okv_schema";
+
+/// The codec identifier of raw bytes, which matches every codec.
+pub const RAW_CODEC: &str = "bytes";
+
+/// The key and value codecs of a database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schema {
+    /// The [codec identifier](CodecId) of the keys.
+    pub key: String,
+    /// The [codec identifier](CodecId) of the values.
+    pub value: String,
+    /// A version managed by the application, `0` when the schema is recorded.
+    pub version: u32,
+}
+
+impl Schema {
+    /// The schema of a database with keys of type `K` and values of type `V`.
+    pub fn of<K: CodecId, V: CodecId>() -> Self {
+        Self {
+            key: K::codec_id().into_owned(),
+            value: V::codec_id().into_owned(),
+            version: 0,
+        }
+    }
+
+    /// Returns `true` if the codecs of both schemas can read each others values.
+    /// The version is not compared.
+    pub fn matches(&self, other: &Schema) -> bool {
+        let codec = |a: &str, b: &str| a == b || a == RAW_CODEC || b == RAW_CODEC;
+        codec(&self.key, &other.key) && codec(&self.value, &other.value)
+    }
+
+    fn is_raw(&self) -> bool {
+        self.key == RAW_CODEC && self.value == RAW_CODEC
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = self.version.to_le_bytes().to_vec();
+        for codec in [&self.key, &self.value] {
+            buf.extend_from_slice(&(codec.len() as u32).to_le_bytes());
+            buf.extend_from_slice(codec.as_bytes());
+        }
+        buf
+    }
+
+    fn decode(mut bytes: &[u8]) -> Result<Self, DecodeError> {
+        fn string(bytes: &mut &[u8]) -> Result<String, DecodeError> {
+            let len = bytes.read_u32::<LittleEndian>()? as usize;
+            if len > bytes.len() {
+                return Err(DecodeError::SizeMismatch);
+            }
+            let (string, rest) = bytes.split_at(len);
+            *bytes = rest;
+            String::from_utf8(string.to_vec()).map_err(|_| DecodeError::InvalidUtf8)
+        }
+
+        Ok(Self {
+            version: bytes.read_u32::<LittleEndian>()?,
+            key: string(&mut bytes)?,
+            value: string(&mut bytes)?,
+        })
+    }
+}
+
+impl fmt::Display for Schema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "key {}, value {}", self.key, self.value)
+    }
+}
+
+/// Access to the registry of an environment.
+pub(crate) struct Registry<C>(C);
+
+impl<C: DBColumn> Registry<C> {
+    pub(crate) fn open<D>(env: &Env<D>) -> Result<Self>
+    where
+        D: DatabaseBackend<Column = C>,
+    {
+        Ok(Self(D::create_or_open(env.clone(), SCHEMA_DATABASE)?))
+    }
+
+    pub(crate) fn get(&self, db: &str) -> Result<Option<Schema>> {
+        match self.0.get(db)? {
+            Some(bytes) => Ok(Some(Schema::decode(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub(crate) fn set(&self, db: &str, schema: &Schema) -> Result<()> {
+        self.0.set(db, schema.encode())
+    }
+
+    pub(crate) fn remove(&self, db: &str) -> Result<()> {
+        self.0.delete(db)
+    }
+
+    /// Record the schema of a database opened for the first time, or compare it to the
+    /// recorded schema.
+    pub(crate) fn check(&self, db: &str, schema: &Schema) -> Result<()> {
+        if schema.is_raw() {
+            return Ok(());
+        }
+        if self.0.set_nx(db, schema.encode())? {
+            return Ok(());
+        }
+
+        match self.get(db)? {
+            Some(stored) if !stored.matches(schema) => Err(Error::SchemaMismatch {
+                db: db.to_string(),
+                stored: stored.to_string(),
+                requested: schema.to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, ops::Range};
 
 use crate::error::{DecodeError, EncodeError, Result};
 use crate::types::RefValue;
@@ -49,6 +49,61 @@ pub trait BytesDecodeOwned {
     fn bytes_decode_owned(bytes: &[u8]) -> Result<Self::DItem, DecodeError>;
 }
 
+/// Identifies the format of encoded bytes, used by the [schema registry](crate::schema).
+///
+/// Types that read and write the same bytes share an identifier, e.g. `&str` and `String`
+/// are both `utf8`.
+pub trait CodecId {
+    /// The identifier of the format.
+    fn codec_id() -> Cow<'static, str>;
+}
+
+/// A stable name of a type, used in the [`CodecId`] of codecs that are generic over the
+/// type they encode, like [`SerdeJson`](crate::types::serde::SerdeJson).
+///
+/// The name is part of the codec identifier, so values of different types don't share an
+/// identifier. It is stored in the [schema registry](crate::schema), so it must not change
+/// when the type is renamed or moved to another module.
+///
+/// # Examples
+///
+/// ```
+/// use okv::TypeName;
+///
+/// struct User {
+///     name: String,
+/// }
+///
+/// impl TypeName for User {
+///     const TYPE_NAME: &'static str = "user";
+/// }
+/// ```
+pub trait TypeName {
+    /// The name of the type.
+    const TYPE_NAME: &'static str;
+}
+
+macro_rules! impl_type_name {
+    ($($ty:ty => $name:literal),* $(,)?) => {
+        $(impl TypeName for $ty {
+            const TYPE_NAME: &'static str = $name;
+        })*
+    };
+}
+
+// scalar types that can be stored on their own with serde or prost
+impl_type_name! {
+    bool => "bool",
+    u32 => "u32",
+    u64 => "u64",
+    i32 => "i32",
+    i64 => "i64",
+    f32 => "f32",
+    f64 => "f64",
+    String => "string",
+    Vec<u8> => "bytes",
+}
+
 /// A trait that represents a common database interface.
 pub trait DBCommon<Key, Val> {
     /// Set a key to a value in the database.
@@ -144,6 +199,31 @@ pub trait DBCommonDelete {
     fn delete_db(self) -> Result<()>;
 }
 
+/// A database that supports deleting ranges of keys.
+pub trait DBCommonDeleteRange<Key> {
+    /// Delete the keys from the bytes `start` (inclusive) to `end` (exclusive), or up to the
+    /// last key if `end` is `None`.
+    /// Returns the number of deleted keys if the backend can report it.
+    fn delete_range_raw(&self, start: &[u8], end: Option<&[u8]>) -> Result<Option<u64>>;
+
+    /// Delete the keys in `range`.
+    /// Keys are compared by their encoded bytes, so this only works as expected with key
+    /// types whose encoding keeps their order, like strings or big-endian integers.
+    /// Returns the number of deleted keys if the backend can report it.
+    fn delete_range<'k>(&self, range: Range<&'k Key::EItem>) -> Result<Option<u64>>
+    where
+        Key: BytesEncode<'k>,
+    {
+        let start = Key::bytes_encode(range.start)?;
+        let end = Key::bytes_encode(range.end)?;
+        self.delete_range_raw(&start, Some(&end))
+    }
+
+    /// Delete the keys that start with the bytes `prefix`.
+    /// Returns the number of deleted keys if the backend can report it.
+    fn delete_prefix(&self, prefix: impl AsRef<[u8]>) -> Result<Option<u64>>;
+}
+
 /// A database that can return references.
 pub trait DBCommonRef<'c, Key, Val, Ref>
 where
@@ -152,7 +232,7 @@ where
     /// Get the serialized `val` from the database by `key`.
     /// Prefer this method over `get` for efficiency when only a reference to the value is needed
     /// and your backend supports it.
-    fn get_ref<'k>(&'c self, key: &'k Key::EItem) -> Result<Option<RefValue<Ref, Val::DItem>>>
+    fn get_ref<'k>(&'c self, key: &'k Key::EItem) -> Result<Option<RefValue<Ref, Val>>>
     where
         Key: BytesEncode<'k>,
         Val: BytesDecode<'c>;
@@ -168,7 +248,7 @@ where
     /// Use this method over `get_multi` for efficiency when you only need a reference to the value
     /// and your backend supports it.
     #[allow(clippy::type_complexity)] // not that complex really
-    fn get_multi_ref<'k, I>(&'c self, keys: I) -> Result<Vec<Option<RefValue<Ref, Val::DItem>>>>
+    fn get_multi_ref<'k, I>(&'c self, keys: I) -> Result<Vec<Option<RefValue<Ref, Val>>>>
     where
         Key: BytesEncode<'k>,
         I: IntoIterator<Item = &'k Key::EItem>,
@@ -196,6 +276,35 @@ pub trait DBCommonIter<Key, Val> {
         });
         Ok(decoded_iterator)
     }
+
+    /// Get a raw iterator over the keys of the database.
+    fn keys_raw(&self) -> Result<impl Iterator<Item = Result<Vec<u8>>>> {
+        Ok(self.iter_raw()?.map(|item| item.map(|(key, _)| key)))
+    }
+
+    /// Get an iterator over the keys of the database, without decoding the values.
+    fn keys(&self) -> Result<impl Iterator<Item = Result<Key::DItem>>>
+    where
+        Key: BytesDecodeOwned,
+    {
+        let keys = self.keys_raw()?;
+        Ok(keys.map(|key| Ok(Key::bytes_decode_owned(&key?)?)))
+    }
+
+    /// Get an iterator over the values of the database, without decoding the keys.
+    fn values(&self) -> Result<impl Iterator<Item = Result<Val::DItem>>>
+    where
+        Val: BytesDecodeOwned,
+    {
+        let values = self.iter_raw()?.map(|item| item.map(|(_, val)| val));
+        Ok(values.map(|val| Ok(Val::bytes_decode_owned(&val?)?)))
+    }
+
+    /// Count the entries of the database.
+    fn count(&self) -> Result<u64> {
+        self.keys_raw()?
+            .try_fold(0, |count, key| key.map(|_| count + 1))
+    }
 }
 
 /// A database that supports iterators over a prefix.
@@ -228,4 +337,33 @@ pub trait DBCommonIterPrefix<'c, Key, Val> {
 
         Ok(decoded_iterator)
     }
+
+    /// Get a raw iterator over the keys that start with the given byte prefix.
+    fn keys_prefix_raw(
+        &'c self,
+        prefix: impl AsRef<[u8]>,
+    ) -> Result<impl Iterator<Item = Result<Vec<u8>>>> {
+        Ok(self
+            .iter_prefix_raw(prefix)?
+            .map(|item| item.map(|(key, _)| key)))
+    }
+
+    /// Get an iterator over the keys that start with `prefix`, without decoding the values.
+    fn keys_prefix<'k, Prefix>(
+        &'c self,
+        prefix: &'k Prefix::EItem,
+    ) -> Result<impl Iterator<Item = Result<Key::DItem>>>
+    where
+        Key: BytesDecodeOwned,
+        Prefix: BytesEncode<'k>,
+    {
+        let keys = self.keys_prefix_raw(Prefix::bytes_encode(prefix)?)?;
+        Ok(keys.map(|key| Ok(Key::bytes_decode_owned(&key?)?)))
+    }
+
+    /// Count the entries whose key starts with the given byte prefix.
+    fn count_prefix(&'c self, prefix: impl AsRef<[u8]>) -> Result<u64> {
+        self.keys_prefix_raw(prefix)?
+            .try_fold(0, |count, key| key.map(|_| count + 1))
+    }
 }
@@ -2,7 +2,7 @@ use std::borrow::Cow;
 
 use crate::{
     error::{DecodeError, EncodeError},
-    traits::{BytesDecode, BytesDecodeOwned, BytesEncode},
+    traits::{BytesDecode, BytesDecodeOwned, BytesEncode, CodecId},
 };
 
 impl BytesEncode<'_> for &[u8] {
@@ -60,3 +60,22 @@ impl BytesDecodeOwned for Vec<u8> {
         Ok(bytes.to_vec())
     }
 }
+
+/// Raw bytes can be used to open any database, see [`crate::schema`].
+impl CodecId for &[u8] {
+    fn codec_id() -> Cow<'static, str> {
+        Cow::Borrowed(crate::schema::RAW_CODEC)
+    }
+}
+
+impl CodecId for Vec<u8> {
+    fn codec_id() -> Cow<'static, str> {
+        Cow::Borrowed(crate::schema::RAW_CODEC)
+    }
+}
+
+impl<const L: usize> CodecId for [u8; L] {
+    fn codec_id() -> Cow<'static, str> {
+        Cow::Borrowed(crate::schema::RAW_CODEC)
+    }
+}
@@ -0,0 +1,287 @@
+use std::borrow::Cow;
+
+use crate::{
+    error::{DecodeError, EncodeError},
+    traits::{BytesDecode, BytesDecodeOwned, BytesEncode, CodecId},
+};
+
+/// Magic bytes in front of the header byte. `0xff` never occurs in UTF-8, and binary values
+/// start with them with a probability of 2^-32.
+const MAGIC: &[u8; 4] = b"\xffOKC";
+/// Header of values that were not compressed because compressing didn't make them smaller.
+const STORED: u8 = 0;
+/// Header of values compressed with [`Zstd`].
+const ZSTD: u8 = 1;
+/// Header of values compressed with [`Lz4`].
+const LZ4: u8 = 2;
+/// Header of values compressed with [`Snappy`].
+const SNAPPY: u8 = 3;
+/// Header of values compressed with [`ZstdDict`].
+const ZSTD_DICT: u8 = 4;
+
+/// A compression algorithm used by [`Compressed`].
+pub trait Compression {
+    /// The header byte written after the magic bytes in front of values compressed with
+    /// this algorithm.
+    const HEADER: u8;
+
+    /// Compress the given bytes.
+    fn compress(bytes: &[u8]) -> Result<Vec<u8>, EncodeError>;
+
+    /// Decompress bytes that were compressed with [`Compression::compress`].
+    fn decompress(bytes: &[u8]) -> Result<Vec<u8>, DecodeError>;
+}
+
+/// Zstandard compression at the default level (requires `zstd` feature).
+#[cfg(feature = "zstd")]
+pub struct Zstd;
+
+#[cfg(feature = "zstd")]
+impl Compression for Zstd {
+    const HEADER: u8 = ZSTD;
+
+    fn compress(bytes: &[u8]) -> Result<Vec<u8>, EncodeError> {
+        Ok(zstd::bulk::compress(
+            bytes,
+            zstd::DEFAULT_COMPRESSION_LEVEL,
+        )?)
+    }
+
+    fn decompress(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+        Ok(zstd::stream::decode_all(bytes)?)
+    }
+}
+
+/// A zstd dictionary, see [`train_zstd_dictionary`].
+///
+/// # Examples
+///
+/// ```
+/// use okv::types::compressed::{Compressed, ZstdDict, ZstdDictionary};
+/// use okv::types::serde::SerdeJson;
+///
+/// struct Users;
+///
+/// impl ZstdDictionary for Users {
+///     fn dictionary() -> &'static [u8] {
+///         // e.g. include_bytes!("users.dict")
+///         b"{\"name\":\"\",\"email\":\"@example.com\"}"
+///     }
+/// }
+///
+/// type User = Compressed<SerdeJson<serde_json::Value>, ZstdDict<Users>>;
+/// ```
+#[cfg(feature = "zstd")]
+pub trait ZstdDictionary {
+    /// The compression level.
+    const LEVEL: i32 = zstd::DEFAULT_COMPRESSION_LEVEL;
+
+    /// The dictionary, it must not change once values were written with it.
+    fn dictionary() -> &'static [u8];
+}
+
+/// Zstandard compression with a dictionary (requires `zstd` feature).
+///
+/// Dictionaries improve the compression of small values that share a structure, like
+/// JSON objects of the same type.
+#[cfg(feature = "zstd")]
+pub struct ZstdDict<D>(std::marker::PhantomData<D>);
+
+#[cfg(feature = "zstd")]
+impl<D: ZstdDictionary> Compression for ZstdDict<D> {
+    const HEADER: u8 = ZSTD_DICT;
+
+    fn compress(bytes: &[u8]) -> Result<Vec<u8>, EncodeError> {
+        let mut compressor = zstd::bulk::Compressor::with_dictionary(D::LEVEL, D::dictionary())?;
+        Ok(compressor.compress(bytes)?)
+    }
+
+    fn decompress(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+        let mut decoder = zstd::stream::Decoder::with_dictionary(
+            std::io::BufReader::new(bytes),
+            D::dictionary(),
+        )?;
+        let mut buf = Vec::new();
+        std::io::Read::read_to_end(&mut decoder, &mut buf)?;
+        Ok(buf)
+    }
+}
+
+/// Train a zstd dictionary of at most `max_size` bytes on sample values, e.g. values
+/// read with [`Database::iter_raw`](crate::db::Database::iter_raw).
+///
+/// Zstd needs a few hundred samples to train a useful dictionary, around 100 times the
+/// size of the dictionary in total.
+#[cfg(feature = "zstd")]
+pub fn train_zstd_dictionary<S: AsRef<[u8]>>(
+    samples: &[S],
+    max_size: usize,
+) -> Result<Vec<u8>, EncodeError> {
+    Ok(zstd::dict::from_samples(samples, max_size)?)
+}
+
+/// LZ4 compression, fast with a lower ratio (requires `lz4` feature).
+#[cfg(feature = "lz4")]
+pub struct Lz4;
+
+#[cfg(feature = "lz4")]
+impl Compression for Lz4 {
+    const HEADER: u8 = LZ4;
+
+    fn compress(bytes: &[u8]) -> Result<Vec<u8>, EncodeError> {
+        Ok(lz4_flex::compress_prepend_size(bytes))
+    }
+
+    fn decompress(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+        lz4_flex::decompress_size_prepended(bytes)
+            .map_err(|e| DecodeError::Compression(e.to_string()))
+    }
+}
+
+/// Snappy compression, fast with a lower ratio (requires `snappy` feature).
+#[cfg(feature = "snappy")]
+pub struct Snappy;
+
+#[cfg(feature = "snappy")]
+impl Compression for Snappy {
+    const HEADER: u8 = SNAPPY;
+
+    fn compress(bytes: &[u8]) -> Result<Vec<u8>, EncodeError> {
+        snap::raw::Encoder::new()
+            .compress_vec(bytes)
+            .map_err(|e| EncodeError::Compression(e.to_string()))
+    }
+
+    fn decompress(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+        snap::raw::Decoder::new()
+            .decompress_vec(bytes)
+            .map_err(|e| DecodeError::Compression(e.to_string()))
+    }
+}
+
+/// Wraps a codec like [`SerdeJson`](crate::types::serde::SerdeJson) and compresses its
+/// values with the algorithm `A`.
+///
+/// Every value starts with a header of the magic bytes `ff 4f 4b 43` and a byte identifying
+/// the algorithm, values are stored uncompressed when compressing doesn't make them smaller.
+/// Values are decoded with the algorithm of their header, so the algorithm can be changed
+/// later. Values that don't start with the magic bytes are passed to the inner codec as is,
+/// so existing databases can be opened compressed. UTF-8 text like JSON never starts with
+/// them, and values of binary formats only with a probability of 2^-32.
+///
+/// The [codec identifier](CodecId) is the one of the inner codec.
+///
+/// # Examples
+///
+/// ```
+/// use okv::{Env, backend::memory::MemDB};
+/// use okv::types::compressed::{Compressed, Zstd};
+/// use okv::types::serde::SerdeJson;
+///
+/// let env = Env::new(MemDB::new());
+/// let db = env.open::<&str, Compressed<SerdeJson<String>, Zstd>>("logs").unwrap();
+///
+/// let log = "GET /index.html 200\n".repeat(100);
+/// db.set("2024-01-01", &log).unwrap();
+/// assert_eq!(db.get("2024-01-01").unwrap(), Some(log));
+/// assert!(db.get_raw("2024-01-01").unwrap().unwrap().len() < 100);
+///
+/// // binary values written without compression are read as is
+/// let db = env.open::<&str, Compressed<Vec<u8>, Zstd>>("blobs").unwrap();
+/// db.set_raw("legacy", &[0xf6, 0x01, 0x02]).unwrap();
+/// assert_eq!(db.get("legacy").unwrap(), Some(vec![0xf6, 0x01, 0x02]));
+/// ```
+pub struct Compressed<C, A>(std::marker::PhantomData<(C, A)>);
+
+/// Decompress a value with the algorithm of its header.
+fn decompress<A: Compression>(bytes: &[u8]) -> Result<Cow<'_, [u8]>, DecodeError> {
+    let Some((&header, rest)) = bytes
+        .strip_prefix(MAGIC.as_slice())
+        .and_then(|rest| rest.split_first())
+    else {
+        return Ok(Cow::Borrowed(bytes));
+    };
+
+    let disabled = |feature: &str| {
+        Err(DecodeError::Compression(format!(
+            "value is compressed with {feature}, which is not enabled"
+        )))
+    };
+
+    match header {
+        header if header == A::HEADER => A::decompress(rest).map(Cow::Owned),
+        STORED => Ok(Cow::Borrowed(rest)),
+        #[cfg(feature = "zstd")]
+        ZSTD => Zstd::decompress(rest).map(Cow::Owned),
+        #[cfg(not(feature = "zstd"))]
+        ZSTD => disabled("zstd"),
+        #[cfg(feature = "lz4")]
+        LZ4 => Lz4::decompress(rest).map(Cow::Owned),
+        #[cfg(not(feature = "lz4"))]
+        LZ4 => disabled("lz4"),
+        #[cfg(feature = "snappy")]
+        SNAPPY => Snappy::decompress(rest).map(Cow::Owned),
+        #[cfg(not(feature = "snappy"))]
+        SNAPPY => disabled("snappy"),
+        // the dictionary is only known to the algorithm that uses it
+        ZSTD_DICT => disabled("a zstd dictionary"),
+        _ => Err(DecodeError::Compression(format!(
+            "unknown compression header {header:#04x}"
+        ))),
+    }
+}
+
+impl<'a, C, A> BytesEncode<'a> for Compressed<C, A>
+where
+    C: BytesEncode<'a>,
+    A: Compression,
+{
+    type EItem = C::EItem;
+
+    fn bytes_encode(item: &'a Self::EItem) -> Result<Cow<'a, [u8]>, EncodeError> {
+        let bytes = C::bytes_encode(item)?;
+        let compressed = A::compress(&bytes)?;
+
+        let mut buf = Vec::with_capacity(compressed.len().min(bytes.len()) + MAGIC.len() + 1);
+        buf.extend_from_slice(MAGIC);
+        if compressed.len() < bytes.len() {
+            buf.push(A::HEADER);
+            buf.extend_from_slice(&compressed);
+        } else {
+            buf.push(STORED);
+            buf.extend_from_slice(&bytes);
+        }
+        Ok(Cow::Owned(buf))
+    }
+}
+
+impl<'a, C, A> BytesDecode<'a> for Compressed<C, A>
+where
+    C: BytesDecodeOwned,
+    C::DItem: 'a,
+    A: Compression,
+{
+    type DItem = C::DItem;
+
+    fn bytes_decode(bytes: &'a [u8]) -> Result<Self::DItem, DecodeError> {
+        C::bytes_decode_owned(&decompress::<A>(bytes)?)
+    }
+}
+
+impl<C, A> BytesDecodeOwned for Compressed<C, A>
+where
+    C: BytesDecodeOwned,
+    A: Compression,
+{
+    type DItem = C::DItem;
+
+    fn bytes_decode_owned(bytes: &[u8]) -> Result<Self::DItem, DecodeError> {
+        C::bytes_decode_owned(&decompress::<A>(bytes)?)
+    }
+}
+
+impl<C: CodecId, A> CodecId for Compressed<C, A> {
+    fn codec_id() -> Cow<'static, str> {
+        C::codec_id()
+    }
+}
@@ -1,8 +1,8 @@
-use std::marker;
+use std::{borrow::Cow, marker};
 
 use crate::{
     error::DecodeError,
-    traits::{BytesDecode, BytesDecodeOwned},
+    traits::{BytesDecode, BytesDecodeOwned, CodecId},
 };
 
 /// Lazily decode the data bytes, it can be used to avoid CPU intensive decoding
@@ -59,3 +59,9 @@ impl<C: BytesDecodeOwned> LazyVec<C> {
         C::bytes_decode_owned(&self.data)
     }
 }
+
+impl<C: CodecId> CodecId for LazyDecode<C> {
+    fn codec_id() -> Cow<'static, str> {
+        C::codec_id()
+    }
+}
@@ -15,6 +15,18 @@ pub mod serde;
 /// Serialization for cuid2 types (requires `cuid2` feature)
 pub mod uuid;
 
+#[cfg(feature = "prost")]
+/// Serialization for protobuf messages (requires `prost` feature)
+pub mod prost;
+
+#[cfg(feature = "rkyv")]
+/// Zero-copy serialization with rkyv (requires `rkyv` feature)
+pub mod rkyv;
+
+#[cfg(feature = "compression")]
+/// Compression of values (requires `zstd`, `lz4` or `snappy` feature)
+pub mod compressed;
+
 /// A reference to a value in the database.
 /// Allows for more efficient access to the underlying bytes by returning a reference.
 /// To deserialize the value, use [`crate::types::RefValue::deserialize()`].
@@ -38,3 +50,40 @@ where
         Ok(Val::bytes_decode(&self.data)?)
     }
 }
+
+/// An entry visited by [`Database::for_each_ref`](crate::db::Database::for_each_ref).
+/// Borrows the key and value from the database, they are only decoded when requested.
+pub struct RefEntry<'a, K, V> {
+    pub(crate) key: &'a [u8],
+    pub(crate) val: &'a [u8],
+    pub(crate) marker: std::marker::PhantomData<(K, V)>,
+}
+
+impl<'a, K, V> RefEntry<'a, K, V> {
+    /// The encoded key.
+    pub fn key_bytes(&self) -> &'a [u8] {
+        self.key
+    }
+
+    /// The encoded value.
+    pub fn value_bytes(&self) -> &'a [u8] {
+        self.val
+    }
+
+    /// Decode the key, borrowing from the database where the codec allows it.
+    pub fn key(&self) -> crate::error::Result<K::DItem>
+    where
+        K: crate::traits::BytesDecode<'a>,
+    {
+        Ok(K::bytes_decode(self.key)?)
+    }
+
+    /// Decode the value, borrowing from the database where the codec allows it.
+    /// Use [`LazyDecode`](lazy::LazyDecode) to decode values only when they are needed.
+    pub fn value(&self) -> crate::error::Result<V::DItem>
+    where
+        V: crate::traits::BytesDecode<'a>,
+    {
+        Ok(V::bytes_decode(self.val)?)
+    }
+}
@@ -4,7 +4,7 @@ use std::mem::size_of;
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 
 use crate::error::{DecodeError, EncodeError};
-use crate::traits::{BytesDecode, BytesDecodeOwned, BytesEncode};
+use crate::traits::{BytesDecode, BytesDecodeOwned, BytesEncode, CodecId};
 
 impl BytesEncode<'_> for bool {
     type EItem = bool;
@@ -30,6 +30,12 @@ impl BytesDecodeOwned for bool {
     }
 }
 
+impl CodecId for bool {
+    fn codec_id() -> Cow<'static, str> {
+        Cow::Borrowed("bool")
+    }
+}
+
 impl BytesEncode<'_> for u8 {
     type EItem = u8;
 
@@ -54,6 +60,21 @@ impl BytesDecodeOwned for u8 {
     }
 }
 
+impl CodecId for u8 {
+    fn codec_id() -> Cow<'static, str> {
+        Cow::Borrowed("u8")
+    }
+}
+
+/// Integers written by older versions are twice as long, with the value after a zeroed
+/// lower half. New values never have that length, so both layouts decode.
+fn strip_legacy_padding(bytes: &[u8], size: usize) -> &[u8] {
+    match bytes.len() == 2 * size && bytes[..size].iter().all(|b| *b == 0) {
+        true => &bytes[size..],
+        false => bytes,
+    }
+}
+
 macro_rules! define_type {
     ($name:ident, $read_method:ident, $write_method:ident) => {
         #[doc = "Encodable version of [`"]
@@ -64,7 +85,7 @@ macro_rules! define_type {
             type EItem = $name;
 
             fn bytes_encode(item: &Self::EItem) -> Result<Cow<[u8]>, EncodeError> {
-                let mut buf = vec![0; size_of::<Self::EItem>()];
+                let mut buf = Vec::with_capacity(size_of::<Self::EItem>());
                 buf.$write_method::<LittleEndian>(*item)
                     .map_err(EncodeError::from)?;
                 Ok(Cow::from(buf))
@@ -74,16 +95,26 @@ macro_rules! define_type {
         impl BytesDecode<'_> for $name {
             type DItem = $name;
 
-            fn bytes_decode(mut bytes: &'_ [u8]) -> Result<Self::DItem, DecodeError> {
-                bytes.$read_method::<LittleEndian>().map_err(Into::into)
+            fn bytes_decode(bytes: &'_ [u8]) -> Result<Self::DItem, DecodeError> {
+                strip_legacy_padding(bytes, size_of::<$name>())
+                    .$read_method::<LittleEndian>()
+                    .map_err(Into::into)
             }
         }
 
         impl BytesDecodeOwned for $name {
             type DItem = $name;
 
-            fn bytes_decode_owned(mut bytes: &[u8]) -> Result<Self::DItem, DecodeError> {
-                bytes.$read_method::<LittleEndian>().map_err(Into::into)
+            fn bytes_decode_owned(bytes: &[u8]) -> Result<Self::DItem, DecodeError> {
+                strip_legacy_padding(bytes, size_of::<$name>())
+                    .$read_method::<LittleEndian>()
+                    .map_err(Into::into)
+            }
+        }
+
+        impl CodecId for $name {
+            fn codec_id() -> Cow<'static, str> {
+                Cow::Borrowed(concat!(stringify!($name), "le"))
             }
         }
     };
@@ -2,7 +2,7 @@ use std::borrow::Cow;
 
 use crate::{
     error::{DecodeError, EncodeError},
-    traits::{BytesDecode, BytesDecodeOwned, BytesEncode},
+    traits::{BytesDecode, BytesDecodeOwned, BytesEncode, CodecId},
 };
 
 impl BytesEncode<'_> for &str {
@@ -21,7 +21,7 @@ impl BytesEncode<'_> for String {
     }
 }
 
-impl<'a> BytesDecode<'a> for &'a str {
+impl<'a> BytesDecode<'a> for &str {
     type DItem = &'a str;
 
     fn bytes_decode(bytes: &'a [u8]) -> Result<Self::DItem, DecodeError> {
@@ -50,3 +50,15 @@ impl BytesDecodeOwned for String {
         }
     }
 }
+
+impl CodecId for &str {
+    fn codec_id() -> Cow<'static, str> {
+        Cow::Borrowed("utf8")
+    }
+}
+
+impl CodecId for String {
+    fn codec_id() -> Cow<'static, str> {
+        Cow::Borrowed("utf8")
+    }
+}
@@ -2,7 +2,7 @@ use std::borrow::Cow;
 
 use crate::{
     error::{DecodeError, EncodeError},
-    traits::{BytesDecode, BytesDecodeOwned, BytesEncode},
+    traits::{BytesDecode, BytesDecodeOwned, BytesEncode, CodecId},
 };
 
 impl BytesEncode<'static> for () {
@@ -34,3 +34,9 @@ impl BytesDecodeOwned for () {
             .ok_or(DecodeError::SizeMismatch)
     }
 }
+
+impl CodecId for () {
+    fn codec_id() -> Cow<'static, str> {
+        Cow::Borrowed("unit")
+    }
+}
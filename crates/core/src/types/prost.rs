@@ -0,0 +1,50 @@
+use std::borrow::Cow;
+
+use prost::Message;
+
+use crate::{
+    error::{DecodeError, EncodeError},
+    traits::{BytesDecode, BytesDecodeOwned, BytesEncode, CodecId, TypeName},
+};
+
+/// Describes a protobuf message that is encoded with `prost`.
+pub struct Prost<T>(std::marker::PhantomData<T>);
+
+impl<'a, T: 'a> BytesEncode<'a> for Prost<T>
+where
+    T: Message,
+{
+    type EItem = T;
+
+    fn bytes_encode(item: &Self::EItem) -> Result<Cow<'_, [u8]>, EncodeError> {
+        Ok(Cow::Owned(item.encode_to_vec()))
+    }
+}
+
+impl<'a, T: 'a> BytesDecode<'a> for Prost<T>
+where
+    T: Message + Default,
+{
+    type DItem = T;
+
+    fn bytes_decode(bytes: &'a [u8]) -> Result<Self::DItem, DecodeError> {
+        T::decode(bytes).map_err(Into::into)
+    }
+}
+
+impl<T> BytesDecodeOwned for Prost<T>
+where
+    T: Message + Default,
+{
+    type DItem = T;
+
+    fn bytes_decode_owned(bytes: &[u8]) -> Result<Self::DItem, DecodeError> {
+        T::decode(bytes).map_err(Into::into)
+    }
+}
+
+impl<T: TypeName> CodecId for Prost<T> {
+    fn codec_id() -> Cow<'static, str> {
+        Cow::Owned(format!("prost<{}>", T::TYPE_NAME))
+    }
+}
@@ -0,0 +1,136 @@
+use std::borrow::Cow;
+
+use rkyv::{
+    de::deserializers::SharedDeserializeMap, ser::serializers::AllocSerializer,
+    validation::validators::DefaultValidator, AlignedVec, Archive, CheckBytes, Deserialize,
+    Serialize,
+};
+
+use crate::{
+    error::{DecodeError, EncodeError},
+    traits::{BytesDecode, BytesDecodeOwned, BytesEncode, CodecId, TypeName},
+};
+
+/// The scratch space used to serialize values without allocating.
+const SCRATCH_SPACE: usize = 256;
+
+/// Describes a type that is archived with `rkyv`.
+///
+/// [`BytesDecode`] validates the archive and returns an [`ArchivedRef`] to it, use it
+/// with [`get_ref`](crate::traits::DBCommonRef::get_ref) on backends that support it. The
+/// archive is read in place if the backend returns bytes aligned to 16 bytes, otherwise
+/// it is copied into an aligned buffer first. The in-memory backend stores every value in
+/// its own allocation, which allocators usually align, but redb and LMDB return slices at
+/// any offset in their pages. [`BytesDecodeOwned`] copies
+/// the bytes and deserializes the value.
+///
+/// # Examples
+///
+/// ```
+/// use okv::types::rkyv::Rkyv;
+/// use okv_core::traits::{BytesDecode, BytesDecodeOwned, BytesEncode};
+/// use rkyv::{Archive, Deserialize, Serialize};
+///
+/// #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+/// #[archive(check_bytes)]
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// let bytes = Rkyv::<Point>::bytes_encode(&Point { x: 1, y: 2 }).unwrap();
+/// let archived = Rkyv::<Point>::bytes_decode(&bytes).unwrap();
+/// assert_eq!(archived.x, 1);
+///
+/// // unaligned bytes are copied before they are read
+/// let unaligned = [&[0][..], &bytes].concat();
+/// let archived = Rkyv::<Point>::bytes_decode(&unaligned[1..]).unwrap();
+/// assert!(!archived.is_borrowed());
+/// assert_eq!(archived.y, 2);
+///
+/// let point = Rkyv::<Point>::bytes_decode_owned(&bytes).unwrap();
+/// assert_eq!(point, Point { x: 1, y: 2 });
+/// ```
+pub struct Rkyv<T>(std::marker::PhantomData<T>);
+
+impl<'a, T: 'a> BytesEncode<'a> for Rkyv<T>
+where
+    T: Serialize<AllocSerializer<SCRATCH_SPACE>>,
+{
+    type EItem = T;
+
+    fn bytes_encode(item: &Self::EItem) -> Result<Cow<'_, [u8]>, EncodeError> {
+        let bytes = rkyv::to_bytes::<_, SCRATCH_SPACE>(item)
+            .map_err(|e| EncodeError::Rkyv(e.to_string()))?;
+        Ok(Cow::Owned(bytes.into_vec()))
+    }
+}
+
+/// A validated archive, read in place or from an aligned copy of the bytes.
+pub enum ArchivedRef<'a, T: Archive> {
+    /// The archive in the bytes returned by the backend.
+    Borrowed(&'a T::Archived),
+    /// An aligned copy of bytes that weren't aligned.
+    Owned(AlignedVec, std::marker::PhantomData<T>),
+}
+
+impl<'a, T: Archive> ArchivedRef<'a, T> {
+    /// Returns `true` if the archive is read in place.
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self, Self::Borrowed(_))
+    }
+}
+
+impl<'a, T: Archive> std::ops::Deref for ArchivedRef<'a, T> {
+    type Target = T::Archived;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Borrowed(archived) => archived,
+            // SAFETY: the copy was validated when it was created and is never modified
+            Self::Owned(bytes, _) => unsafe { rkyv::archived_root::<T>(bytes) },
+        }
+    }
+}
+
+impl<'a, T: 'a> BytesDecode<'a> for Rkyv<T>
+where
+    T: Archive,
+    for<'b> T::Archived: CheckBytes<DefaultValidator<'b>>,
+{
+    type DItem = ArchivedRef<'a, T>;
+
+    fn bytes_decode(bytes: &'a [u8]) -> Result<Self::DItem, DecodeError> {
+        if bytes.as_ptr() as usize % AlignedVec::ALIGNMENT == 0 {
+            let archived = rkyv::check_archived_root::<T>(bytes)
+                .map_err(|e| DecodeError::Rkyv(e.to_string()))?;
+            return Ok(ArchivedRef::Borrowed(archived));
+        }
+
+        let mut aligned = AlignedVec::with_capacity(bytes.len());
+        aligned.extend_from_slice(bytes);
+        rkyv::check_archived_root::<T>(&aligned).map_err(|e| DecodeError::Rkyv(e.to_string()))?;
+        Ok(ArchivedRef::Owned(aligned, std::marker::PhantomData))
+    }
+}
+
+impl<T> BytesDecodeOwned for Rkyv<T>
+where
+    T: Archive,
+    for<'a> T::Archived: CheckBytes<DefaultValidator<'a>> + Deserialize<T, SharedDeserializeMap>,
+{
+    type DItem = T;
+
+    fn bytes_decode_owned(bytes: &[u8]) -> Result<Self::DItem, DecodeError> {
+        // copy the bytes, they might not be aligned
+        let mut aligned = AlignedVec::with_capacity(bytes.len());
+        aligned.extend_from_slice(bytes);
+        rkyv::from_bytes::<T>(&aligned).map_err(|e| DecodeError::Rkyv(e.to_string()))
+    }
+}
+
+impl<T: TypeName> CodecId for Rkyv<T> {
+    fn codec_id() -> Cow<'static, str> {
+        Cow::Owned(format!("rkyv<{}>", T::TYPE_NAME))
+    }
+}
@@ -0,0 +1,71 @@
+use std::borrow::Cow;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::SerdeCodec;
+use crate::{
+    error::{DecodeError, EncodeError},
+    traits::{BytesDecode, BytesDecodeOwned, BytesEncode, CodecId, TypeName},
+};
+
+/// Describes a type that is [`Serialize`]/[`Deserialize`](serde::Deserialize) and uses `ciborium` (CBOR) to do so.
+///
+/// Values are always copied, CBOR can't borrow from the original slice.
+pub struct SerdeCbor<T>(std::marker::PhantomData<T>);
+
+fn cbor_to_vec<T: Serialize>(item: &T) -> Result<Vec<u8>, EncodeError> {
+    let mut buf = Vec::new();
+    ciborium::into_writer(item, &mut buf)?;
+    Ok(buf)
+}
+
+impl<'a, T: 'a> BytesEncode<'a> for SerdeCbor<T>
+where
+    T: Serialize,
+{
+    type EItem = T;
+
+    fn bytes_encode(item: &Self::EItem) -> Result<Cow<'_, [u8]>, EncodeError> {
+        cbor_to_vec(item).map(Cow::Owned)
+    }
+}
+
+impl<'a, T: 'a> BytesDecode<'a> for SerdeCbor<T>
+where
+    T: DeserializeOwned,
+{
+    type DItem = T;
+
+    fn bytes_decode(bytes: &'a [u8]) -> Result<Self::DItem, DecodeError> {
+        ciborium::from_reader(bytes).map_err(Into::into)
+    }
+}
+
+impl<T> BytesDecodeOwned for SerdeCbor<T>
+where
+    T: DeserializeOwned,
+{
+    type DItem = T;
+
+    fn bytes_decode_owned(bytes: &[u8]) -> Result<Self::DItem, DecodeError> {
+        ciborium::from_reader(bytes).map_err(Into::into)
+    }
+}
+
+impl<T: TypeName> CodecId for SerdeCbor<T> {
+    fn codec_id() -> Cow<'static, str> {
+        Cow::Owned(format!("cbor<{}>", T::TYPE_NAME))
+    }
+}
+
+impl<T> SerdeCodec for SerdeCbor<T> {
+    type Item = T;
+
+    fn serialize_item<U: Serialize>(item: &U) -> Result<Vec<u8>, EncodeError> {
+        cbor_to_vec(item)
+    }
+
+    fn deserialize_item<U: DeserializeOwned>(bytes: &[u8]) -> Result<U, DecodeError> {
+        ciborium::from_reader(bytes).map_err(Into::into)
+    }
+}
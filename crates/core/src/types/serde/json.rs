@@ -2,9 +2,10 @@ use std::borrow::Cow;
 
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
+use super::SerdeCodec;
 use crate::{
     error::{DecodeError, EncodeError},
-    traits::{BytesDecode, BytesDecodeOwned, BytesEncode},
+    traits::{BytesDecode, BytesDecodeOwned, BytesEncode, CodecId, TypeName},
 };
 
 /// Describes a type that is [`Serialize`]/[`Deserialize`] and uses `serde_json` to do so.
@@ -44,3 +45,21 @@ where
         serde_json::from_slice(bytes).map_err(Into::into)
     }
 }
+
+impl<T: TypeName> CodecId for SerdeJson<T> {
+    fn codec_id() -> Cow<'static, str> {
+        Cow::Owned(format!("json<{}>", T::TYPE_NAME))
+    }
+}
+
+impl<T> SerdeCodec for SerdeJson<T> {
+    type Item = T;
+
+    fn serialize_item<U: Serialize>(item: &U) -> Result<Vec<u8>, EncodeError> {
+        serde_json::to_vec(item).map_err(Into::into)
+    }
+
+    fn deserialize_item<U: DeserializeOwned>(bytes: &[u8]) -> Result<U, DecodeError> {
+        serde_json::from_slice(bytes).map_err(Into::into)
+    }
+}
@@ -1,3 +1,7 @@
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::error::{DecodeError, EncodeError};
+
 #[cfg(feature = "serde_json")]
 mod json;
 #[cfg(feature = "serde_json")]
@@ -7,3 +11,35 @@ pub use self::json::SerdeJson;
 mod rmp;
 #[cfg(feature = "rmp-serde")]
 pub use self::rmp::SerdeRmp;
+
+#[cfg(feature = "bincode")]
+mod bincode;
+#[cfg(feature = "bincode")]
+pub use self::bincode::SerdeBincode;
+
+#[cfg(feature = "postcard")]
+mod postcard;
+#[cfg(feature = "postcard")]
+pub use self::postcard::SerdePostcard;
+
+#[cfg(feature = "ciborium")]
+mod cbor;
+#[cfg(feature = "ciborium")]
+pub use self::cbor::SerdeCbor;
+
+mod versioned;
+pub(crate) use self::versioned::versioned_codec_id;
+pub use self::versioned::{Initial, Migrate, Versioned};
+
+/// A codec that can serialize any serde type, used by [`Versioned`] to decode older
+/// versions of [`SerdeCodec::Item`] with the same format.
+pub trait SerdeCodec {
+    /// The type the codec is used for.
+    type Item;
+
+    /// Serialize a value with this format.
+    fn serialize_item<T: Serialize>(item: &T) -> Result<Vec<u8>, EncodeError>;
+
+    /// Deserialize a value with this format.
+    fn deserialize_item<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, DecodeError>;
+}
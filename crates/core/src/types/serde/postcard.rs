@@ -0,0 +1,67 @@
+use std::borrow::Cow;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::SerdeCodec;
+use crate::{
+    error::{DecodeError, EncodeError},
+    traits::{BytesDecode, BytesDecodeOwned, BytesEncode, CodecId, TypeName},
+};
+
+/// Describes a type that is [`Serialize`]/[`Deserialize`] and uses `postcard` to do so.
+///
+/// It can borrow bytes from the original slice.
+pub struct SerdePostcard<T>(std::marker::PhantomData<T>);
+
+impl<'a, T: 'a> BytesEncode<'a> for SerdePostcard<T>
+where
+    T: Serialize,
+{
+    type EItem = T;
+
+    fn bytes_encode(item: &Self::EItem) -> Result<Cow<'_, [u8]>, EncodeError> {
+        postcard::to_allocvec(item)
+            .map(Cow::Owned)
+            .map_err(Into::into)
+    }
+}
+
+impl<'a, T: 'a> BytesDecode<'a> for SerdePostcard<T>
+where
+    T: Deserialize<'a>,
+{
+    type DItem = T;
+
+    fn bytes_decode(bytes: &'a [u8]) -> Result<Self::DItem, DecodeError> {
+        postcard::from_bytes(bytes).map_err(Into::into)
+    }
+}
+
+impl<T> BytesDecodeOwned for SerdePostcard<T>
+where
+    T: DeserializeOwned,
+{
+    type DItem = T;
+
+    fn bytes_decode_owned(bytes: &[u8]) -> Result<Self::DItem, DecodeError> {
+        postcard::from_bytes(bytes).map_err(Into::into)
+    }
+}
+
+impl<T: TypeName> CodecId for SerdePostcard<T> {
+    fn codec_id() -> Cow<'static, str> {
+        Cow::Owned(format!("postcard<{}>", T::TYPE_NAME))
+    }
+}
+
+impl<T> SerdeCodec for SerdePostcard<T> {
+    type Item = T;
+
+    fn serialize_item<U: Serialize>(item: &U) -> Result<Vec<u8>, EncodeError> {
+        postcard::to_allocvec(item).map_err(Into::into)
+    }
+
+    fn deserialize_item<U: DeserializeOwned>(bytes: &[u8]) -> Result<U, DecodeError> {
+        postcard::from_bytes(bytes).map_err(Into::into)
+    }
+}
@@ -2,9 +2,10 @@ use std::borrow::Cow;
 
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
+use super::SerdeCodec;
 use crate::{
     error::{DecodeError, EncodeError},
-    traits::{BytesDecode, BytesDecodeOwned, BytesEncode},
+    traits::{BytesDecode, BytesDecodeOwned, BytesEncode, CodecId, TypeName},
 };
 
 /// Describes a type that is [`Serialize`]/[`Deserialize`] and uses `rmp_serde` to do so.
@@ -44,3 +45,21 @@ where
         rmp_serde::from_slice(bytes).map_err(Into::into)
     }
 }
+
+impl<T: TypeName> CodecId for SerdeRmp<T> {
+    fn codec_id() -> Cow<'static, str> {
+        Cow::Owned(format!("msgpack<{}>", T::TYPE_NAME))
+    }
+}
+
+impl<T> SerdeCodec for SerdeRmp<T> {
+    type Item = T;
+
+    fn serialize_item<U: Serialize>(item: &U) -> Result<Vec<u8>, EncodeError> {
+        rmp_serde::to_vec(item).map_err(Into::into)
+    }
+
+    fn deserialize_item<U: DeserializeOwned>(bytes: &[u8]) -> Result<U, DecodeError> {
+        rmp_serde::from_slice(bytes).map_err(Into::into)
+    }
+}
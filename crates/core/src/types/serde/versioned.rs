@@ -0,0 +1,238 @@
+use std::borrow::Cow;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::SerdeCodec;
+use crate::{
+    error::{DecodeError, EncodeError},
+    traits::{BytesDecode, BytesDecodeOwned, BytesEncode, CodecId},
+};
+
+/// A type that can be migrated from the previous version of itself.
+///
+/// Versions start at `1` and must increase along the chain, the first version uses
+/// [`Initial`] as its previous version.
+///
+/// # Examples
+///
+/// ```
+/// use okv::types::serde::{Initial, Migrate};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct UserV1 {
+///     name: String,
+/// }
+///
+/// impl Migrate for UserV1 {
+///     const VERSION: u32 = 1;
+///     type Previous = Initial;
+///     fn migrate(previous: Initial) -> Self {
+///         match previous {}
+///     }
+/// }
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct User {
+///     first_name: String,
+///     last_name: String,
+/// }
+///
+/// impl Migrate for User {
+///     const VERSION: u32 = 2;
+///     type Previous = UserV1;
+///     fn migrate(previous: UserV1) -> Self {
+///         let (first, last) = previous.name.split_once(' ').unwrap_or((&previous.name, ""));
+///         Self {
+///             first_name: first.to_string(),
+///             last_name: last.to_string(),
+///         }
+///     }
+/// }
+/// ```
+pub trait Migrate: DeserializeOwned {
+    /// The version of this type, stored in front of every value.
+    const VERSION: u32;
+
+    /// The previous version of this type.
+    type Previous: Migrate;
+
+    /// Convert a value of the previous version.
+    fn migrate(previous: Self::Previous) -> Self;
+}
+
+/// The end of a [`Migrate`] chain, used as the previous version of the first version.
+#[derive(Debug, Deserialize)]
+pub enum Initial {}
+
+impl Migrate for Initial {
+    const VERSION: u32 = 0;
+    type Previous = Initial;
+
+    fn migrate(previous: Initial) -> Self {
+        previous
+    }
+}
+
+/// Wraps a serde codec like [`SerdeJson`](super::SerdeJson) and prefixes every value with
+/// the [`Migrate::VERSION`] of its type (`u32`, little endian).
+///
+/// Values of older versions are decoded with their own type and migrated along the chain
+/// when they are read, use [`Database::migrate_all`](crate::db::Database::migrate_all) to
+/// rewrite them in the latest version. Values written without this wrapper can't be read,
+/// use [`Database::into_versioned`](crate::db::Database::into_versioned) to adopt them as
+/// the first version.
+///
+/// # Examples
+///
+/// ```
+/// use okv::{Env, TypeName, backend::memory::MemDB};
+/// use okv::types::serde::{Initial, Migrate, SerdeJson, Versioned};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize, Debug, PartialEq)]
+/// struct Counter(u64);
+///
+/// impl TypeName for Counter {
+///     const TYPE_NAME: &'static str = "counter";
+/// }
+///
+/// impl Migrate for Counter {
+///     const VERSION: u32 = 1;
+///     type Previous = Initial;
+///     fn migrate(previous: Initial) -> Self {
+///         match previous {}
+///     }
+/// }
+///
+/// let env = Env::new(MemDB::new());
+/// let db = env.open::<&str, Versioned<SerdeJson<Counter>>>("counters").unwrap();
+/// db.set("visits", &Counter(42)).unwrap();
+/// assert_eq!(db.get("visits").unwrap(), Some(Counter(42)));
+/// assert_eq!(db.get_raw("visits").unwrap(), Some(b"\x01\x00\x00\x0042".to_vec()));
+/// ```
+pub struct Versioned<C>(std::marker::PhantomData<C>);
+
+const TAG_LEN: usize = 4;
+
+/// Returns the version of an encoded value.
+fn version(bytes: &[u8]) -> Result<u32, DecodeError> {
+    let tag = bytes.get(..TAG_LEN).ok_or(DecodeError::SizeMismatch)?;
+    Ok(u32::from_le_bytes(tag.try_into().expect("tag has 4 bytes")))
+}
+
+/// Decode a value of the given version as `T`, migrating it along the chain.
+fn decode_version<C, T>(version: u32, bytes: &[u8]) -> Result<T, DecodeError>
+where
+    C: SerdeCodec,
+    T: Migrate,
+{
+    if version == T::VERSION {
+        return C::deserialize_item(bytes);
+    }
+    // versions decrease along the chain, which ends at `Initial` with version 0
+    if version > T::VERSION {
+        return Err(DecodeError::UnknownVersion(version));
+    }
+    decode_version::<C, T::Previous>(version, bytes).map(T::migrate)
+}
+
+impl<C> Versioned<C>
+where
+    C: SerdeCodec,
+    C::Item: Migrate + Serialize,
+{
+    /// Encode a value with its version tag.
+    fn encode(item: &C::Item) -> Result<Vec<u8>, EncodeError> {
+        let mut buf = <C::Item as Migrate>::VERSION.to_le_bytes().to_vec();
+        buf.extend_from_slice(&C::serialize_item(item)?);
+        Ok(buf)
+    }
+
+    /// Decode a value of any version in the chain.
+    fn decode(bytes: &[u8]) -> Result<C::Item, DecodeError> {
+        decode_version::<C, C::Item>(version(bytes)?, &bytes[TAG_LEN..])
+    }
+
+    /// Re-encode a value of an older version in the latest version.
+    /// Returns `None` if the value is already in the latest version.
+    pub(crate) fn upgrade(bytes: &[u8]) -> crate::error::Result<Option<Vec<u8>>> {
+        if version(bytes)? == <C::Item as Migrate>::VERSION {
+            return Ok(None);
+        }
+        Ok(Some(Self::encode(&Self::decode(bytes)?)?))
+    }
+}
+
+impl<C> Versioned<C>
+where
+    C: SerdeCodec,
+    C::Item: Migrate,
+{
+    /// Tag a value written without [`Versioned`] as the version of `C::Item`.
+    /// Returns `None` if the value is already tagged, which is the case if it only decodes
+    /// with its tag.
+    pub(crate) fn adopt(bytes: &[u8]) -> Option<Vec<u8>> {
+        let tagged = version(bytes)
+            .and_then(|version| decode_version::<C, C::Item>(version, &bytes[TAG_LEN..]))
+            .is_ok();
+        if tagged && C::deserialize_item::<C::Item>(bytes).is_err() {
+            return None;
+        }
+        Some(
+            [
+                <C::Item as Migrate>::VERSION.to_le_bytes().as_slice(),
+                bytes,
+            ]
+            .concat(),
+        )
+    }
+}
+
+impl<'a, C> BytesEncode<'a> for Versioned<C>
+where
+    C: SerdeCodec,
+    C::Item: Migrate + Serialize + 'a,
+{
+    type EItem = C::Item;
+
+    fn bytes_encode(item: &Self::EItem) -> Result<Cow<'_, [u8]>, EncodeError> {
+        Self::encode(item).map(Cow::Owned)
+    }
+}
+
+impl<'a, C> BytesDecode<'a> for Versioned<C>
+where
+    C: SerdeCodec,
+    C::Item: Migrate + Serialize + 'a,
+{
+    type DItem = C::Item;
+
+    fn bytes_decode(bytes: &[u8]) -> Result<Self::DItem, DecodeError> {
+        Self::decode(bytes)
+    }
+}
+
+impl<C> BytesDecodeOwned for Versioned<C>
+where
+    C: SerdeCodec,
+    C::Item: Migrate + Serialize,
+{
+    type DItem = C::Item;
+
+    fn bytes_decode_owned(bytes: &[u8]) -> Result<Self::DItem, DecodeError> {
+        Self::decode(bytes)
+    }
+}
+
+/// The version is not part of the identifier, so migrating a type keeps its schema.
+impl<C: CodecId> CodecId for Versioned<C> {
+    fn codec_id() -> Cow<'static, str> {
+        Cow::Owned(versioned_codec_id(&C::codec_id()))
+    }
+}
+
+/// The identifier of a versioned codec, wrapping the identifier of the inner codec.
+pub(crate) fn versioned_codec_id(inner: &str) -> String {
+    format!("versioned<{inner}>")
+}
@@ -3,7 +3,7 @@ use std::borrow::Cow;
 use uuid::Uuid;
 
 use crate::error::{DecodeError, EncodeError};
-use crate::traits::{BytesDecode, BytesDecodeOwned, BytesEncode};
+use crate::traits::{BytesDecode, BytesDecodeOwned, BytesEncode, CodecId};
 
 impl BytesEncode<'_> for Uuid {
     type EItem = Uuid;
@@ -28,3 +28,9 @@ impl BytesDecodeOwned for Uuid {
         Ok(Uuid::from_slice(bytes)?)
     }
 }
+
+impl CodecId for Uuid {
+    fn codec_id() -> Cow<'static, str> {
+        Cow::Borrowed("uuid")
+    }
+}
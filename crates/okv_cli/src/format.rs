@@ -0,0 +1,138 @@
+use std::{fmt::Display, mem::size_of, str::FromStr};
+
+use clap::ValueEnum;
+use okv_core::{
+    error::{DecodeError, Error, Result},
+    traits::{BytesDecodeOwned, BytesEncode},
+    types::serde::{SerdeJson, SerdeRmp},
+};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// How keys and values are read from arguments and displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum Format {
+    /// UTF-8 text
+    Utf8,
+    /// Hexadecimal bytes, optionally prefixed with `0x`
+    Hex,
+    /// JSON, using `okv_core::types::serde::SerdeJson`
+    Json,
+    /// MessagePack, displayed and entered as JSON
+    Msgpack,
+    /// 16 byte UUIDs
+    Uuid,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    I16,
+    I32,
+    I64,
+    I128,
+}
+
+fn invalid(format: Format, input: &str) -> Error {
+    Error::Unknown(format!("invalid {format:?} input: {input}"))
+}
+
+fn encode_int<T>(format: Format, input: &str) -> Result<Vec<u8>>
+where
+    T: FromStr + for<'a> BytesEncode<'a, EItem = T>,
+{
+    let item = input.parse::<T>().map_err(|_| invalid(format, input))?;
+    Ok(T::bytes_encode(&item)?.into_owned())
+}
+
+fn decode_int<T>(bytes: &[u8]) -> Result<String>
+where
+    T: BytesDecodeOwned,
+    T::DItem: Display,
+{
+    // integers are decoded from the first bytes, reject values that are longer, except for
+    // values written by older versions, which are twice as long with a zeroed first half
+    let size = size_of::<T>();
+    let legacy = bytes.len() == 2 * size && bytes[..size].iter().all(|b| *b == 0);
+    if bytes.len() != size && !legacy {
+        return Err(DecodeError::SizeMismatch.into());
+    }
+    Ok(T::bytes_decode_owned(bytes)?.to_string())
+}
+
+fn parse_json(format: Format, input: &str) -> Result<Value> {
+    serde_json::from_str(input).map_err(|_| invalid(format, input))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn from_hex(input: &str) -> Result<Vec<u8>> {
+    let hex = input.strip_prefix("0x").unwrap_or(input);
+    // slicing in pairs of bytes needs ASCII, and from_str_radix would accept a sign
+    if hex.len() % 2 != 0 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(invalid(Format::Hex, input));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid(Format::Hex, input)))
+        .collect()
+}
+
+impl Format {
+    /// Encode an argument given in this format.
+    pub(crate) fn encode(self, input: &str) -> Result<Vec<u8>> {
+        let bytes = match self {
+            Format::Utf8 => input.as_bytes().to_vec(),
+            Format::Hex => from_hex(input)?,
+            Format::Json => {
+                SerdeJson::<Value>::bytes_encode(&parse_json(self, input)?)?.into_owned()
+            }
+            Format::Msgpack => {
+                SerdeRmp::<Value>::bytes_encode(&parse_json(self, input)?)?.into_owned()
+            }
+            Format::Uuid => {
+                let uuid = Uuid::parse_str(input).map_err(|_| invalid(self, input))?;
+                Uuid::bytes_encode(&uuid)?.into_owned()
+            }
+            Format::U8 => encode_int::<u8>(self, input)?,
+            Format::U16 => encode_int::<u16>(self, input)?,
+            Format::U32 => encode_int::<u32>(self, input)?,
+            Format::U64 => encode_int::<u64>(self, input)?,
+            Format::U128 => encode_int::<u128>(self, input)?,
+            Format::I16 => encode_int::<i16>(self, input)?,
+            Format::I32 => encode_int::<i32>(self, input)?,
+            Format::I64 => encode_int::<i64>(self, input)?,
+            Format::I128 => encode_int::<i128>(self, input)?,
+        };
+        Ok(bytes)
+    }
+
+    /// Decode bytes stored in this format.
+    pub(crate) fn decode(self, bytes: &[u8]) -> Result<String> {
+        let text = match self {
+            Format::Utf8 => String::bytes_decode_owned(bytes)?,
+            Format::Hex => to_hex(bytes),
+            Format::Json => SerdeJson::<Value>::bytes_decode_owned(bytes)?.to_string(),
+            Format::Msgpack => SerdeRmp::<Value>::bytes_decode_owned(bytes)?.to_string(),
+            Format::Uuid => Uuid::bytes_decode_owned(bytes)?.to_string(),
+            Format::U8 => decode_int::<u8>(bytes)?,
+            Format::U16 => decode_int::<u16>(bytes)?,
+            Format::U32 => decode_int::<u32>(bytes)?,
+            Format::U64 => decode_int::<u64>(bytes)?,
+            Format::U128 => decode_int::<u128>(bytes)?,
+            Format::I16 => decode_int::<i16>(bytes)?,
+            Format::I32 => decode_int::<i32>(bytes)?,
+            Format::I64 => decode_int::<i64>(bytes)?,
+            Format::I128 => decode_int::<i128>(bytes)?,
+        };
+        Ok(text)
+    }
+
+    /// Decode bytes for display, showing bytes that can't be decoded as `0x` prefixed hex.
+    pub(crate) fn display(self, bytes: &[u8]) -> String {
+        self.decode(bytes)
+            .unwrap_or_else(|_| format!("0x{}", to_hex(bytes)))
+    }
+}
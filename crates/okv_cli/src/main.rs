@@ -0,0 +1,300 @@
+//! Inspect and edit OKV databases from the command line.
+//!
+//! ```text
+//! okv [--key-format <format>] [--value-format <format>] <backend> <command>
+//!
+//! backend: memory:<path>, redb:<path>, rocksdb:<path>, heed:<path>, sled:<path>,
+//!          tcp://<address>, unix://<path> or redis://<address>
+//! ```
+//!
+//! Run `okv --help` for a list of commands and formats.
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use clap::{Parser, Subcommand};
+use okv_core::{
+    backend::{DBColumn, DBColumnBatch, DBColumnIterator, DBColumnIteratorPrefix, DatabaseBackend},
+    env::Env,
+    error::{Error, Result},
+};
+
+mod format;
+
+use format::Format;
+
+#[derive(Debug, Parser)]
+#[command(name = "okv", version, about = "Inspect and edit OKV databases")]
+struct Cli {
+    /// The backend to open: memory:<path>, redb:<path>, rocksdb:<path>, heed:<path>,
+    /// sled:<path>, tcp://<address>, unix://<path> or redis://<address>
+    backend: String,
+
+    /// The format of keys
+    #[arg(long, short, global = true, default_value = "utf8")]
+    key_format: Format,
+
+    /// The format of values
+    #[arg(long, short, global = true, default_value = "utf8")]
+    value_format: Format,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// List all databases
+    ListDbs,
+    /// Delete a database and all of its entries
+    DropDb { db: String },
+    /// Rename a database
+    RenameDb { old: String, new: String },
+    /// Print the value of a key
+    Get { db: String, key: String },
+    /// Set a key to a value
+    Set {
+        db: String,
+        key: String,
+        value: String,
+    },
+    /// Delete a key
+    Delete { db: String, key: String },
+    /// Print all entries, tab separated
+    Scan {
+        db: String,
+        /// Only print keys starting with this prefix, in the key format
+        #[arg(long)]
+        prefix: Option<String>,
+        /// Stop after this many entries
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+    /// Count the entries of a database
+    Count {
+        db: String,
+        /// Only count keys starting with this prefix, in the key format
+        #[arg(long)]
+        prefix: Option<String>,
+    },
+    /// Export all databases to a dump, see `okv_core::dump`
+    Dump {
+        /// Write to this file instead of stdout
+        file: Option<PathBuf>,
+        /// Only export this database
+        #[arg(long)]
+        db: Option<String>,
+    },
+    /// Import a dump
+    Load {
+        /// Read from this file instead of stdin
+        file: Option<PathBuf>,
+        /// Import a dump of a single database into this database
+        #[arg(long)]
+        db: Option<String>,
+    },
+    /// Print the number of entries and the size of keys and values
+    Stats {
+        /// Only show this database
+        db: Option<String>,
+    },
+    /// Compact the files of the backend
+    Compact,
+    /// Check the files of the backend for corruption, repairing them where supported
+    Check,
+}
+
+fn io_err(e: io::Error) -> Error {
+    Error::Unknown(e.to_string())
+}
+
+fn backend_err(e: impl std::error::Error + Send + Sync + 'static) -> Error {
+    Error::DatabaseBackend(Box::new(e))
+}
+
+/// Open an existing database, backends that can't list their databases open it regardless.
+fn open_column<D: DatabaseBackend>(env: &Env<D>, db: &str) -> Result<D::Column> {
+    if let Ok(false) = env.exists(db) {
+        return Err(Error::DatabaseNotFound { db: db.to_string() });
+    }
+    D::create_or_open(env.clone(), db)
+}
+
+type Entries<'c> = Box<dyn Iterator<Item = Result<(Vec<u8>, Vec<u8>)>> + 'c>;
+
+/// Iterate over all entries of a column, or the entries starting with a prefix.
+fn entries<'c, C>(column: &'c C, prefix: &Option<String>, format: Format) -> Result<Entries<'c>>
+where
+    C: DBColumnIterator + DBColumnIteratorPrefix,
+{
+    Ok(match prefix {
+        Some(prefix) => Box::new(column.iter_prefix(format.encode(prefix)?)?),
+        None => Box::new(column.iter()?),
+    })
+}
+
+fn run<D>(env: Env<D>, cli: &Cli) -> Result<()>
+where
+    D: DatabaseBackend,
+    D::Column: DBColumn + DBColumnBatch + DBColumnIterator + DBColumnIteratorPrefix,
+{
+    let mut out = BufWriter::new(io::stdout().lock());
+
+    match &cli.command {
+        Command::ListDbs => {
+            for name in env.databases()? {
+                writeln!(out, "{name}").map_err(io_err)?;
+            }
+        }
+        Command::DropDb { db } => env.drop_database(db)?,
+        Command::RenameDb { old, new } => env.rename_database(old, new)?,
+        Command::Get { db, key } => {
+            let key = cli.key_format.encode(key)?;
+            let value = open_column(&env, db)?
+                .get(&key)?
+                .ok_or(Error::KeyNotFound { key })?;
+            writeln!(out, "{}", cli.value_format.display(&value)).map_err(io_err)?;
+        }
+        Command::Set { db, key, value } => {
+            let column = D::create_or_open(env.clone(), db)?;
+            column.set(cli.key_format.encode(key)?, cli.value_format.encode(value)?)?;
+        }
+        Command::Delete { db, key } => {
+            open_column(&env, db)?.delete(cli.key_format.encode(key)?)?;
+        }
+        Command::Scan { db, prefix, limit } => {
+            let column = open_column(&env, db)?;
+            for item in entries(&column, prefix, cli.key_format)?.take(limit.unwrap_or(usize::MAX))
+            {
+                let (key, value) = item?;
+                let (key, value) = (
+                    cli.key_format.display(&key),
+                    cli.value_format.display(&value),
+                );
+                writeln!(out, "{key}\t{value}").map_err(io_err)?;
+            }
+        }
+        Command::Count { db, prefix } => {
+            let column = open_column(&env, db)?;
+            let mut count = 0u64;
+            for item in entries(&column, prefix, cli.key_format)? {
+                item?;
+                count += 1;
+            }
+            writeln!(out, "{count}").map_err(io_err)?;
+        }
+        Command::Dump { file, db } => {
+            let writer: Box<dyn Write> = match file {
+                Some(path) => Box::new(File::create(path).map_err(io_err)?),
+                None => Box::new(&mut out),
+            };
+            let stats = match db {
+                Some(db) => {
+                    open_column(&env, db)?;
+                    env.open::<&[u8], &[u8]>(db)?.export(writer)?
+                }
+                None => env.export(writer)?,
+            };
+            eprintln!(
+                "exported {} entries in {} databases",
+                stats.entries, stats.databases
+            );
+        }
+        Command::Load { file, db } => {
+            let reader: Box<dyn io::Read> = match file {
+                Some(path) => Box::new(File::open(path).map_err(io_err)?),
+                None => Box::new(io::stdin().lock()),
+            };
+            let stats = match db {
+                Some(db) => env.open::<&[u8], &[u8]>(db)?.import(reader)?,
+                None => env.import(reader)?,
+            };
+            eprintln!(
+                "imported {} entries in {} databases",
+                stats.entries, stats.databases
+            );
+        }
+        Command::Stats { db } => {
+            let databases = match db {
+                Some(db) => vec![db.clone()],
+                None => env.databases()?,
+            };
+            writeln!(out, "database\tentries\tkey bytes\tvalue bytes").map_err(io_err)?;
+            for name in databases {
+                let (mut entries, mut key_bytes, mut value_bytes) = (0u64, 0u64, 0u64);
+                for item in open_column(&env, &name)?.iter()? {
+                    let (key, value) = item?;
+                    entries += 1;
+                    key_bytes += key.len() as u64;
+                    value_bytes += value.len() as u64;
+                }
+                writeln!(out, "{name}\t{entries}\t{key_bytes}\t{value_bytes}").map_err(io_err)?;
+            }
+        }
+        Command::Compact => env.compact()?,
+        Command::Check => {
+            let status = match env.check_integrity()? {
+                true => "ok",
+                false => "repaired",
+            };
+            writeln!(out, "{status}").map_err(io_err)?;
+        }
+    }
+
+    out.flush().map_err(io_err)
+}
+
+fn open(cli: &Cli) -> Result<()> {
+    let backend = cli.backend.as_str();
+    match backend.split_once(':').unwrap_or((backend, "")) {
+        #[cfg(feature = "memory")]
+        ("memory", path) => run(Env::new(okv_memory::MemDB::open(path)?), cli),
+
+        #[cfg(feature = "redb")]
+        ("redb", path) => run(
+            Env::new(okv_redb::Redb::new(path).map_err(backend_err)?),
+            cli,
+        ),
+
+        #[cfg(feature = "rocksdb")]
+        ("rocksdb", path) => run(
+            Env::new(okv_rocksdb::RocksDb::new(path).map_err(backend_err)?),
+            cli,
+        ),
+
+        #[cfg(feature = "heed")]
+        ("heed", path) => run(
+            Env::new(okv_heed::Heed::new(path).map_err(backend_err)?),
+            cli,
+        ),
+
+        #[cfg(feature = "sled")]
+        ("sled", path) => run(
+            Env::new(okv_sled::Sled::new(path).map_err(backend_err)?),
+            cli,
+        ),
+
+        #[cfg(feature = "remote")]
+        ("tcp" | "unix", _) => run(Env::new(okv_remote::RemoteDb::connect(backend)?), cli),
+
+        #[cfg(feature = "redis")]
+        ("redis" | "rediss", _) => run(Env::new(okv_redis::Redis::new(backend)?), cli),
+
+        _ => Err(Error::Unknown(format!("unsupported backend: {backend}"))),
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match open(&cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+type Result<T = ()> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// A fresh directory for the files of a test.
+fn test_dir(name: &str) -> Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!("okv-cli-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Run `okv` and return its output, failing if it didn't succeed.
+fn okv(backend: &str, args: &[&str]) -> Result<String> {
+    let Output {
+        status,
+        stdout,
+        stderr,
+    } = Command::new(env!("CARGO_BIN_EXE_okv"))
+        .arg(backend)
+        .args(args)
+        .output()?;
+    if !status.success() {
+        return Err(format!("okv {args:?} failed: {}", String::from_utf8_lossy(&stderr)).into());
+    }
+    Ok(String::from_utf8(stdout)?)
+}
+
+/// The lines of an output in sorted order, not every backend scans in key order.
+fn sorted_lines(output: String) -> Vec<String> {
+    let mut lines: Vec<String> = output.lines().map(String::from).collect();
+    lines.sort();
+    lines
+}
+
+/// Write and read entries, then dump them and load them into a new database.
+fn roundtrip(backend: &str, restored: &str, dir: &Path) -> Result {
+    okv(backend, &["set", "users", "alice", "admin"])?;
+    okv(backend, &["set", "users", "bob", "guest"])?;
+    okv(backend, &["set", "settings", "theme", "dark"])?;
+    okv(backend, &["set", "counters", "visits", "42", "-v", "u64"])?;
+
+    assert_eq!(okv(backend, &["get", "users", "alice"])?, "admin\n");
+    assert_eq!(
+        okv(backend, &["get", "counters", "visits", "-v", "u64"])?,
+        "42\n"
+    );
+    assert_eq!(
+        sorted_lines(okv(backend, &["scan", "users"])?),
+        ["alice\tadmin", "bob\tguest"]
+    );
+    assert_eq!(
+        okv(backend, &["scan", "users", "--prefix", "b"])?,
+        "bob\tguest\n"
+    );
+    assert!(okv(backend, &["get", "users", "carol"]).is_err());
+
+    // integers written by older versions are twice as long, with a zeroed first half
+    okv(
+        backend,
+        &["set", "counters", "legacy", "0000000007000000", "-v", "hex"],
+    )?;
+    assert_eq!(
+        okv(backend, &["get", "counters", "legacy", "-v", "u32"])?,
+        "7\n"
+    );
+
+    let dump = dir.join("backup.okvdump");
+    okv(backend, &["dump", dump.to_str().unwrap()])?;
+    okv(restored, &["load", dump.to_str().unwrap()])?;
+
+    assert_eq!(
+        sorted_lines(okv(restored, &["list-dbs"])?),
+        ["counters", "settings", "users"]
+    );
+    assert_eq!(
+        sorted_lines(okv(restored, &["scan", "users"])?),
+        ["alice\tadmin", "bob\tguest"]
+    );
+    assert_eq!(okv(restored, &["get", "settings", "theme"])?, "dark\n");
+    Ok(())
+}
+
+#[cfg(feature = "memory")]
+#[test]
+fn memory() -> Result {
+    let dir = test_dir("memory")?;
+    let backend = format!("memory:{}", dir.join("data.okv").display());
+    let restored = format!("memory:{}", dir.join("restored.okv").display());
+    roundtrip(&backend, &restored, &dir)?;
+    Ok(std::fs::remove_dir_all(dir)?)
+}
+
+#[cfg(feature = "redb")]
+#[test]
+fn redb() -> Result {
+    let dir = test_dir("redb")?;
+    let backend = format!("redb:{}", dir.join("data.redb").display());
+    let restored = format!("redb:{}", dir.join("restored.redb").display());
+    roundtrip(&backend, &restored, &dir)?;
+    Ok(std::fs::remove_dir_all(dir)?)
+}
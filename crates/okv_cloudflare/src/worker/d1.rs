@@ -186,4 +186,45 @@ impl DatabaseBackend for CfD1 {
             table: db.to_string(),
         })
     }
+
+    fn list_databases(&self) -> Result<Vec<String>> {
+        futures::executor::block_on(async {
+            // skip internal tables of SQLite and D1
+            let statement = self.d1()?.prepare(
+                "SELECT name FROM sqlite_master WHERE type = 'table' \
+                 AND name NOT LIKE 'sqlite_%' AND name NOT LIKE '_cf_%'",
+            );
+            let rows = statement.raw::<String>().await.map_err(okv_err)?;
+            Ok(rows.into_iter().flatten().collect())
+        })
+    }
+
+    fn drop_database(&self, db: &str) -> Result<()> {
+        let sql = format!("DROP TABLE {}", quote_ident(db));
+        futures::executor::block_on(async { exec(self.d1()?, &sql).await })
+    }
+
+    fn rename_database(&self, old: &str, new: &str) -> Result<()> {
+        let sql = format!(
+            "ALTER TABLE {} RENAME TO {}",
+            quote_ident(old),
+            quote_ident(new)
+        );
+        futures::executor::block_on(async { exec(self.d1()?, &sql).await })
+    }
+}
+
+/// Quote a table name, table names can't be bound as parameters.
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+async fn exec(d1: worker::D1Database, sql: &str) -> Result<()> {
+    match d1.prepare(sql).run().await {
+        Err(e) => Err(okv_err(e)),
+        Ok(res) => match res.error() {
+            Some(e) => Err(okv_err(e)),
+            None => Ok(()),
+        },
+    }
 }
@@ -1,6 +1,11 @@
+use std::ops::Bound;
+
 use futures::future::join_all;
 use okv_core::backend_async::sync_fallback;
-use okv_core::{backend::DatabaseBackend, backend_async::DBColumnAsync};
+use okv_core::{
+    backend::{page_start, DBColumnPage, DatabaseBackend},
+    backend_async::DBColumnAsync,
+};
 use okv_core::{error::Result, traits::Innerable};
 
 use super::okv_err;
@@ -159,6 +164,64 @@ impl DBColumnAsync for CfKVColumn {
     }
 }
 
+/// Pages are listed with the cursor of the KV `list` operation. KV cursors can't start at a
+/// given key, so the keys up to `after` are listed and skipped again on every page.
+impl DBColumnPage for CfKVColumn {
+    fn page(
+        &self,
+        after: Option<&[u8]>,
+        limit: usize,
+        prefix: &[u8],
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let list_prefix = self.str_key(prefix)?;
+        let start = page_start(after, prefix);
+
+        futures::executor::block_on(async {
+            let kv = self.kv()?;
+            let mut keys = Vec::new();
+            let mut cursor = None;
+            // keys are listed in the order of their UTF-8 bytes
+            while keys.len() < limit {
+                let mut list = kv.list().prefix(list_prefix.clone());
+                if let Some(cursor) = cursor.take() {
+                    list = list.cursor(cursor);
+                }
+                let res = list.execute().await.map_err(okv_err)?;
+
+                for key in res.keys {
+                    let key = key.name.as_bytes()[self.prefix.len() + 1..].to_vec();
+                    let in_page = match start {
+                        Bound::Excluded(after) => key.as_slice() > after,
+                        _ => true,
+                    };
+                    if in_page && keys.len() < limit {
+                        keys.push(key);
+                    }
+                }
+
+                match res.cursor {
+                    Some(next) if !res.list_complete => cursor = Some(next),
+                    _ => break,
+                }
+            }
+
+            let values =
+                join_all(keys.iter().map(|key| async {
+                    kv.get(&self.str_key(key)?).bytes().await.map_err(okv_err)
+                }))
+                .await;
+
+            // skip keys that were deleted since they were listed
+            keys.into_iter()
+                .zip(values)
+                .filter_map(|(key, val)| val.map(|val| val.map(|val| (key, val))).transpose())
+                .collect()
+        })
+    }
+}
+
+/// Databases are key prefixes in a single KV namespace, so they can't be listed, dropped or
+/// renamed reliably.
 impl DatabaseBackend for CfKV {
     type Column = CfKVColumn;
     fn create_or_open(
@@ -0,0 +1,181 @@
+use std::collections::BTreeMap;
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm,
+};
+use chacha20poly1305::ChaCha20Poly1305;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::EncryptionError;
+
+const ID_LEN: usize = 4;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = ID_LEN + NONCE_LEN;
+
+enum Cipher {
+    Aes256Gcm(Box<Aes256Gcm>),
+    ChaCha20Poly1305(ChaCha20Poly1305),
+}
+
+/// An encryption key and the algorithm it is used with.
+pub struct Key {
+    cipher: Cipher,
+    /// Derived from the key, used to compute the nonce of encrypted keys.
+    nonce_key: [u8; 32],
+}
+
+impl Key {
+    /// A 256 bit AES-GCM key, fast on CPUs with AES instructions.
+    pub fn aes_256_gcm(key: &[u8; 32]) -> Self {
+        let cipher = Cipher::Aes256Gcm(Box::new(Aes256Gcm::new(key.into())));
+        Self::with_cipher(cipher, key)
+    }
+
+    /// A 256 bit ChaCha20-Poly1305 key, fast on CPUs without AES instructions.
+    pub fn chacha20_poly1305(key: &[u8; 32]) -> Self {
+        let cipher = Cipher::ChaCha20Poly1305(ChaCha20Poly1305::new(key.into()));
+        Self::with_cipher(cipher, key)
+    }
+
+    fn with_cipher(cipher: Cipher, key: &[u8; 32]) -> Self {
+        let nonce_key = hmac(key, b"okv_encrypted key nonce");
+        Self { cipher, nonce_key }
+    }
+
+    fn encrypt(&self, nonce: &[u8], msg: &[u8], aad: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        let payload = Payload { msg, aad };
+        match &self.cipher {
+            Cipher::Aes256Gcm(c) => c.encrypt(nonce.into(), payload),
+            Cipher::ChaCha20Poly1305(c) => c.encrypt(nonce.into(), payload),
+        }
+        .map_err(|_| EncryptionError::Encrypt)
+    }
+
+    fn decrypt(&self, nonce: &[u8], msg: &[u8], aad: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        let payload = Payload { msg, aad };
+        match &self.cipher {
+            Cipher::Aes256Gcm(c) => c.decrypt(nonce.into(), payload),
+            Cipher::ChaCha20Poly1305(c) => c.decrypt(nonce.into(), payload),
+        }
+        .map_err(|_| EncryptionError::Decrypt)
+    }
+}
+
+fn hmac(key: &[u8], msg: &[u8]) -> [u8; 32] {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC accepts any key size");
+    mac.update(msg);
+    mac.finalize().into_bytes().into()
+}
+
+/// The keys of an [`EncryptedBackend`](crate::EncryptedBackend).
+///
+/// New values are encrypted with the current key, older keys are only used to decrypt
+/// values written before the key was rotated. Every encrypted value starts with the id of
+/// its key (`u32`, little endian), followed by a random nonce.
+///
+/// # Examples
+///
+/// ```
+/// use okv_encrypted::{Key, Keyring};
+///
+/// // rotate from key 1 to key 2, values encrypted with key 1 can still be read
+/// let keys = Keyring::new(2, Key::chacha20_poly1305(&[2; 32]))
+///     .with_key(1, Key::aes_256_gcm(&[1; 32]));
+/// assert_eq!(keys.current(), 2);
+/// ```
+pub struct Keyring {
+    current: u32,
+    keys: BTreeMap<u32, Key>,
+}
+
+impl Keyring {
+    /// Create a keyring that encrypts with the given key.
+    pub fn new(id: u32, key: Key) -> Self {
+        Self {
+            current: id,
+            keys: BTreeMap::from([(id, key)]),
+        }
+    }
+
+    /// Add an older key that is only used for decryption.
+    /// Ignored if a key with the same id exists.
+    pub fn with_key(mut self, id: u32, key: Key) -> Self {
+        self.keys.entry(id).or_insert(key);
+        self
+    }
+
+    /// The id of the key that new values are encrypted with.
+    pub fn current(&self) -> u32 {
+        self.current
+    }
+
+    /// The ids of all keys, starting with the current key.
+    pub(crate) fn ids(&self) -> impl Iterator<Item = u32> + '_ {
+        let older = self.keys.keys().rev().filter(|id| **id != self.current);
+        std::iter::once(self.current).chain(older.copied())
+    }
+
+    fn key(&self, id: u32) -> Result<&Key, EncryptionError> {
+        self.keys.get(&id).ok_or(EncryptionError::UnknownKey(id))
+    }
+
+    /// Returns the id of the key that `bytes` were encrypted with.
+    pub(crate) fn key_id(bytes: &[u8]) -> Result<u32, EncryptionError> {
+        let id = bytes.get(..ID_LEN).ok_or(EncryptionError::Invalid)?;
+        Ok(u32::from_le_bytes(id.try_into().expect("id has 4 bytes")))
+    }
+
+    /// Encrypt a value with the current key and a random nonce. The value is bound to
+    /// `aad`, the database and plain key it is stored under, so it can't be moved.
+    pub(crate) fn encrypt_value(&self, aad: &[u8], val: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        let nonce = match &self.key(self.current)?.cipher {
+            Cipher::Aes256Gcm(_) => Aes256Gcm::generate_nonce(&mut OsRng),
+            Cipher::ChaCha20Poly1305(_) => ChaCha20Poly1305::generate_nonce(&mut OsRng),
+        };
+        self.seal(self.current, &nonce, val, aad)
+    }
+
+    /// Encrypt a key with the given key id. The nonce is derived from the key, so the same
+    /// key is always encrypted to the same bytes and can be looked up.
+    pub(crate) fn encrypt_key(&self, id: u32, key: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        let nonce = hmac(&self.key(id)?.nonce_key, key);
+        self.seal(id, &nonce[..NONCE_LEN], key, &[])
+    }
+
+    pub(crate) fn decrypt_value(
+        &self,
+        aad: &[u8],
+        bytes: &[u8],
+    ) -> Result<Vec<u8>, EncryptionError> {
+        self.open(bytes, aad)
+    }
+
+    pub(crate) fn decrypt_key(&self, bytes: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        self.open(bytes, &[])
+    }
+
+    fn seal(
+        &self,
+        id: u32,
+        nonce: &[u8],
+        msg: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, EncryptionError> {
+        let ciphertext = self.key(id)?.encrypt(nonce, msg, aad)?;
+        let mut buf = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+        buf.extend_from_slice(&id.to_le_bytes());
+        buf.extend_from_slice(nonce);
+        buf.extend_from_slice(&ciphertext);
+        Ok(buf)
+    }
+
+    fn open(&self, bytes: &[u8], aad: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        if bytes.len() < HEADER_LEN {
+            return Err(EncryptionError::Invalid);
+        }
+        let key = self.key(Self::key_id(bytes)?)?;
+        key.decrypt(&bytes[ID_LEN..HEADER_LEN], &bytes[HEADER_LEN..], aad)
+    }
+}
@@ -0,0 +1,542 @@
+//! Encryption at rest for any OKV backend.
+//!
+//! [`EncryptedBackend`] wraps another [`DatabaseBackend`] and encrypts every value with
+//! AES-256-GCM or ChaCha20-Poly1305 before it is written, using the keys of a [`Keyring`].
+//! Values are bound to the database and key they are stored under, so they can't be swapped
+//! between keys or databases. Renaming a database encrypts its values again.
+//!
+//! Keys are stored in plain text by default, to keep iteration in key order and prefix
+//! iteration working. With [`EncryptedBackend::encrypt_keys`], keys are encrypted
+//! deterministically instead. This reveals which entries share a key, but not the key itself.
+//! Database names are never encrypted.
+//!
+//! # Examples
+//!
+//! ```
+//! use okv::{Env, backend::memory::MemDB};
+//! use okv_encrypted::{EncryptedBackend, Key, Keyring};
+//!
+//! let keys = Keyring::new(1, Key::aes_256_gcm(&[7; 32]));
+//! let env = Env::new(EncryptedBackend::new(MemDB::new(), keys));
+//!
+//! let db = env.open::<&str, &str>("people").unwrap();
+//! db.set("alice", "alice@example.com").unwrap();
+//! assert_eq!(db.get("alice").unwrap(), Some("alice@example.com".to_string()));
+//! ```
+
+use okv_core::{
+    backend::*,
+    env::Env,
+    error::{Error, Result},
+    traits::{Flushable, Innerable},
+};
+use thiserror::Error;
+
+mod keys;
+
+pub use keys::{Key, Keyring};
+
+/// An error that can occur when encrypting or decrypting.
+#[derive(Error, Debug)]
+pub enum EncryptionError {
+    /// A value was encrypted with a key that is not part of the keyring.
+    #[error("Unknown key id: {0}")]
+    UnknownKey(u32),
+
+    /// A value is too short to be encrypted.
+    #[error("Invalid encrypted value")]
+    Invalid,
+
+    /// A value was modified, or encrypted with a different key with the same id.
+    #[error("Decryption failed")]
+    Decrypt,
+
+    /// A value could not be encrypted.
+    #[error("Encryption failed")]
+    Encrypt,
+
+    /// The operation doesn't work with encrypted keys.
+    #[error("{0} is not supported with encrypted keys")]
+    Unsupported(&'static str),
+}
+
+pub(crate) fn okv_err(e: EncryptionError) -> Error {
+    Error::DatabaseBackend(Box::new(e))
+}
+
+/// A backend that encrypts the values of another backend.
+pub struct EncryptedBackend<D: DatabaseBackend> {
+    env: Env<D>,
+    keys: std::sync::Arc<Keyring>,
+    encrypt_keys: bool,
+}
+
+impl<D: DatabaseBackend> EncryptedBackend<D> {
+    /// Encrypt the values of `db` with the given keys.
+    pub fn new(db: D, keys: Keyring) -> Self {
+        Self {
+            env: Env::new(db),
+            keys: std::sync::Arc::new(keys),
+            encrypt_keys: false,
+        }
+    }
+
+    /// Encrypt keys as well. Keys are looked up with every key of the keyring until they
+    /// are rotated, and prefix iteration is not supported.
+    ///
+    /// This changes how keys are stored, so it can't be enabled for existing databases.
+    pub fn encrypt_keys(mut self) -> Self {
+        self.encrypt_keys = true;
+        self
+    }
+
+    /// The keyring used to encrypt and decrypt values.
+    pub fn keys(&self) -> &Keyring {
+        &self.keys
+    }
+}
+
+impl<D: DatabaseBackend> EncryptedBackend<D>
+where
+    D::Column: DBColumn + DBColumnIterator,
+{
+    /// Encrypt all entries of a database that were written with an older key with the
+    /// current key. Returns the number of rewritten entries.
+    ///
+    /// Afterwards the older keys can be removed from the keyring. The entries are collected
+    /// before they are rewritten, so this needs memory for all outdated entries.
+    pub fn rotate(&self, db: &str) -> Result<u64> {
+        let column = self.column(db, D::create_or_open(self.env.clone(), db)?);
+        let current = self.keys.current();
+
+        let mut outdated = Vec::new();
+        for item in column.inner.iter()? {
+            let (key, val) = item?;
+            let stored_with = |bytes: &[u8]| Keyring::key_id(bytes).map_err(okv_err);
+            let key_outdated = self.encrypt_keys && stored_with(&key)? != current;
+            if key_outdated || stored_with(&val)? != current {
+                outdated.push((key, val));
+            }
+        }
+
+        for (key, val) in &outdated {
+            let plain_key = column.decrypt_key(key)?;
+            let val = column.decrypt_value(&plain_key, val)?;
+            column.set(&plain_key, val)?;
+        }
+        Ok(outdated.len() as u64)
+    }
+}
+
+impl<D: DatabaseBackend> EncryptedBackend<D> {
+    fn column(&self, name: &str, inner: D::Column) -> EncryptedColumn<D::Column> {
+        EncryptedColumn {
+            name: name.to_owned(),
+            inner,
+            keys: self.keys.clone(),
+            encrypt_keys: self.encrypt_keys,
+        }
+    }
+}
+
+impl<D: DatabaseBackend> Innerable for EncryptedBackend<D> {
+    type Inner = D;
+    fn inner(&self) -> &Self::Inner {
+        self.env.db()
+    }
+}
+
+impl<D: DatabaseBackend + Flushable> Flushable for EncryptedBackend<D> {
+    fn flush(&self) -> Result<()> {
+        self.env.db().flush()
+    }
+}
+
+impl<D: DatabaseBackend> DatabaseBackend for EncryptedBackend<D>
+where
+    D::Column: DBColumn + DBColumnIterator,
+{
+    type Column = EncryptedColumn<D::Column>;
+
+    fn create_or_open(env: Env<Self>, db: &str) -> Result<Self::Column> {
+        let backend = env.db();
+        Ok(backend.column(db, D::create_or_open(backend.env.clone(), db)?))
+    }
+
+    fn list_databases(&self) -> Result<Vec<String>> {
+        self.env.db().list_databases()
+    }
+
+    fn exists(&self, db: &str) -> Result<bool> {
+        self.env.db().exists(db)
+    }
+
+    fn drop_database(&self, db: &str) -> Result<()> {
+        self.env.db().drop_database(db)
+    }
+
+    /// Values are bound to the database name, so they are encrypted again under the new
+    /// name after the inner backend renamed the database. The entries are collected first,
+    /// so this needs memory for all entries.
+    fn rename_database(&self, old: &str, new: &str) -> Result<()> {
+        self.env.db().rename_database(old, new)?;
+        let renamed = self.column(new, D::create_or_open(self.env.clone(), new)?);
+        let entries = renamed.inner.iter()?.collect::<Result<Vec<_>>>()?;
+        for (key, val) in entries {
+            let plain_key = renamed.decrypt_key(&key)?;
+            let val = self
+                .keys
+                .decrypt_value(&aad(old, &plain_key), &val)
+                .map_err(okv_err)?;
+            renamed
+                .inner
+                .set(key, renamed.encrypt_value(&plain_key, &val)?)?;
+        }
+        Ok(())
+    }
+
+    fn compact(&self) -> Result<()> {
+        self.env.db().compact()
+    }
+
+    fn check_integrity(&self) -> Result<bool> {
+        self.env.db().check_integrity()
+    }
+}
+
+/// The associated data of a value: the name of its database and the plain key it is stored
+/// under, so it can't be moved to another key or database.
+fn aad(db: &str, key: &[u8]) -> Vec<u8> {
+    let mut aad = Vec::with_capacity(4 + db.len() + key.len());
+    aad.extend_from_slice(&(db.len() as u32).to_le_bytes());
+    aad.extend_from_slice(db.as_bytes());
+    aad.extend_from_slice(key);
+    aad
+}
+
+/// A database of an [`EncryptedBackend`].
+pub struct EncryptedColumn<C> {
+    name: String,
+    inner: C,
+    keys: std::sync::Arc<Keyring>,
+    encrypt_keys: bool,
+}
+
+impl<C> EncryptedColumn<C> {
+    /// The key as it is stored with the given key id.
+    fn stored_key(&self, id: u32, key: &[u8]) -> Result<Vec<u8>> {
+        match self.encrypt_keys {
+            true => self.keys.encrypt_key(id, key).map_err(okv_err),
+            false => Ok(key.to_vec()),
+        }
+    }
+
+    /// The key as it is stored with every key of the keyring, starting with the current key.
+    fn stored_keys<'a>(&'a self, key: &'a [u8]) -> impl Iterator<Item = Result<Vec<u8>>> + 'a {
+        let ids = match self.encrypt_keys {
+            true => self.keys.ids().collect(),
+            false => vec![self.keys.current()],
+        };
+        ids.into_iter().map(move |id| self.stored_key(id, key))
+    }
+
+    fn decrypt_key(&self, key: &[u8]) -> Result<Vec<u8>> {
+        match self.encrypt_keys {
+            true => self.keys.decrypt_key(key).map_err(okv_err),
+            false => Ok(key.to_vec()),
+        }
+    }
+
+    fn encrypt_value(&self, key: &[u8], val: &[u8]) -> Result<Vec<u8>> {
+        let aad = aad(&self.name, key);
+        self.keys.encrypt_value(&aad, val).map_err(okv_err)
+    }
+
+    fn decrypt_value(&self, key: &[u8], val: &[u8]) -> Result<Vec<u8>> {
+        let aad = aad(&self.name, key);
+        self.keys.decrypt_value(&aad, val).map_err(okv_err)
+    }
+
+    fn decrypt_item(&self, item: Result<(Vec<u8>, Vec<u8>)>) -> Result<(Vec<u8>, Vec<u8>)> {
+        let (key, val) = item?;
+        let key = self.decrypt_key(&key)?;
+        let val = self.decrypt_value(&key, &val)?;
+        Ok((key, val))
+    }
+}
+
+impl<C> Innerable for EncryptedColumn<C> {
+    type Inner = C;
+    fn inner(&self) -> &Self::Inner {
+        &self.inner
+    }
+}
+
+impl<C: Flushable> Flushable for EncryptedColumn<C> {
+    fn flush(&self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<C: DBColumn> DBColumn for EncryptedColumn<C> {
+    fn set(&self, key: impl AsRef<[u8]>, val: impl AsRef<[u8]>) -> Result<()> {
+        let key = key.as_ref();
+        let val = self.encrypt_value(key, val.as_ref())?;
+
+        let mut stored_keys = self.stored_keys(key);
+        let current = stored_keys.next().expect("the current key")?;
+        self.inner.set(current, val)?;
+        // remove the entry written with an older key
+        for stored in stored_keys {
+            self.inner.delete(stored?)?;
+        }
+        Ok(())
+    }
+
+    /// With encrypted keys, checking the older keys and setting the value is not atomic.
+    fn set_nx(&self, key: impl AsRef<[u8]>, val: impl AsRef<[u8]>) -> Result<bool> {
+        let key = key.as_ref();
+        let mut stored_keys = self.stored_keys(key);
+        let current = stored_keys.next().expect("the current key")?;
+        for stored in stored_keys {
+            if self.inner.contains(stored?)? {
+                return Ok(false);
+            }
+        }
+
+        let val = self.encrypt_value(key, val.as_ref())?;
+        self.inner.set_nx(current, val)
+    }
+
+    fn get(&self, key: impl AsRef<[u8]>) -> Result<Option<Vec<u8>>> {
+        let key = key.as_ref();
+        for stored in self.stored_keys(key) {
+            if let Some(val) = self.inner.get(stored?)? {
+                return self.decrypt_value(key, &val).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    fn get_multi<I>(&self, keys: I) -> Result<Vec<Option<Vec<u8>>>>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        if self.encrypt_keys {
+            return keys.into_iter().map(|key| self.get(key)).collect();
+        }
+
+        let keys: Vec<I::Item> = keys.into_iter().collect();
+        let values = self.inner.get_multi(keys.iter().map(AsRef::as_ref))?;
+        keys.iter()
+            .zip(values)
+            .map(|(key, val)| match val {
+                Some(val) => self.decrypt_value(key.as_ref(), &val).map(Some),
+                None => Ok(None),
+            })
+            .collect()
+    }
+
+    fn delete(&self, key: impl AsRef<[u8]>) -> Result<()> {
+        for stored in self.stored_keys(key.as_ref()) {
+            self.inner.delete(stored?)?;
+        }
+        Ok(())
+    }
+
+    fn contains(&self, key: impl AsRef<[u8]>) -> Result<bool> {
+        for stored in self.stored_keys(key.as_ref()) {
+            if self.inner.contains(stored?)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+impl<C: DBColumnBatch> DBColumnBatch for EncryptedColumn<C> {
+    /// Entries written with an older key are removed in the same batch.
+    fn set_multi<I, K, V>(&self, entries: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        self.write_multi(entries.into_iter().map(|(key, val)| (key, Some(val))))
+    }
+
+    fn write_multi<I, K, V>(&self, writes: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (K, Option<V>)>,
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        let mut batch = Vec::new();
+        for (key, val) in writes {
+            let key = key.as_ref();
+            let mut stored_keys = self.stored_keys(key);
+            let current = stored_keys.next().expect("the current key")?;
+            let val = match val {
+                Some(val) => Some(self.encrypt_value(key, val.as_ref())?),
+                None => None,
+            };
+
+            let older = stored_keys.collect::<Result<Vec<_>>>()?;
+            batch.extend(older.into_iter().map(|stored| (stored, None)));
+            batch.push((current, val));
+        }
+        self.inner.write_multi(batch)
+    }
+}
+
+impl<C: DBColumnClear> DBColumnClear for EncryptedColumn<C> {
+    fn clear(&self) -> Result<()> {
+        self.inner.clear()
+    }
+}
+
+impl<C: DBColumnDelete> DBColumnDelete for EncryptedColumn<C> {
+    fn delete_db(&self) -> Result<()> {
+        self.inner.delete_db()
+    }
+}
+
+impl<C: DBColumnDeleteRange> DBColumnDeleteRange for EncryptedColumn<C> {
+    fn delete_range(&self, start: &[u8], end: Option<&[u8]>) -> Result<Option<u64>> {
+        if self.encrypt_keys {
+            return Err(okv_err(EncryptionError::Unsupported("range deletion")));
+        }
+        self.inner.delete_range(start, end)
+    }
+}
+
+impl<C: DBColumnCompact> DBColumnCompact for EncryptedColumn<C> {
+    /// With encrypted keys, ranges of plain keys don't map to stored keys, so the whole
+    /// database is compacted.
+    fn compact_range(&self, range: KeyRange) -> Result<()> {
+        match self.encrypt_keys {
+            true => self.inner.compact_range(KeyRange::default()),
+            false => self.inner.compact_range(range),
+        }
+    }
+}
+
+impl<C: DBColumnStats> DBColumnStats for EncryptedColumn<C> {
+    /// The sizes include the nonces and tags of the encrypted entries.
+    fn stats(&self) -> Result<ColumnStats> {
+        self.inner.stats()
+    }
+}
+
+impl<C: DBColumnIterator> DBColumnIterator for EncryptedColumn<C> {
+    /// With encrypted keys, entries are not in key order.
+    fn iter(&self) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>> {
+        Ok(self.inner.iter()?.map(|item| self.decrypt_item(item)))
+    }
+
+    /// Only the keys are decrypted, the values are not read.
+    fn keys(&self) -> Result<impl Iterator<Item = Result<Vec<u8>>>> {
+        Ok(self.inner.keys()?.map(|key| match self.encrypt_keys {
+            true => self.decrypt_key(&key?),
+            false => key,
+        }))
+    }
+
+    fn count(&self) -> Result<u64> {
+        self.inner.count()
+    }
+}
+
+impl<C: DBColumnIteratorRef> DBColumnIteratorRef for EncryptedColumn<C> {
+    /// Values are decrypted into a buffer, so they are copied once.
+    fn for_each_ref(
+        &self,
+        prefix: &[u8],
+        mut f: impl FnMut(&[u8], &[u8]) -> Result<bool>,
+    ) -> Result<()> {
+        if self.encrypt_keys && !prefix.is_empty() {
+            return Err(okv_err(EncryptionError::Unsupported("prefix iteration")));
+        }
+        self.inner.for_each_ref(prefix, |key, val| {
+            let key = self.decrypt_key(key)?;
+            f(&key, &self.decrypt_value(&key, val)?)
+        })
+    }
+}
+
+impl<C: DBColumnIteratorRange> DBColumnIteratorRange for EncryptedColumn<C> {
+    fn iter_range(
+        &self,
+        range: KeyRange,
+    ) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>> {
+        if self.encrypt_keys {
+            return Err(okv_err(EncryptionError::Unsupported("range iteration")));
+        }
+        Ok(self
+            .inner
+            .iter_range(range)?
+            .map(|item| self.decrypt_item(item)))
+    }
+
+    /// The keys are not encrypted if ranges are supported, so the inner column can split them.
+    fn split_ranges(&self, n: usize) -> Result<Vec<KeyRange>> {
+        if self.encrypt_keys {
+            return Err(okv_err(EncryptionError::Unsupported("range iteration")));
+        }
+        self.inner.split_ranges(n)
+    }
+}
+
+impl<C: DBColumnPage> DBColumnPage for EncryptedColumn<C> {
+    fn page(
+        &self,
+        after: Option<&[u8]>,
+        limit: usize,
+        prefix: &[u8],
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        if self.encrypt_keys {
+            return Err(okv_err(EncryptionError::Unsupported("paging")));
+        }
+        let entries = self.inner.page(after, limit, prefix)?;
+        entries
+            .into_iter()
+            .map(|(key, val)| {
+                let val = self.decrypt_value(&key, &val)?;
+                Ok((key, val))
+            })
+            .collect()
+    }
+}
+
+impl<C: DBColumnIteratorPrefix> DBColumnIteratorPrefix for EncryptedColumn<C> {
+    fn iter_prefix(
+        &self,
+        prefix: impl AsRef<[u8]>,
+    ) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>> {
+        if self.encrypt_keys {
+            return Err(okv_err(EncryptionError::Unsupported("prefix iteration")));
+        }
+        Ok(self
+            .inner
+            .iter_prefix(prefix)?
+            .map(|item| self.decrypt_item(item)))
+    }
+
+    fn keys_prefix(
+        &self,
+        prefix: impl AsRef<[u8]>,
+    ) -> Result<impl Iterator<Item = Result<Vec<u8>>>> {
+        if self.encrypt_keys {
+            return Err(okv_err(EncryptionError::Unsupported("prefix iteration")));
+        }
+        self.inner.keys_prefix(prefix)
+    }
+
+    fn count_prefix(&self, prefix: impl AsRef<[u8]>) -> Result<u64> {
+        if self.encrypt_keys {
+            return Err(okv_err(EncryptionError::Unsupported("prefix iteration")));
+        }
+        self.inner.count_prefix(prefix)
+    }
+}
@@ -0,0 +1,12 @@
+use crate::{HeedColumn, HeedTransaction};
+use okv_core::async_fallback;
+use okv_core::backend::DBColumn;
+
+async_fallback!(HeedColumn);
+
+impl<'a> okv_core::backend_async::DBColumnAsync for HeedTransaction<'a>
+where
+    HeedTransaction<'a>: okv_core::backend::DBColumn,
+{
+    okv_core::async_fallback_impl!();
+}
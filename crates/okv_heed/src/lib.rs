@@ -0,0 +1,581 @@
+//! LMDB database backend for OKV, based on [heed].
+//!
+//! Every OKV database is a named LMDB database in a single environment.
+//! LMDB only allows a single write transaction at a time, so writing to any database
+//! while a [`HeedTransaction`] is open on the same thread will block forever.
+
+use std::{
+    ops::{Bound, Deref},
+    path::Path,
+    sync::Arc,
+};
+
+use okv_core::{
+    backend::*,
+    env::Env,
+    error::{Error, Result},
+    traits::{Flushable, Innerable},
+};
+
+pub use heed;
+use heed::{
+    types::{Bytes, DecodeIgnore, Str},
+    EnvOpenOptions, RoTxn,
+};
+use self_cell::self_cell;
+
+mod r#async;
+mod tx;
+
+pub use tx::HeedTransaction;
+
+/// The default maximum size of the environment (1 GiB).
+pub const DEFAULT_MAP_SIZE: usize = 1024 * 1024 * 1024;
+
+/// The default maximum number of databases in the environment.
+pub const DEFAULT_MAX_DBS: u32 = 128;
+
+pub(crate) fn okv_err(e: heed::Error) -> Error {
+    Error::DatabaseBackend(Box::new(e))
+}
+
+pub(crate) type BytesDatabase = heed::Database<Bytes, Bytes>;
+
+/// An LMDB database backend.
+pub struct Heed {
+    env: heed::Env,
+}
+
+impl Heed {
+    /// Open or create an environment in the given directory with the default options.
+    ///
+    /// The environment can grow up to [`DEFAULT_MAP_SIZE`] bytes and hold up to
+    /// [`DEFAULT_MAX_DBS`] databases. Use [`Heed::new_with_options`] to change these limits.
+    pub fn new(path: impl AsRef<Path>) -> Result<Self, heed::Error> {
+        let mut options = EnvOpenOptions::new();
+        options.map_size(DEFAULT_MAP_SIZE).max_dbs(DEFAULT_MAX_DBS);
+        Self::new_with_options(path, options)
+    }
+
+    /// Open or create an environment in the given directory.
+    ///
+    /// The directory is created if it doesn't exist yet.
+    /// Make sure to allow enough databases with [`EnvOpenOptions::max_dbs`],
+    /// every OKV database is a named LMDB database.
+    pub fn new_with_options(
+        path: impl AsRef<Path>,
+        options: EnvOpenOptions,
+    ) -> Result<Self, heed::Error> {
+        std::fs::create_dir_all(path.as_ref())?;
+
+        // SAFETY: LMDB environments must not be opened more than once in the same process,
+        // which is the responsibility of the caller just like with any other on-disk backend.
+        let env = unsafe { options.open(path)? };
+        Ok(Self { env })
+    }
+}
+
+impl Innerable for Heed {
+    type Inner = heed::Env;
+    fn inner(&self) -> &Self::Inner {
+        &self.env
+    }
+}
+
+impl Flushable for Heed {
+    fn flush(&self) -> Result<()> {
+        self.env.force_sync().map_err(okv_err)
+    }
+}
+
+impl DatabaseBackend for Heed {
+    type Column = HeedColumn;
+
+    fn create_or_open(env: Env<Self>, db: &str) -> Result<Self::Column> {
+        let mut tx = env.db().env.write_txn().map_err(okv_err)?;
+        let database = env
+            .db()
+            .env
+            .create_database(&mut tx, Some(db))
+            .map_err(okv_err)?;
+        tx.commit().map_err(okv_err)?;
+
+        Ok(HeedColumn { env, database })
+    }
+
+    /// Empty databases are not listed, see [`Heed::drop_database`](DatabaseBackend::drop_database).
+    fn list_databases(&self) -> Result<Vec<String>> {
+        let tx = self.env.read_txn().map_err(okv_err)?;
+        let main: Option<heed::Database<Str, DecodeIgnore>> =
+            self.env.open_database(&tx, None).map_err(okv_err)?;
+        let Some(main) = main else {
+            return Ok(Vec::new());
+        };
+
+        let mut names = Vec::new();
+        for item in main.iter(&tx).map_err(okv_err)? {
+            let (name, ()) = item.map_err(okv_err)?;
+            let database: Option<BytesDatabase> =
+                self.env.open_database(&tx, Some(name)).map_err(okv_err)?;
+            if let Some(database) = database {
+                if !database.is_empty(&tx).map_err(okv_err)? {
+                    names.push(name.to_string());
+                }
+            }
+        }
+
+        // committing keeps the database handles opened in the transaction
+        tx.commit().map_err(okv_err)?;
+        Ok(names)
+    }
+
+    /// heed can't delete named databases, so the database is cleared instead.
+    fn drop_database(&self, db: &str) -> Result<()> {
+        let mut tx = self.env.write_txn().map_err(okv_err)?;
+        let database: Option<BytesDatabase> =
+            self.env.open_database(&tx, Some(db)).map_err(okv_err)?;
+        if let Some(database) = database {
+            database.clear(&mut tx).map_err(okv_err)?;
+        }
+        tx.commit().map_err(okv_err)
+    }
+
+    /// Moves all entries to the new database in a single transaction.
+    fn rename_database(&self, old: &str, new: &str) -> Result<()> {
+        let mut tx = self.env.write_txn().map_err(okv_err)?;
+        let source: Option<BytesDatabase> =
+            self.env.open_database(&tx, Some(old)).map_err(okv_err)?;
+        let Some(source) = source else {
+            return Err(Error::DatabaseNotFound { db: old.into() });
+        };
+        let target: BytesDatabase = self
+            .env
+            .create_database(&mut tx, Some(new))
+            .map_err(okv_err)?;
+
+        let entries = source
+            .iter(&tx)
+            .map_err(okv_err)?
+            .map(|item| item.map(|(k, v)| (k.to_vec(), v.to_vec())))
+            .collect::<heed::Result<Vec<_>>>()
+            .map_err(okv_err)?;
+        for (key, val) in entries {
+            target.put(&mut tx, &key, &val).map_err(okv_err)?;
+        }
+
+        source.clear(&mut tx).map_err(okv_err)?;
+        tx.commit().map_err(okv_err)
+    }
+}
+
+/// A named LMDB database.
+pub struct HeedColumn {
+    pub(crate) env: Env<Heed>,
+    pub(crate) database: BytesDatabase,
+}
+
+impl HeedColumn {
+    fn heed(&self) -> &heed::Env {
+        &self.env.db().env
+    }
+
+    /// A read transaction that owns a handle to the environment, so it can outlive this column.
+    fn static_read_txn(&self) -> Result<RoTxn<'static>> {
+        self.heed().clone().static_read_txn().map_err(okv_err)
+    }
+
+    /// Returns the number of entries in the database.
+    pub fn len(&self) -> Result<u64> {
+        let tx = self.heed().read_txn().map_err(okv_err)?;
+        self.database.len(&tx).map_err(okv_err)
+    }
+
+    /// Returns `true` if the database contains no entries.
+    pub fn is_empty(&self) -> Result<bool> {
+        let tx = self.heed().read_txn().map_err(okv_err)?;
+        self.database.is_empty(&tx).map_err(okv_err)
+    }
+}
+
+impl Flushable for HeedColumn {
+    fn flush(&self) -> Result<()> {
+        self.env.db().flush()
+    }
+}
+
+impl DBColumn for HeedColumn {
+    fn set(&self, key: impl AsRef<[u8]>, val: impl AsRef<[u8]>) -> Result<()> {
+        let mut tx = self.heed().write_txn().map_err(okv_err)?;
+        self.database
+            .put(&mut tx, key.as_ref(), val.as_ref())
+            .map_err(okv_err)?;
+        tx.commit().map_err(okv_err)
+    }
+
+    fn set_nx(&self, key: impl AsRef<[u8]>, val: impl AsRef<[u8]>) -> Result<bool> {
+        let mut tx = self.heed().write_txn().map_err(okv_err)?;
+        let inserted = self
+            .database
+            .put_with_flags(
+                &mut tx,
+                heed::PutFlags::NO_OVERWRITE,
+                key.as_ref(),
+                val.as_ref(),
+            )
+            .map(|_| true)
+            .or_else(|e| match e {
+                heed::Error::Mdb(heed::MdbError::KeyExist) => Ok(false),
+                e => Err(okv_err(e)),
+            })?;
+
+        match inserted {
+            true => tx.commit().map_err(okv_err)?,
+            false => tx.abort(),
+        }
+        Ok(inserted)
+    }
+
+    fn get(&self, key: impl AsRef<[u8]>) -> Result<Option<Vec<u8>>> {
+        let tx = self.heed().read_txn().map_err(okv_err)?;
+        let res = self.database.get(&tx, key.as_ref()).map_err(okv_err)?;
+        Ok(res.map(<[u8]>::to_vec))
+    }
+
+    fn get_multi<I>(&self, keys: I) -> Result<Vec<Option<Vec<u8>>>>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let tx = self.heed().read_txn().map_err(okv_err)?;
+        keys.into_iter()
+            .map(|key| {
+                let res = self.database.get(&tx, key.as_ref()).map_err(okv_err)?;
+                Ok(res.map(<[u8]>::to_vec))
+            })
+            .collect()
+    }
+
+    fn delete(&self, key: impl AsRef<[u8]>) -> Result<()> {
+        let mut tx = self.heed().write_txn().map_err(okv_err)?;
+        self.database
+            .delete(&mut tx, key.as_ref())
+            .map_err(okv_err)?;
+        tx.commit().map_err(okv_err)
+    }
+
+    fn contains(&self, key: impl AsRef<[u8]>) -> Result<bool> {
+        let tx = self.heed().read_txn().map_err(okv_err)?;
+        let res = self.database.get(&tx, key.as_ref()).map_err(okv_err)?;
+        Ok(res.is_some())
+    }
+}
+
+impl DBColumnClear for HeedColumn {
+    fn clear(&self) -> Result<()> {
+        let mut tx = self.heed().write_txn().map_err(okv_err)?;
+        self.database.clear(&mut tx).map_err(okv_err)?;
+        tx.commit().map_err(okv_err)
+    }
+}
+
+impl DBColumnBatch for HeedColumn {
+    fn set_multi<I, K, V>(&self, entries: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        let mut tx = self.heed().write_txn().map_err(okv_err)?;
+        for (key, val) in entries {
+            self.database
+                .put(&mut tx, key.as_ref(), val.as_ref())
+                .map_err(okv_err)?;
+        }
+        tx.commit().map_err(okv_err)
+    }
+
+    fn write_multi<I, K, V>(&self, writes: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (K, Option<V>)>,
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        let mut tx = self.heed().write_txn().map_err(okv_err)?;
+        for (key, val) in writes {
+            match val {
+                Some(val) => self.database.put(&mut tx, key.as_ref(), val.as_ref()),
+                None => self.database.delete(&mut tx, key.as_ref()).map(|_| ()),
+            }
+            .map_err(okv_err)?;
+        }
+        tx.commit().map_err(okv_err)
+    }
+}
+
+impl DBColumnDeleteRange for HeedColumn {
+    fn delete_range(&self, start: &[u8], end: Option<&[u8]>) -> Result<Option<u64>> {
+        let mut tx = self.heed().write_txn().map_err(okv_err)?;
+        let deleted = self.delete_range_in(&mut tx, start, end)?;
+        tx.commit().map_err(okv_err)?;
+        Ok(Some(deleted))
+    }
+}
+
+impl HeedColumn {
+    /// Delete the keys from `start` to `end` in a write transaction.
+    pub(crate) fn delete_range_in(
+        &self,
+        tx: &mut heed::RwTxn<'_>,
+        start: &[u8],
+        end: Option<&[u8]>,
+    ) -> Result<u64> {
+        let range = (
+            start_bound(Bound::Included(start)),
+            end.map_or(Bound::Unbounded, Bound::Excluded),
+        );
+        let deleted = self.database.delete_range(tx, &range).map_err(okv_err)?;
+        Ok(deleted as u64)
+    }
+}
+
+/// A read transaction shared by all values read in it.
+struct SharedTxn(RoTxn<'static>);
+
+// SAFETY: the environment is opened without thread local storage (`read-txn-no-tls`), so
+// read transactions are not bound to the thread that created them. A shared transaction is
+// only used to read values when they are created and to abort it when the last value is dropped.
+unsafe impl Send for SharedTxn {}
+unsafe impl Sync for SharedTxn {}
+
+type Slice<'txn> = &'txn [u8];
+
+self_cell!(
+    struct HeedRefInner {
+        owner: Arc<SharedTxn>,
+
+        #[covariant]
+        dependent: Slice,
+    }
+);
+
+/// A value borrowed from the memory map of the environment.
+///
+/// The value is valid as long as the read transaction it was read in, which is
+/// kept alive until the last value read in it is dropped.
+pub struct HeedRef(HeedRefInner);
+
+impl HeedRef {
+    fn get(tx: &Arc<SharedTxn>, db: BytesDatabase, key: &[u8]) -> Result<Option<Self>> {
+        let res = HeedRefInner::try_new(tx.clone(), |tx| match db.get(&tx.0, key) {
+            Ok(Some(val)) => Ok(val),
+            Ok(None) => Err(None),
+            Err(e) => Err(Some(okv_err(e))),
+        });
+
+        match res {
+            Ok(inner) => Ok(Some(Self(inner))),
+            Err(None) => Ok(None),
+            Err(Some(e)) => Err(e),
+        }
+    }
+}
+
+impl Deref for HeedRef {
+    type Target = [u8];
+    fn deref(&self) -> &Self::Target {
+        self.0.borrow_dependent()
+    }
+}
+
+impl AsRef<[u8]> for HeedRef {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl<'c> DBColumnRef<'c> for HeedColumn {
+    type Ref = HeedRef;
+
+    fn get_ref(&'c self, key: impl AsRef<[u8]>) -> Result<Option<Self::Ref>> {
+        let tx = Arc::new(SharedTxn(self.static_read_txn()?));
+        HeedRef::get(&tx, self.database, key.as_ref())
+    }
+}
+
+impl<'c> DBColumnRefBatch<'c> for HeedColumn {
+    type Ref = HeedRef;
+
+    /// All values are read in the same transaction.
+    fn get_multi_ref<'a, K, I>(&'c self, keys: I) -> Result<Vec<Option<Self::Ref>>>
+    where
+        K: AsRef<[u8]> + 'a + ?Sized,
+        I: IntoIterator<Item = &'a K>,
+    {
+        let tx = Arc::new(SharedTxn(self.static_read_txn()?));
+        keys.into_iter()
+            .map(|key| HeedRef::get(&tx, self.database, key.as_ref()))
+            .collect()
+    }
+}
+
+type BytesIter<'txn> = Box<dyn Iterator<Item = heed::Result<(&'txn [u8], &'txn [u8])>> + 'txn>;
+
+self_cell!(
+    struct HeedIterInner {
+        owner: RoTxn<'static>,
+
+        #[not_covariant]
+        dependent: BytesIter,
+    }
+);
+
+impl HeedColumn {
+    /// Iterate over the entries whose key starts with `prefix`. LMDB rejects empty keys, so
+    /// an empty prefix iterates over all entries instead.
+    fn prefix_iter<'txn>(&self, tx: &'txn RoTxn<'_>, prefix: &[u8]) -> Result<BytesIter<'txn>> {
+        Ok(match prefix.is_empty() {
+            true => Box::new(self.database.iter(tx).map_err(okv_err)?),
+            false => Box::new(self.database.prefix_iter(tx, prefix).map_err(okv_err)?),
+        })
+    }
+}
+
+/// Iterates over a database in its own read transaction.
+struct HeedIter(HeedIterInner);
+
+impl Iterator for HeedIter {
+    type Item = Result<(Vec<u8>, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.with_dependent_mut(|_, iter| {
+            let item = iter.next()?;
+            Some(item.map(|(k, v)| (k.to_vec(), v.to_vec())).map_err(okv_err))
+        })
+    }
+}
+
+impl DBColumnIterator for HeedColumn {
+    fn iter(&self) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>> {
+        let db = self.database;
+        let inner = HeedIterInner::try_new(self.static_read_txn()?, |tx| {
+            let iter = db.iter(tx).map_err(okv_err)?;
+            Result::<BytesIter<'_>>::Ok(Box::new(iter))
+        })?;
+        Ok(HeedIter(inner))
+    }
+
+    fn count(&self) -> Result<u64> {
+        let tx = self.heed().read_txn().map_err(okv_err)?;
+        self.database.len(&tx).map_err(okv_err)
+    }
+}
+
+impl DBColumnStats for HeedColumn {
+    /// The disk size is the number of pages used by the database times the page size.
+    fn stats(&self) -> Result<ColumnStats> {
+        let tx = self.heed().read_txn().map_err(okv_err)?;
+        let stat = self.database.stat(&tx).map_err(okv_err)?;
+
+        let pages = stat.branch_pages + stat.leaf_pages + stat.overflow_pages;
+        let mut stats = ColumnStats::new(KeyCount::Exact(stat.entries as u64));
+        stats.disk_bytes = Some(pages as u64 * stat.page_size as u64);
+        stats.properties = [
+            ("page_size", stat.page_size as usize),
+            ("depth", stat.depth as usize),
+            ("branch_pages", stat.branch_pages),
+            ("leaf_pages", stat.leaf_pages),
+            ("overflow_pages", stat.overflow_pages),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+        Ok(stats)
+    }
+}
+
+impl DBColumnIteratorRef for HeedColumn {
+    /// Keys and values point into the memory map, entries are visited in a single read
+    /// transaction.
+    fn for_each_ref(
+        &self,
+        prefix: &[u8],
+        mut f: impl FnMut(&[u8], &[u8]) -> Result<bool>,
+    ) -> Result<()> {
+        let tx = self.heed().read_txn().map_err(okv_err)?;
+        for item in self.prefix_iter(&tx, prefix)? {
+            let (k, v) = item.map_err(okv_err)?;
+            if !f(k, v)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A range that starts at the empty key starts at the first key, see [`HeedColumn::prefix_iter`].
+fn start_bound(bound: Bound<&[u8]>) -> Bound<&[u8]> {
+    match bound {
+        Bound::Included([]) => Bound::Unbounded,
+        bound => bound,
+    }
+}
+
+impl DBColumnIteratorRange for HeedColumn {
+    fn iter_range(
+        &self,
+        range: KeyRange,
+    ) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>> {
+        let db = self.database;
+        let bounds = (
+            start_bound(Bound::Included(range.start.as_slice())),
+            range
+                .end
+                .as_deref()
+                .map_or(Bound::Unbounded, Bound::Excluded),
+        );
+        let inner = HeedIterInner::try_new(self.static_read_txn()?, |tx| {
+            let iter = db.range(tx, &bounds).map_err(okv_err)?;
+            Result::<BytesIter<'_>>::Ok(Box::new(iter))
+        })?;
+        Ok(HeedIter(inner))
+    }
+}
+
+impl DBColumnPage for HeedColumn {
+    fn page(
+        &self,
+        after: Option<&[u8]>,
+        limit: usize,
+        prefix: &[u8],
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let tx = self.heed().read_txn().map_err(okv_err)?;
+        let range = (start_bound(page_start(after, prefix)), Bound::Unbounded);
+
+        let mut entries = Vec::new();
+        for item in self
+            .database
+            .range(&tx, &range)
+            .map_err(okv_err)?
+            .take(limit)
+        {
+            let (k, v) = item.map_err(okv_err)?;
+            if !k.starts_with(prefix) {
+                break;
+            }
+            entries.push((k.to_vec(), v.to_vec()));
+        }
+        Ok(entries)
+    }
+}
+
+impl DBColumnIteratorPrefix for HeedColumn {
+    fn iter_prefix(
+        &self,
+        prefix: impl AsRef<[u8]>,
+    ) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>> {
+        let inner = HeedIterInner::try_new(self.static_read_txn()?, |tx| {
+            self.prefix_iter(tx, prefix.as_ref())
+        })?;
+        Ok(HeedIter(inner))
+    }
+}
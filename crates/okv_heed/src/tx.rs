@@ -0,0 +1,106 @@
+use std::cell::RefCell;
+
+use heed::RwTxn;
+use okv_core::{
+    backend::{DBColumn, DBColumnDeleteRange, DBColumnTransaction, DBTransaction},
+    error::Result,
+};
+
+use crate::{okv_err, HeedColumn};
+
+/// A write transaction on a single database.
+///
+/// Reads see the writes of this transaction. Until the transaction is committed or
+/// rolled back, all other writes to the environment block.
+pub struct HeedTransaction<'a> {
+    column: &'a HeedColumn,
+    tx: RefCell<RwTxn<'a>>,
+}
+
+impl<'a> DBColumnTransaction<'a> for HeedColumn {
+    type Txn = HeedTransaction<'a>;
+
+    fn transaction(&'a self) -> Result<Self::Txn> {
+        let tx = self.heed().write_txn().map_err(okv_err)?;
+        Ok(HeedTransaction {
+            column: self,
+            tx: RefCell::new(tx),
+        })
+    }
+}
+
+impl<'a> DBColumn for HeedTransaction<'a> {
+    fn set(&self, key: impl AsRef<[u8]>, val: impl AsRef<[u8]>) -> Result<()> {
+        let mut tx = self.tx.borrow_mut();
+        self.column
+            .database
+            .put(&mut tx, key.as_ref(), val.as_ref())
+            .map_err(okv_err)
+    }
+
+    fn get(&self, key: impl AsRef<[u8]>) -> Result<Option<Vec<u8>>> {
+        let tx = self.tx.borrow();
+        let res = self
+            .column
+            .database
+            .get(&tx, key.as_ref())
+            .map_err(okv_err)?;
+        Ok(res.map(<[u8]>::to_vec))
+    }
+
+    fn get_multi<I>(&self, keys: I) -> Result<Vec<Option<Vec<u8>>>>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let tx = self.tx.borrow();
+        keys.into_iter()
+            .map(|key| {
+                let res = self
+                    .column
+                    .database
+                    .get(&tx, key.as_ref())
+                    .map_err(okv_err)?;
+                Ok(res.map(<[u8]>::to_vec))
+            })
+            .collect()
+    }
+
+    fn delete(&self, key: impl AsRef<[u8]>) -> Result<()> {
+        let mut tx = self.tx.borrow_mut();
+        self.column
+            .database
+            .delete(&mut tx, key.as_ref())
+            .map_err(okv_err)?;
+        Ok(())
+    }
+
+    fn contains(&self, key: impl AsRef<[u8]>) -> Result<bool> {
+        let tx = self.tx.borrow();
+        let res = self
+            .column
+            .database
+            .get(&tx, key.as_ref())
+            .map_err(okv_err)?;
+        Ok(res.is_some())
+    }
+}
+
+impl<'a> DBColumnDeleteRange for HeedTransaction<'a> {
+    fn delete_range(&self, start: &[u8], end: Option<&[u8]>) -> Result<Option<u64>> {
+        let mut tx = self.tx.borrow_mut();
+        let deleted = self.column.delete_range_in(&mut tx, start, end)?;
+        Ok(Some(deleted))
+    }
+}
+
+impl<'a> DBTransaction for HeedTransaction<'a> {
+    fn commit(self) -> Result<()> {
+        self.tx.into_inner().commit().map_err(okv_err)
+    }
+
+    fn rollback(self) -> Result<()> {
+        self.tx.into_inner().abort();
+        Ok(())
+    }
+}
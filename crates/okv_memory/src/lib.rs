@@ -1,41 +1,107 @@
-use dashmap::{try_result::TryResult, DashMap};
+use dashmap::DashMap;
 use okv_core::{
     backend::*,
     env::Env,
     error::{Error, Result},
     traits::*,
 };
-use self_cell::self_cell;
+use std::{path::Path, sync::Arc};
 
 use okv_core::async_fallback;
 use okv_core::backend::DBColumn;
 
+mod persist;
+pub use persist::PersistConfig;
+use persist::{Op, Persistence};
+
 async_fallback!(MemDBColumn);
 
+pub(crate) fn okv_err(e: std::io::Error) -> Error {
+    Error::DatabaseBackend(Box::new(e))
+}
+
 /// An in-memory database backend.
 /// This is useful for testing and prototyping.
+///
+/// By default, all data is lost when the database is dropped. Databases opened with
+/// [`MemDB::open`] are persisted to a snapshot file and a write-ahead log, see
+/// [`PersistConfig`] for details.
+///
+/// Clones share the same data.
 #[derive(Clone)]
 pub struct MemDB {
-    columns: DashMap<String, DashMap<Vec<u8>, Vec<u8>>>,
+    columns: Arc<DashMap<String, MemDBMap>>,
+    persist: Option<Arc<Persistence>>,
 }
 
+/// Values are reference counted, so they can be returned by [`DBColumnRef::get_ref`]
+/// without copying them or holding a lock.
+type MemDBMap = Arc<DashMap<Vec<u8>, Arc<[u8]>>>;
+
 impl MemDB {
     /// Create a new in-memory database backend.
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Open a persistent in-memory database backend with the default configuration.
+    ///
+    /// The snapshot is stored at `path` and the write-ahead log at `path` with an
+    /// additional `.wal` extension. Both are created if they don't exist yet.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::open_with_config(path, PersistConfig::default())
+    }
+
+    /// Open a persistent in-memory database backend.
+    ///
+    /// All data is loaded into memory on open. Every write is appended to the write-ahead
+    /// log, and a snapshot is taken on [`flush`](Flushable::flush) or once the log grows
+    /// beyond [`PersistConfig::snapshot_after`].
+    pub fn open_with_config(path: impl AsRef<Path>, config: PersistConfig) -> Result<Self> {
+        let columns = Arc::new(DashMap::new());
+        let persist = Persistence::open(path.as_ref(), config, columns.clone()).map_err(okv_err)?;
+
+        Ok(Self {
+            columns,
+            persist: Some(Arc::new(persist)),
+        })
+    }
+}
+
+impl MemDB {
+    /// Apply a change to the databases, logging it first if the database is persistent.
+    fn write(&self, op: Op<'_>, apply: impl FnOnce()) -> Result<()> {
+        match &self.persist {
+            Some(persist) => persist.write(op, apply).map_err(okv_err),
+            None => {
+                apply();
+                Ok(())
+            }
+        }
+    }
 }
 
 impl Default for MemDB {
     fn default() -> Self {
         Self {
-            columns: DashMap::new(),
+            columns: Arc::new(DashMap::new()),
+            persist: None,
+        }
+    }
+}
+
+impl Flushable for MemDB {
+    /// Take a snapshot of all databases if the database is persistent.
+    fn flush(&self) -> Result<()> {
+        match &self.persist {
+            Some(persist) => persist.snapshot().map_err(okv_err),
+            None => Ok(()),
         }
     }
 }
 
 impl Innerable for MemDB {
-    type Inner = DashMap<String, DashMap<Vec<u8>, Vec<u8>>>;
+    type Inner = DashMap<String, MemDBMap>;
     fn inner(&self) -> &Self::Inner {
         &self.columns
     }
@@ -45,62 +111,127 @@ impl DatabaseBackend for MemDB {
     type Column = MemDBColumn;
 
     fn create_or_open(env: Env<MemDB>, name: &str) -> Result<Self::Column> {
-        let col = MemDBColumn::try_new(env.clone(), |backend| {
-            match backend.db().columns.try_get(name) {
-                TryResult::Absent => {
-                    let col = DashMap::new();
-                    backend.db().columns.insert(name.to_owned(), col);
-                    let col = backend
-                        .db()
-                        .columns
-                        .try_get(name)
-                        .try_unwrap()
-                        .expect("Newly inserted column should not be locked");
-                    Ok(col)
-                }
-                TryResult::Present(col) => Ok(col),
-                TryResult::Locked => Err(Error::DatabaseNotFound {
-                    db: name.to_string(),
-                }),
+        let db = env.db();
+        // log the creation, so empty databases still exist after a restart
+        if !db.columns.contains_key(name) {
+            db.write(Op::Create(name), || {
+                db.columns.entry(name.to_owned()).or_default();
+            })?;
+        }
+
+        // columns only hold a reference to their map and not a guard into `columns`,
+        // holding a guard would deadlock when a database in the same shard is created
+        let map = db.columns.entry(name.to_owned()).or_default().clone();
+        Ok(MemDBColumn {
+            name: name.to_owned(),
+            map,
+            persist: db.persist.clone(),
+        })
+    }
+
+    fn list_databases(&self) -> Result<Vec<String>> {
+        Ok(self.columns.iter().map(|c| c.key().clone()).collect())
+    }
+
+    fn exists(&self, db: &str) -> Result<bool> {
+        Ok(self.columns.contains_key(db))
+    }
+
+    /// Columns of the database that are still open keep their entries, but are detached
+    /// from the backend.
+    fn drop_database(&self, db: &str) -> Result<()> {
+        self.write(Op::Drop(db), || {
+            self.columns.remove(db);
+        })
+    }
+
+    fn rename_database(&self, old: &str, new: &str) -> Result<()> {
+        self.write(Op::Rename(old, new), || {
+            if let Some((_, map)) = self.columns.remove(old) {
+                self.columns.insert(new.to_owned(), map);
             }
-        })?;
+        })
+    }
 
-        Ok(col)
+    /// Nothing to compact in memory, a persistent database takes a snapshot, which
+    /// replaces the write-ahead log.
+    fn compact(&self) -> Result<()> {
+        self.flush()
     }
-}
 
-type MemDBColumnInner<'a> = dashmap::mapref::one::Ref<'a, String, DashMap<Vec<u8>, Vec<u8>>>;
+    /// Snapshots and logs are checked when they are loaded, so there is nothing to check.
+    fn check_integrity(&self) -> Result<bool> {
+        Ok(true)
+    }
+}
 
-self_cell!(
-    /// A column in an in-memory database.
-    pub struct MemDBColumn {
-        owner: Env<MemDB>,
+/// A column in an in-memory database.
+pub struct MemDBColumn {
+    name: String,
+    map: MemDBMap,
+    persist: Option<Arc<Persistence>>,
+}
 
-        #[covariant]
-        dependent: MemDBColumnInner,
+impl MemDBColumn {
+    /// Apply a write, logging it first if the database is persistent.
+    fn write(&self, op: Op<'_>, apply: impl FnOnce()) -> Result<()> {
+        match &self.persist {
+            Some(persist) => persist.write(op, apply).map_err(okv_err),
+            None => {
+                apply();
+                Ok(())
+            }
+        }
     }
-);
+}
 
 impl DBColumnClear for MemDBColumn {
     fn clear(&self) -> Result<()> {
-        self.borrow_dependent().clear();
-        Ok(())
+        self.write(Op::Clear(&self.name), || self.map.clear())
+    }
+}
+
+/// Returns whether `key` is in the range from `start` (inclusive) to `end` (exclusive).
+pub(crate) fn in_range(key: &[u8], start: &[u8], end: Option<&[u8]>) -> bool {
+    key >= start && end.map_or(true, |end| key < end)
+}
+
+impl DBColumnDeleteRange for MemDBColumn {
+    fn delete_range(&self, start: &[u8], end: Option<&[u8]>) -> Result<Option<u64>> {
+        let mut deleted = 0;
+        self.write(Op::DeleteRange(&self.name, start, end), || {
+            self.map.retain(|key, _| {
+                let delete = in_range(key, start, end);
+                deleted += delete as u64;
+                !delete
+            })
+        })?;
+        Ok(Some(deleted))
     }
 }
 
-impl Flushable for MemDBColumn {}
+impl Flushable for MemDBColumn {
+    /// Take a snapshot of all databases if the database is persistent.
+    fn flush(&self) -> Result<()> {
+        match &self.persist {
+            Some(persist) => persist.snapshot().map_err(okv_err),
+            None => Ok(()),
+        }
+    }
+}
 
 impl DBColumn for MemDBColumn {
     fn set(&self, key: impl AsRef<[u8]>, val: impl AsRef<[u8]>) -> Result<()> {
-        self.borrow_dependent()
-            .insert(key.as_ref().to_vec(), val.as_ref().to_vec());
-        Ok(())
+        let (key, val) = (key.as_ref(), val.as_ref());
+        self.write(Op::Set(&self.name, key, val), || {
+            self.map.insert(key.to_vec(), val.into());
+        })
     }
 
     fn get(&self, key: impl AsRef<[u8]>) -> Result<Option<Vec<u8>>> {
-        match self.borrow_dependent().get(&key.as_ref().to_vec()) {
+        match self.map.get(&key.as_ref().to_vec()) {
             None => Ok(None),
-            Some(val) => Ok(Some(val.value().clone())),
+            Some(val) => Ok(Some(val.value().to_vec())),
         }
     }
 
@@ -117,23 +248,190 @@ impl DBColumn for MemDBColumn {
     }
 
     fn contains(&self, key: impl AsRef<[u8]>) -> Result<bool> {
-        Ok(self.borrow_dependent().contains_key(&key.as_ref().to_vec()))
+        Ok(self.map.contains_key(&key.as_ref().to_vec()))
     }
 
     fn delete(&self, key: impl AsRef<[u8]>) -> Result<()> {
-        self.borrow_dependent().remove(&key.as_ref().to_vec());
+        let key = key.as_ref();
+        self.write(Op::Delete(&self.name, key), || {
+            self.map.remove(key);
+        })
+    }
+}
+
+impl DBColumnBatch for MemDBColumn {
+    /// The pairs are set one by one, other threads may see some of them before all are set.
+    fn set_multi<I, K, V>(&self, entries: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        for (key, val) in entries {
+            self.set(key, val)?;
+        }
         Ok(())
     }
+
+    /// The writes are applied one by one, like [`set_multi`](DBColumnBatch::set_multi).
+    fn write_multi<I, K, V>(&self, writes: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (K, Option<V>)>,
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        for (key, val) in writes {
+            match val {
+                Some(val) => self.set(key, val)?,
+                None => self.delete(key)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'c> DBColumnRef<'c> for MemDBColumn {
+    type Ref = Arc<[u8]>;
+
+    fn get_ref(&'c self, key: impl AsRef<[u8]>) -> Result<Option<Self::Ref>> {
+        Ok(self.map.get(key.as_ref()).map(|val| val.value().clone()))
+    }
+}
+
+impl<'c> DBColumnRefBatch<'c> for MemDBColumn {
+    type Ref = Arc<[u8]>;
+
+    fn get_multi_ref<'a, K, I>(&'c self, keys: I) -> Result<Vec<Option<Self::Ref>>>
+    where
+        K: AsRef<[u8]> + 'a + ?Sized,
+        I: IntoIterator<Item = &'a K>,
+    {
+        keys.into_iter()
+            .map(|key| self.get_ref(key.as_ref()))
+            .collect()
+    }
 }
 
 impl DBColumnIterator for MemDBColumn {
     fn iter(&self) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>> {
         Ok(self
-            .borrow_dependent()
+            .map
             .iter()
-            .map(|item| (item.key().clone(), item.value().clone()))
+            .map(|item| (item.key().clone(), item.value().to_vec()))
             .map(Ok))
     }
+
+    fn keys(&self) -> Result<impl Iterator<Item = Result<Vec<u8>>>> {
+        Ok(self.map.iter().map(|item| Ok(item.key().clone())))
+    }
+
+    fn count(&self) -> Result<u64> {
+        Ok(self.map.len() as u64)
+    }
+}
+
+impl DBColumnCompact for MemDBColumn {
+    /// Does nothing, entries are only stored in memory.
+    fn compact_range(&self, _range: KeyRange) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl DBColumnStats for MemDBColumn {
+    /// The memory size only counts the bytes of keys and values, not the map itself.
+    fn stats(&self) -> Result<ColumnStats> {
+        let bytes = self
+            .map
+            .iter()
+            .map(|item| (item.key().len() + item.value().len()) as u64)
+            .sum();
+        let mut stats = ColumnStats::new(KeyCount::Exact(self.map.len() as u64));
+        stats.memory_bytes = Some(bytes);
+        Ok(stats)
+    }
+}
+
+impl DBColumnIteratorRef for MemDBColumn {
+    /// Entries are visited in no particular order, while holding a read lock on their shard.
+    /// Writing to the same database from `f` deadlocks.
+    fn for_each_ref(
+        &self,
+        prefix: &[u8],
+        mut f: impl FnMut(&[u8], &[u8]) -> Result<bool>,
+    ) -> Result<()> {
+        for item in self.map.iter() {
+            if item.key().starts_with(prefix) && !f(item.key(), item.value())? {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl DBColumnIteratorRange for MemDBColumn {
+    /// Entries are not stored in order, so every range scans all entries and sorts the
+    /// entries in the range.
+    fn iter_range(
+        &self,
+        range: KeyRange,
+    ) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>> {
+        let mut entries: Vec<_> = self
+            .map
+            .iter()
+            .filter(|item| range.contains(item.key()))
+            .map(|item| (item.key().clone(), item.value().to_vec()))
+            .collect();
+        entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        Ok(entries.into_iter().map(Ok))
+    }
+
+    /// Entries are not stored in order, so the keys are sorted to find the boundaries.
+    fn split_ranges(&self, n: usize) -> Result<Vec<KeyRange>> {
+        let mut keys: Vec<_> = self.map.iter().map(|item| item.key().clone()).collect();
+        keys.sort_unstable();
+        let step = keys.len().div_ceil(n.max(1)).max(1);
+        Ok(split_at_keys(keys.into_iter().skip(step).step_by(step)))
+    }
+}
+
+impl DBColumnPage for MemDBColumn {
+    /// Entries are not stored in order, so every page selects the first `limit` keys with
+    /// the prefix before copying their values.
+    fn page(
+        &self,
+        after: Option<&[u8]>,
+        limit: usize,
+        prefix: &[u8],
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut keys: Vec<_> = self
+            .map
+            .iter()
+            .filter(|item| {
+                let key = item.key().as_slice();
+                key.starts_with(prefix) && after.map_or(true, |after| key > after)
+            })
+            .map(|item| item.key().clone())
+            .collect();
+
+        if keys.len() > limit {
+            keys.select_nth_unstable(limit - 1);
+            keys.truncate(limit);
+        }
+        keys.sort_unstable();
+
+        // entries deleted since the keys were collected are skipped
+        Ok(keys
+            .into_iter()
+            .filter_map(|key| {
+                let val = self.map.get(&key)?.to_vec();
+                Some((key, val))
+            })
+            .collect())
+    }
 }
 
 impl DBColumnIteratorPrefix for MemDBColumn {
@@ -143,12 +441,26 @@ impl DBColumnIteratorPrefix for MemDBColumn {
     ) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>> {
         let prefix = prefix.as_ref().to_vec();
         let iter = self
-            .borrow_dependent()
+            .map
             .iter()
             .filter(move |item| item.key().starts_with(&prefix))
-            .map(|item| (item.key().clone(), item.value().clone()))
+            .map(|item| (item.key().clone(), item.value().to_vec()))
             .map(Ok);
 
         Ok(iter)
     }
+
+    fn keys_prefix(
+        &self,
+        prefix: impl AsRef<[u8]>,
+    ) -> Result<impl Iterator<Item = Result<Vec<u8>>>> {
+        let prefix = prefix.as_ref().to_vec();
+        let iter = self
+            .map
+            .iter()
+            .filter(move |item| item.key().starts_with(&prefix))
+            .map(|item| Ok(item.key().clone()));
+
+        Ok(iter)
+    }
 }
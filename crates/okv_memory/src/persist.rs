@@ -0,0 +1,487 @@
+//! Snapshot and write-ahead log persistence for [`MemDB`](crate::MemDB).
+//!
+//! A persistent database consists of two files: the snapshot at the given path, and a
+//! write-ahead log next to it with an additional `.wal` extension.
+//!
+//! Every write is appended to the log before it is applied in memory.
+//! Taking a snapshot writes all databases to a temporary file, atomically replaces the
+//! previous snapshot with it and starts a new log. On open, the snapshot is loaded and
+//! the log is replayed on top of it. A partially written record at the end of the log,
+//! e.g. after a crash, is discarded. A damaged record before the end of the log, or a log
+//! with an unknown header, fails to open instead, because discarding it would also discard
+//! the valid records after it.
+//!
+//! Every snapshot has a generation, which is one higher than the one of the previous
+//! snapshot, and the log stores the generation of the snapshot it continues. Replaying
+//! writes twice is not harmless (e.g. renaming a database again after another database
+//! was created with the old name), so a log that is left over from an older generation
+//! after a crash is discarded instead of replayed.
+//!
+//! Both files store integers in little endian and are protected by CRC32 checksums.
+//!
+//! Snapshot: `OKVMEM` magic, version byte, generation (`u64`), database count (`u32`),
+//! every database as name (`u32` length + bytes), entry count (`u64`) and entries
+//! (`u32` length + key, `u32` length + value), followed by the checksum of everything
+//! before it (`u32`).
+//!
+//! Log: `OKVWAL` magic and generation (`u64`), followed by the records.
+//!
+//! Log record: body length (`u32`), checksum of the body (`u32`) and the body, which is
+//! the operation (`u8`), the database name and, depending on the operation, key and value
+//! or the new name of the database (each `u32` length + bytes). Range deletes store the
+//! start key, a byte that is `1` if the range has an end and the end key.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use dashmap::DashMap;
+
+use crate::MemDBMap;
+
+const SNAPSHOT_MAGIC: &[u8; 6] = b"OKVMEM";
+const SNAPSHOT_VERSION: u8 = 2;
+
+const WAL_MAGIC: &[u8; 6] = b"OKVWAL";
+const WAL_HEADER_LEN: u64 = WAL_MAGIC.len() as u64 + 8;
+
+const OP_SET: u8 = 1;
+const OP_DELETE: u8 = 2;
+const OP_CLEAR: u8 = 3;
+const OP_DROP: u8 = 4;
+const OP_RENAME: u8 = 5;
+const OP_DELETE_RANGE: u8 = 6;
+const OP_CREATE: u8 = 7;
+
+/// Configuration for a persistent [`MemDB`](crate::MemDB).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PersistConfig {
+    /// Sync the log to disk after every write.
+    ///
+    /// Without this, writes survive a crash of the process but may be lost on power loss.
+    /// Defaults to `false`.
+    pub sync_writes: bool,
+
+    /// Take a snapshot once the log grows beyond this many bytes.
+    ///
+    /// With `None`, snapshots are only taken on `flush`. Defaults to 64 MiB.
+    pub snapshot_after: Option<u64>,
+}
+
+impl Default for PersistConfig {
+    fn default() -> Self {
+        Self {
+            sync_writes: false,
+            snapshot_after: Some(64 * 1024 * 1024),
+        }
+    }
+}
+
+/// A write to a database.
+pub(crate) enum Op<'a> {
+    Set(&'a str, &'a [u8], &'a [u8]),
+    Delete(&'a str, &'a [u8]),
+    DeleteRange(&'a str, &'a [u8], Option<&'a [u8]>),
+    Clear(&'a str),
+    Create(&'a str),
+    Drop(&'a str),
+    Rename(&'a str, &'a str),
+}
+
+impl Op<'_> {
+    fn encode(&self) -> Vec<u8> {
+        let mut body = Vec::new();
+        match self {
+            Op::Set(db, key, val) => {
+                body.push(OP_SET);
+                put_bytes(&mut body, db.as_bytes());
+                put_bytes(&mut body, key);
+                put_bytes(&mut body, val);
+            }
+            Op::Delete(db, key) => {
+                body.push(OP_DELETE);
+                put_bytes(&mut body, db.as_bytes());
+                put_bytes(&mut body, key);
+            }
+            Op::DeleteRange(db, start, end) => {
+                body.push(OP_DELETE_RANGE);
+                put_bytes(&mut body, db.as_bytes());
+                put_bytes(&mut body, start);
+                body.push(end.is_some() as u8);
+                put_bytes(&mut body, end.unwrap_or_default());
+            }
+            Op::Clear(db) => {
+                body.push(OP_CLEAR);
+                put_bytes(&mut body, db.as_bytes());
+            }
+            Op::Create(db) => {
+                body.push(OP_CREATE);
+                put_bytes(&mut body, db.as_bytes());
+            }
+            Op::Drop(db) => {
+                body.push(OP_DROP);
+                put_bytes(&mut body, db.as_bytes());
+            }
+            Op::Rename(old, new) => {
+                body.push(OP_RENAME);
+                put_bytes(&mut body, old.as_bytes());
+                put_bytes(&mut body, new.as_bytes());
+            }
+        }
+
+        let mut record = Vec::with_capacity(body.len() + 8);
+        record.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        record.extend_from_slice(&crc32fast::hash(&body).to_le_bytes());
+        record.extend_from_slice(&body);
+        record
+    }
+
+    fn decode(body: &[u8]) -> io::Result<Op<'_>> {
+        let mut r = Reader(body);
+        let op = match r.u8()? {
+            OP_SET => Op::Set(r.str()?, r.bytes()?, r.bytes()?),
+            OP_DELETE => Op::Delete(r.str()?, r.bytes()?),
+            OP_DELETE_RANGE => {
+                let (db, start) = (r.str()?, r.bytes()?);
+                let has_end = r.u8()? == 1;
+                let end = r.bytes()?;
+                Op::DeleteRange(db, start, has_end.then_some(end))
+            }
+            OP_CLEAR => Op::Clear(r.str()?),
+            OP_CREATE => Op::Create(r.str()?),
+            OP_DROP => Op::Drop(r.str()?),
+            OP_RENAME => Op::Rename(r.str()?, r.str()?),
+            _ => return Err(invalid("unknown log operation")),
+        };
+        Ok(op)
+    }
+
+    fn apply(&self, columns: &DashMap<String, MemDBMap>) {
+        let column = |db: &str| columns.entry(db.to_string()).or_default().clone();
+        match *self {
+            Op::Set(db, key, val) => {
+                column(db).insert(key.to_vec(), val.into());
+            }
+            Op::Delete(db, key) => {
+                column(db).remove(key);
+            }
+            Op::DeleteRange(db, start, end) => {
+                column(db).retain(|key, _| !crate::in_range(key, start, end));
+            }
+            Op::Clear(db) => column(db).clear(),
+            Op::Create(db) => {
+                column(db);
+            }
+            Op::Drop(db) => {
+                columns.remove(db);
+            }
+            Op::Rename(old, new) => {
+                if let Some((_, map)) = columns.remove(old) {
+                    columns.insert(new.to_string(), map);
+                }
+            }
+        }
+    }
+}
+
+fn put_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    buf.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    buf.extend_from_slice(bytes);
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if self.0.len() < len {
+            return Err(invalid("unexpected end of data"));
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn bytes(&mut self) -> io::Result<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    fn str(&mut self) -> io::Result<&'a str> {
+        std::str::from_utf8(self.bytes()?).map_err(|_| invalid("database name is not valid utf-8"))
+    }
+}
+
+/// Hashes everything written through it.
+struct HashWriter<W> {
+    inner: W,
+    hasher: crc32fast::Hasher,
+}
+
+impl<W: Write> Write for HashWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+struct Wal {
+    file: File,
+    len: u64,
+    // the generation of the last snapshot, which the log continues
+    generation: u64,
+}
+
+impl Wal {
+    /// Truncate the log and start it for the given generation.
+    fn restart(&mut self, generation: u64) -> io::Result<()> {
+        self.file.set_len(0)?;
+        let mut header = WAL_MAGIC.to_vec();
+        header.extend_from_slice(&generation.to_le_bytes());
+        self.file.write_all(&header)?;
+        self.file.sync_all()?;
+        self.len = WAL_HEADER_LEN;
+        self.generation = generation;
+        Ok(())
+    }
+}
+
+/// The files backing a persistent [`MemDB`](crate::MemDB).
+pub(crate) struct Persistence {
+    path: PathBuf,
+    config: PersistConfig,
+    columns: Arc<DashMap<String, MemDBMap>>,
+    // held while writing, so the log has the same order as the writes in memory
+    wal: Mutex<Wal>,
+}
+
+impl Persistence {
+    /// Load the snapshot and replay the log into `columns`.
+    pub(crate) fn open(
+        path: &Path,
+        config: PersistConfig,
+        columns: Arc<DashMap<String, MemDBMap>>,
+    ) -> io::Result<Self> {
+        let mut wal_path = path.as_os_str().to_owned();
+        wal_path.push(".wal");
+        let wal_path = PathBuf::from(wal_path);
+
+        let generation = match fs::read(path) {
+            Ok(snapshot) => load_snapshot(&snapshot, &columns)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => 0,
+            Err(e) => return Err(e),
+        };
+
+        let file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&wal_path)?;
+        let mut wal = Wal {
+            file,
+            len: 0,
+            generation,
+        };
+
+        let log = fs::read(&wal_path)?;
+        let mut r = Reader(&log);
+        match wal_generation(&mut r)? {
+            Some(wal_generation) if wal_generation == generation => {
+                wal.len = WAL_HEADER_LEN + replay_wal(r.0, &columns)?;
+                // drop a partially written record, new records are appended after the valid ones
+                wal.file.set_len(wal.len)?;
+            }
+            Some(wal_generation) if wal_generation > generation => {
+                return Err(invalid("log is newer than the snapshot"));
+            }
+            // a new log, or the log of an older generation, whose writes are in the snapshot
+            _ => wal.restart(generation)?,
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            config,
+            columns,
+            wal: Mutex::new(wal),
+        })
+    }
+
+    /// Append a write to the log and apply it in memory.
+    pub(crate) fn write(&self, op: Op<'_>, apply: impl FnOnce()) -> io::Result<()> {
+        let mut wal = self.wal.lock().unwrap_or_else(|e| e.into_inner());
+
+        let record = op.encode();
+        let res = wal
+            .file
+            .write_all(&record)
+            .and_then(|()| match self.config.sync_writes {
+                true => wal.file.sync_data(),
+                false => Ok(()),
+            });
+        if let Err(e) = res {
+            // drop the partial record, the next record would be appended after it
+            wal.file.set_len(wal.len)?;
+            return Err(e);
+        }
+        wal.len += record.len() as u64;
+        apply();
+
+        if self
+            .config
+            .snapshot_after
+            .is_some_and(|limit| wal.len >= limit)
+        {
+            self.snapshot_locked(&mut wal)?;
+        }
+        Ok(())
+    }
+
+    /// Write a snapshot of all databases and truncate the log.
+    pub(crate) fn snapshot(&self) -> io::Result<()> {
+        let mut wal = self.wal.lock().unwrap_or_else(|e| e.into_inner());
+        self.snapshot_locked(&mut wal)
+    }
+
+    fn snapshot_locked(&self, wal: &mut Wal) -> io::Result<()> {
+        let mut tmp_path = self.path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+
+        let file = File::create(&tmp_path)?;
+        let mut w = HashWriter {
+            inner: BufWriter::new(file),
+            hasher: crc32fast::Hasher::new(),
+        };
+
+        let generation = wal.generation + 1;
+        w.write_all(SNAPSHOT_MAGIC)?;
+        w.write_all(&[SNAPSHOT_VERSION])?;
+        w.write_all(&generation.to_le_bytes())?;
+
+        // collect the maps first, so no guard into `columns` is held while writing
+        let columns: Vec<(String, MemDBMap)> = self
+            .columns
+            .iter()
+            .map(|c| (c.key().clone(), c.value().clone()))
+            .collect();
+
+        w.write_all(&(columns.len() as u32).to_le_bytes())?;
+        for (name, map) in columns {
+            w.write_all(&(name.len() as u32).to_le_bytes())?;
+            w.write_all(name.as_bytes())?;
+            w.write_all(&(map.len() as u64).to_le_bytes())?;
+            for item in map.iter() {
+                for bytes in [item.key().as_slice(), item.value()] {
+                    w.write_all(&(bytes.len() as u32).to_le_bytes())?;
+                    w.write_all(bytes)?;
+                }
+            }
+        }
+
+        let checksum = w.hasher.clone().finalize();
+        let mut file = w.inner.into_inner().map_err(|e| e.into_error())?;
+        file.write_all(&checksum.to_le_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, &self.path)?;
+
+        // the log is only restarted once the snapshot is in place, after a crash in
+        // between the old log has an older generation and is not replayed
+        wal.restart(generation)
+    }
+}
+
+/// Load a snapshot into `columns` and return its generation.
+fn load_snapshot(snapshot: &[u8], columns: &DashMap<String, MemDBMap>) -> io::Result<u64> {
+    let Some((data, checksum)) = snapshot.split_last_chunk::<4>() else {
+        return Err(invalid("snapshot is truncated"));
+    };
+    if crc32fast::hash(data) != u32::from_le_bytes(*checksum) {
+        return Err(invalid("snapshot checksum mismatch"));
+    }
+
+    let mut r = Reader(data);
+    if r.take(SNAPSHOT_MAGIC.len())? != SNAPSHOT_MAGIC {
+        return Err(invalid("not a snapshot file"));
+    }
+    if r.u8()? != SNAPSHOT_VERSION {
+        return Err(invalid("unsupported snapshot version"));
+    }
+    let generation = r.u64()?;
+
+    for _ in 0..r.u32()? {
+        let name = r.str()?;
+        let map = MemDBMap::default();
+        for _ in 0..r.u64()? {
+            map.insert(r.bytes()?.to_vec(), r.bytes()?.into());
+        }
+        columns.insert(name.to_string(), map);
+    }
+    Ok(generation)
+}
+
+/// Read the header of the log, `None` if it is empty or the header was cut short while
+/// the log was restarted.
+fn wal_generation(r: &mut Reader<'_>) -> io::Result<Option<u64>> {
+    let len = r.0.len().min(WAL_MAGIC.len());
+    if r.0[..len] != WAL_MAGIC[..len] {
+        return Err(invalid("not a log file"));
+    }
+    if r.0.len() < WAL_HEADER_LEN as usize {
+        return Ok(None);
+    }
+
+    r.take(WAL_MAGIC.len())?;
+    r.u64().map(Some)
+}
+
+/// Apply all records of the log and return the length of the valid part.
+///
+/// Only the last record may be damaged, it was being written when the process stopped.
+fn replay_wal(wal: &[u8], columns: &DashMap<String, MemDBMap>) -> io::Result<u64> {
+    let mut r = Reader(wal);
+    while !r.0.is_empty() {
+        let valid = wal.len() - r.0.len();
+        let Some((checksum, body)) = next_record(&mut r) else {
+            return Ok(valid as u64);
+        };
+        if crc32fast::hash(body) != checksum {
+            return match r.0.is_empty() {
+                true => Ok(valid as u64),
+                false => Err(invalid("log record checksum mismatch")),
+            };
+        }
+        Op::decode(body)?.apply(columns);
+    }
+    Ok(wal.len() as u64)
+}
+
+/// Read the checksum and body of the next record, `None` if the log ends within it.
+fn next_record<'a>(r: &mut Reader<'a>) -> Option<(u32, &'a [u8])> {
+    let len = r.u32().ok()? as usize;
+    let checksum = r.u32().ok()?;
+    let body = r.take(len).ok()?;
+    Some((checksum, body))
+}
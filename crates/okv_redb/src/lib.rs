@@ -1,12 +1,24 @@
 use okv_core::{
-    backend::{DBColumn, DatabaseBackend},
+    backend::{
+        page_start, ColumnStats, DBColumn, DBColumnBatch, DBColumnCompact, DBColumnDeleteRange,
+        DBColumnIterator, DBColumnIteratorPrefix, DBColumnIteratorRange, DBColumnIteratorRef,
+        DBColumnPage, DBColumnRef, DBColumnRefBatch, DBColumnStats, DatabaseBackend, KeyCount,
+        KeyRange,
+    },
     error::{Error, Result},
     traits::Innerable,
 };
 
 pub use redb;
-use redb::{Database, ReadableTable, ReadableTableMetadata, TableDefinition};
+use redb::{
+    AccessGuard, Database, ReadTransaction, ReadableTable, ReadableTableMetadata, TableDefinition,
+    TableHandle,
+};
 use self_cell::self_cell;
+use std::{
+    ops::Bound,
+    sync::{RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
 
 mod r#async;
 mod tx;
@@ -16,18 +28,38 @@ pub(crate) fn okv_err(e: impl Into<redb::Error>) -> Error {
 }
 
 pub struct Redb {
-    db: Database,
+    /// Compaction and integrity checks need exclusive access, other calls only hold the
+    /// read lock while they begin a transaction.
+    db: RwLock<Database>,
 }
 
 impl Redb {
     pub fn new(connect_str: &str) -> Result<Self, redb::DatabaseError> {
         let db = Database::create(connect_str)?;
-        Ok(Self { db })
+        Ok(Self {
+            db: RwLock::new(db),
+        })
+    }
+
+    /// Returns the redb database. Compaction and integrity checks wait until the guard is
+    /// dropped.
+    pub fn database(&self) -> RwLockReadGuard<'_, Database> {
+        self.db()
+    }
+
+    fn db(&self) -> RwLockReadGuard<'_, Database> {
+        self.db.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn db_mut(&self) -> RwLockWriteGuard<'_, Database> {
+        self.db.write().unwrap_or_else(|e| e.into_inner())
     }
 }
 
+/// The database is behind a lock, since compaction needs exclusive access. Use
+/// [`Redb::database`] to read it.
 impl Innerable for Redb {
-    type Inner = Database;
+    type Inner = RwLock<Database>;
     fn inner(&self) -> &Self::Inner {
         &self.db
     }
@@ -39,8 +71,8 @@ pub struct RedbColumn {
 }
 
 impl RedbColumn {
-    fn db(&self) -> &Database {
-        self.env.inner()
+    fn db(&self) -> RwLockReadGuard<'_, Database> {
+        self.env.db().db()
     }
 
     fn table(&self) -> TableDefinition<'_, &'static [u8], &'static [u8]> {
@@ -48,6 +80,9 @@ impl RedbColumn {
     }
 
     /// Returns the metadata for the table.
+    ///
+    /// This method shadows [`DBColumnStats::stats`], call it as
+    /// `DBColumnStats::stats(&column)` for the backend independent statistics.
     pub fn stats(&self) -> Result<redb::TableStats> {
         let tx = self.db().begin_read().map_err(okv_err)?;
         let table = tx.open_table(self.table()).map_err(okv_err)?;
@@ -126,6 +161,320 @@ impl DBColumn for RedbColumn {
     }
 }
 
+/// A value read from redb without copying it.
+///
+/// Keeps the read transaction open, so the value stays valid while newer transactions
+/// are committed.
+pub struct RedbRef {
+    // dropped before the transaction
+    guard: AccessGuard<'static, &'static [u8]>,
+    _tx: std::sync::Arc<ReadTransaction>,
+}
+
+impl std::ops::Deref for RedbRef {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        self.guard.value()
+    }
+}
+
+impl AsRef<[u8]> for RedbRef {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl RedbColumn {
+    fn get_refs<'a, K>(&self, keys: impl IntoIterator<Item = &'a K>) -> Result<Vec<Option<RedbRef>>>
+    where
+        K: AsRef<[u8]> + 'a + ?Sized,
+    {
+        let tx = std::sync::Arc::new(self.db().begin_read().map_err(okv_err)?);
+        let table = tx.open_table(self.table()).map_err(okv_err)?;
+        keys.into_iter()
+            .map(|key| {
+                let guard = table.get(key.as_ref()).map_err(okv_err)?;
+                Ok(guard.map(|guard| RedbRef {
+                    guard,
+                    _tx: tx.clone(),
+                }))
+            })
+            .collect()
+    }
+}
+
+impl<'c> DBColumnRef<'c> for RedbColumn {
+    type Ref = RedbRef;
+
+    fn get_ref(&'c self, key: impl AsRef<[u8]>) -> Result<Option<Self::Ref>> {
+        Ok(self.get_refs([key.as_ref()])?.pop().flatten())
+    }
+}
+
+impl<'c> DBColumnRefBatch<'c> for RedbColumn {
+    /// All values of a batch share one read transaction.
+    type Ref = RedbRef;
+
+    fn get_multi_ref<'a, K, I>(&'c self, keys: I) -> Result<Vec<Option<Self::Ref>>>
+    where
+        K: AsRef<[u8]> + 'a + ?Sized,
+        I: IntoIterator<Item = &'a K>,
+    {
+        self.get_refs(keys)
+    }
+}
+
+/// Delete the keys from `start` to `end` in a table, returns the number of deleted keys.
+pub(crate) fn delete_range_in(
+    table: &mut redb::Table<'_, &'static [u8], &'static [u8]>,
+    start: &[u8],
+    end: Option<&[u8]>,
+) -> Result<u64> {
+    let end = end.map_or(Bound::Unbounded, Bound::Excluded);
+    let mut deleted = 0;
+    for item in table
+        .extract_from_if::<&[u8], _>((Bound::Included(start), end), |_, _| true)
+        .map_err(okv_err)?
+    {
+        item.map_err(okv_err)?;
+        deleted += 1;
+    }
+    Ok(deleted)
+}
+
+impl DBColumnBatch for RedbColumn {
+    fn set_multi<I, K, V>(&self, entries: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        let tx = self.db().begin_write().map_err(okv_err)?;
+
+        {
+            let mut table = tx.open_table(self.table()).map_err(okv_err)?;
+            for (key, val) in entries {
+                table.insert(key.as_ref(), val.as_ref()).map_err(okv_err)?;
+            }
+        }
+
+        tx.commit().map_err(okv_err)?;
+        Ok(())
+    }
+
+    fn write_multi<I, K, V>(&self, writes: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (K, Option<V>)>,
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        let tx = self.db().begin_write().map_err(okv_err)?;
+
+        {
+            let mut table = tx.open_table(self.table()).map_err(okv_err)?;
+            for (key, val) in writes {
+                match val {
+                    Some(val) => table.insert(key.as_ref(), val.as_ref()).map(|_| ()),
+                    None => table.remove(key.as_ref()).map(|_| ()),
+                }
+                .map_err(okv_err)?;
+            }
+        }
+
+        tx.commit().map_err(okv_err)?;
+        Ok(())
+    }
+}
+
+impl DBColumnDeleteRange for RedbColumn {
+    fn delete_range(&self, start: &[u8], end: Option<&[u8]>) -> Result<Option<u64>> {
+        let tx = self.db().begin_write().map_err(okv_err)?;
+        let deleted = {
+            let mut table = tx.open_table(self.table()).map_err(okv_err)?;
+            delete_range_in(&mut table, start, end)?
+        };
+        tx.commit().map_err(okv_err)?;
+        Ok(Some(deleted))
+    }
+}
+
+impl DBColumnIterator for RedbColumn {
+    fn iter(&self) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>> {
+        let tx = self.db().begin_read().map_err(okv_err)?;
+        let table = tx.open_table(self.table()).map_err(okv_err)?;
+
+        // the range keeps the read transaction alive until it is dropped
+        let range = table.range::<&[u8]>(..).map_err(okv_err)?;
+        Ok(range.map(|item| {
+            let (k, v) = item.map_err(okv_err)?;
+            Ok((k.value().to_vec(), v.value().to_vec()))
+        }))
+    }
+
+    fn keys(&self) -> Result<impl Iterator<Item = Result<Vec<u8>>>> {
+        let tx = self.db().begin_read().map_err(okv_err)?;
+        let table = tx.open_table(self.table()).map_err(okv_err)?;
+
+        // only the key guards are read, the values stay in the pages
+        let range = table.range::<&[u8]>(..).map_err(okv_err)?;
+        Ok(range.map(|item| {
+            let (k, _) = item.map_err(okv_err)?;
+            Ok(k.value().to_vec())
+        }))
+    }
+
+    fn count(&self) -> Result<u64> {
+        let tx = self.db().begin_read().map_err(okv_err)?;
+        let table = tx.open_table(self.table()).map_err(okv_err)?;
+        table.len().map_err(okv_err)
+    }
+}
+
+impl DBColumnCompact for RedbColumn {
+    /// redb can't compact single tables, so the whole file is compacted.
+    fn compact_range(&self, _range: KeyRange) -> Result<()> {
+        self.env.db().compact()
+    }
+}
+
+impl DBColumnStats for RedbColumn {
+    /// The disk size includes the table's metadata and fragmented pages, the properties
+    /// contain the fields of [`redb::TableStats`].
+    fn stats(&self) -> Result<ColumnStats> {
+        let tx = self.db().begin_read().map_err(okv_err)?;
+        let table = tx.open_table(self.table()).map_err(okv_err)?;
+        let table_stats = table.stats().map_err(okv_err)?;
+
+        let mut stats = ColumnStats::new(KeyCount::Exact(table.len().map_err(okv_err)?));
+        stats.disk_bytes = Some(
+            table_stats.stored_bytes()
+                + table_stats.metadata_bytes()
+                + table_stats.fragmented_bytes(),
+        );
+        stats.properties = [
+            ("tree_height", table_stats.tree_height() as u64),
+            ("leaf_pages", table_stats.leaf_pages()),
+            ("branch_pages", table_stats.branch_pages()),
+            ("stored_bytes", table_stats.stored_bytes()),
+            ("metadata_bytes", table_stats.metadata_bytes()),
+            ("fragmented_bytes", table_stats.fragmented_bytes()),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+        Ok(stats)
+    }
+}
+
+impl DBColumnIteratorRef for RedbColumn {
+    /// Entries are visited in a single read transaction.
+    fn for_each_ref(
+        &self,
+        prefix: &[u8],
+        mut f: impl FnMut(&[u8], &[u8]) -> Result<bool>,
+    ) -> Result<()> {
+        let tx = self.db().begin_read().map_err(okv_err)?;
+        let table = tx.open_table(self.table()).map_err(okv_err)?;
+        for item in table.range::<&[u8]>(prefix..).map_err(okv_err)? {
+            let (k, v) = item.map_err(okv_err)?;
+            if !k.value().starts_with(prefix) || !f(k.value(), v.value())? {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl DBColumnIteratorRange for RedbColumn {
+    fn iter_range(
+        &self,
+        range: KeyRange,
+    ) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>> {
+        let tx = self.db().begin_read().map_err(okv_err)?;
+        let table = tx.open_table(self.table()).map_err(okv_err)?;
+
+        let end = range
+            .end
+            .as_deref()
+            .map_or(Bound::Unbounded, Bound::Excluded);
+        let range = table
+            .range::<&[u8]>((Bound::Included(range.start.as_slice()), end))
+            .map_err(okv_err)?;
+        Ok(range.map(|item| {
+            let (k, v) = item.map_err(okv_err)?;
+            Ok((k.value().to_vec(), v.value().to_vec()))
+        }))
+    }
+}
+
+impl DBColumnPage for RedbColumn {
+    fn page(
+        &self,
+        after: Option<&[u8]>,
+        limit: usize,
+        prefix: &[u8],
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let tx = self.db().begin_read().map_err(okv_err)?;
+        let table = tx.open_table(self.table()).map_err(okv_err)?;
+
+        let range = (page_start(after, prefix), Bound::Unbounded);
+        let mut entries = Vec::new();
+        for item in table.range::<&[u8]>(range).map_err(okv_err)?.take(limit) {
+            let (k, v) = item.map_err(okv_err)?;
+            if !k.value().starts_with(prefix) {
+                break;
+            }
+            entries.push((k.value().to_vec(), v.value().to_vec()));
+        }
+        Ok(entries)
+    }
+}
+
+impl DBColumnIteratorPrefix for RedbColumn {
+    fn iter_prefix(
+        &self,
+        prefix: impl AsRef<[u8]>,
+    ) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>> {
+        let tx = self.db().begin_read().map_err(okv_err)?;
+        let table = tx.open_table(self.table()).map_err(okv_err)?;
+
+        let prefix = prefix.as_ref().to_vec();
+        let range = table.range::<&[u8]>(prefix.as_slice()..).map_err(okv_err)?;
+
+        Ok(range
+            .map(|item| {
+                let (k, v) = item.map_err(okv_err)?;
+                Ok((k.value().to_vec(), v.value().to_vec()))
+            })
+            .take_while(move |item| match item {
+                Ok((k, _)) => k.starts_with(&prefix),
+                Err(_) => true,
+            }))
+    }
+
+    fn keys_prefix(
+        &self,
+        prefix: impl AsRef<[u8]>,
+    ) -> Result<impl Iterator<Item = Result<Vec<u8>>>> {
+        let tx = self.db().begin_read().map_err(okv_err)?;
+        let table = tx.open_table(self.table()).map_err(okv_err)?;
+
+        let prefix = prefix.as_ref().to_vec();
+        let range = table.range::<&[u8]>(prefix.as_slice()..).map_err(okv_err)?;
+
+        Ok(range
+            .map(|item| {
+                let (k, _) = item.map_err(okv_err)?;
+                Ok(k.value().to_vec())
+            })
+            .take_while(move |item| match item {
+                Ok(k) => k.starts_with(&prefix),
+                Err(_) => true,
+            }))
+    }
+}
+
 impl DatabaseBackend for Redb {
     type Column = RedbColumn;
     fn create_or_open(
@@ -140,8 +489,57 @@ impl DatabaseBackend for Redb {
             }),
         };
 
+        // redb only creates tables in write transactions, so make sure it exists
+        // before the first read
+        let tx = table.db().begin_write().map_err(okv_err)?;
+        tx.open_table(table.table()).map_err(okv_err)?;
+        tx.commit().map_err(okv_err)?;
+
         Ok(table)
     }
+
+    fn list_databases(&self) -> Result<Vec<String>> {
+        let tx = self.db().begin_read().map_err(okv_err)?;
+        let tables = tx.list_tables().map_err(okv_err)?;
+        Ok(tables.map(|table| table.name().to_string()).collect())
+    }
+
+    fn drop_database(&self, db: &str) -> Result<()> {
+        let tx = self.db().begin_write().map_err(okv_err)?;
+        tx.delete_table(bytes_table(db)).map_err(okv_err)?;
+        tx.commit().map_err(okv_err)
+    }
+
+    /// Copies all entries to a new table and deletes the old one in a single transaction.
+    fn rename_database(&self, old: &str, new: &str) -> Result<()> {
+        let tx = self.db().begin_write().map_err(okv_err)?;
+        {
+            let source = tx.open_table(bytes_table(old)).map_err(okv_err)?;
+            let mut target = tx.open_table(bytes_table(new)).map_err(okv_err)?;
+            for item in source.iter().map_err(okv_err)? {
+                let (key, val) = item.map_err(okv_err)?;
+                target.insert(key.value(), val.value()).map_err(okv_err)?;
+            }
+        }
+        tx.delete_table(bytes_table(old)).map_err(okv_err)?;
+        tx.commit().map_err(okv_err)
+    }
+
+    /// Waits until running calls have begun their transactions. Fails while transactions
+    /// are still open, e.g. iterators or values returned by `get_ref`.
+    fn compact(&self) -> Result<()> {
+        self.db_mut().compact().map_err(okv_err)?;
+        Ok(())
+    }
+
+    /// Repairs the database if needed, which needs exclusive access like [`Self::compact`].
+    fn check_integrity(&self) -> Result<bool> {
+        self.db_mut().check_integrity().map_err(okv_err)
+    }
+}
+
+fn bytes_table(name: &str) -> TableDefinition<'_, &'static [u8], &'static [u8]> {
+    TableDefinition::new(name)
 }
 
 struct BytesTable<'a>(TableDefinition<'a, &'static [u8], &'static [u8]>);
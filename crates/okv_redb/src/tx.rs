@@ -1,13 +1,13 @@
 use std::cell::RefCell;
 
 use okv_core::{
-    backend::{DBColumn, DBColumnTransaction, DBTransaction},
+    backend::{DBColumn, DBColumnDeleteRange, DBColumnTransaction, DBTransaction},
     error::{Error, Result},
 };
 use ouroboros::self_referencing;
 use redb::{ReadableTable, ReadableTableMetadata, Table, TableDefinition};
 
-use crate::{okv_err, RedbColumn};
+use crate::{delete_range_in, okv_err, RedbColumn};
 
 type TxTable<'tx> = Table<'tx, &'static [u8], &'static [u8]>;
 pub struct RedbTransaction<'a>(RefCell<RedbTxInner<'a>>);
@@ -64,6 +64,16 @@ impl<'a> DBColumn for RedbTransaction<'a> {
     }
 }
 
+impl<'a> DBColumnDeleteRange for RedbTransaction<'a> {
+    fn delete_range(&self, start: &[u8], end: Option<&[u8]>) -> Result<Option<u64>> {
+        let deleted = self
+            .0
+            .borrow_mut()
+            .with_table_mut(|table| delete_range_in(table, start, end))?;
+        Ok(Some(deleted))
+    }
+}
+
 impl<'a> DBTransaction for RedbTransaction<'a> {
     fn commit(self) -> Result<()> {
         let inner = self.0.into_inner();
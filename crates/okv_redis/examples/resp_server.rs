@@ -0,0 +1,63 @@
+use std::net::TcpListener;
+
+use eyre::Result;
+use okv_core::{env::Env, traits::DBCommonIterPrefix};
+use okv_memory::MemDB;
+use okv_redis::Redis;
+use okv_resp::RespServer;
+
+fn main() -> Result<()> {
+    // serve an in-memory database over the redis protocol on a random port
+    // usually, this would be a regular redis server
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+    let server = RespServer::new(Env::new(MemDB::new()));
+    std::thread::spawn(move || server.serve_tcp(listener));
+
+    let env = Env::new(Redis::new(&format!("redis://{addr}/"))?);
+    let db = env.open::<&str, &str>("test")?;
+    let other = env.open::<&str, &str>("other")?;
+    assert!(env.open::<&str, &str>("not:allowed").is_err());
+
+    assert!(db.set_nx("hello", "world")?);
+    assert!(!db.set_nx("hello", "sailor")?);
+    assert_eq!(db.get("hello")?, Some("world".to_string()));
+    assert_eq!(other.get("hello")?, None);
+
+    // glob characters in prefixes are matched literally
+    db.set("a*1", "1")?;
+    db.set("a*2", "2")?;
+    db.set("ab", "3")?;
+    let items = db
+        .iter_prefix::<&str>("a*")?
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(items.len(), 2);
+
+    // writes in a transaction are applied with MULTI/EXEC on commit
+    let tx = db.transaction()?;
+    tx.set("foo", "bar")?;
+    assert_eq!(tx.get("foo")?, Some("bar".to_string()));
+    assert_eq!(db.get("foo")?, None);
+    tx.commit()?;
+    assert_eq!(db.get("foo")?, Some("bar".to_string()));
+
+    // the commit fails if a key read in the transaction was modified by someone else
+    let tx = db.transaction()?;
+    assert_eq!(tx.get("foo")?, Some("bar".to_string()));
+    db.set("foo", "baz")?;
+    tx.set("foo", "qux")?;
+    assert!(tx.commit().is_err());
+    assert_eq!(db.get("foo")?, Some("baz".to_string()));
+
+    other.set("hello", "world")?;
+    db.clear()?;
+    assert_eq!(db.iter()?.count(), 0);
+    assert_eq!(other.get("hello")?, Some("world".to_string()));
+
+    Ok(())
+}
+
+#[test] // ensure that the example always works
+fn test() -> Result<()> {
+    main()
+}
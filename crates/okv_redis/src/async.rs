@@ -0,0 +1,12 @@
+use crate::{RedisColumn, RedisTransaction};
+use okv_core::async_fallback;
+use okv_core::backend::DBColumn;
+
+async_fallback!(RedisColumn);
+
+impl<'a> okv_core::backend_async::DBColumnAsync for RedisTransaction<'a>
+where
+    RedisTransaction<'a>: okv_core::backend::DBColumn,
+{
+    okv_core::async_fallback_impl!();
+}
@@ -0,0 +1,694 @@
+//! Redis database backend for OKV.
+//!
+//! Every OKV database is a key namespace in a single Redis database: the key `foo` of the
+//! database `users` is stored as the Redis key `users:foo`. Database names can't contain `:`,
+//! so two databases never share keys.
+//!
+//! Iteration uses `SCAN MATCH`, so keys are not returned in order, and Redis may return a key
+//! more than once if the keyspace is modified during the scan.
+//!
+//! Transactions use optimistic locking: every key read in a transaction is `WATCH`ed and
+//! the buffered writes are applied with `MULTI`/`EXEC` on commit. If another client modified
+//! a watched key in the meantime, the commit fails with an [`redis::ErrorKind::ExecAbortError`].
+//!
+//! Expiration is supported through the inherent methods of [`RedisColumn`], which can be
+//! accessed through `Database::inner`.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::{BTreeMap, BTreeSet},
+    ops::{Deref, DerefMut},
+    sync::Mutex,
+    time::Duration,
+};
+
+use okv_core::{
+    backend::*,
+    env::Env,
+    error::{Error, Result},
+    traits::Innerable,
+};
+use redis::{Cmd, Connection, ConnectionLike, FromRedisValue};
+
+mod r#async;
+
+/// The separator between the database name and the key.
+pub const NAMESPACE_SEPARATOR: u8 = b':';
+
+/// The number of keys requested from Redis per `SCAN` call.
+const SCAN_COUNT: usize = 1000;
+
+pub(crate) fn okv_err(e: redis::RedisError) -> Error {
+    Error::DatabaseBackend(Box::new(e))
+}
+
+/// A Redis database backend.
+///
+/// Connections are pooled and reused between requests.
+pub struct Redis {
+    client: redis::Client,
+    pool: Mutex<Vec<Connection>>,
+}
+
+impl Redis {
+    /// Connect to a Redis server.
+    ///
+    /// The url has the format `redis://[<username>][:<password>@]<host>[:port][/<db>]`
+    /// or `redis+unix:///<path>[?db=<db>]`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use okv_core::env::Env;
+    /// use okv_redis::Redis;
+    ///
+    /// let backend = Redis::new("redis://127.0.0.1:6379/0").unwrap();
+    /// let env = Env::new(backend);
+    /// ```
+    pub fn new(url: &str) -> Result<Self> {
+        let client = redis::Client::open(url).map_err(okv_err)?;
+        let conn = client.get_connection().map_err(okv_err)?;
+
+        Ok(Self {
+            client,
+            pool: Mutex::new(vec![conn]),
+        })
+    }
+
+    fn connection(&self) -> Result<PooledConnection<'_>> {
+        let conn = self.pool.lock().unwrap_or_else(|e| e.into_inner()).pop();
+        let conn = match conn {
+            Some(conn) => conn,
+            None => self.client.get_connection().map_err(okv_err)?,
+        };
+
+        Ok(PooledConnection {
+            redis: self,
+            conn: Some(conn),
+        })
+    }
+
+    fn query<T: FromRedisValue>(&self, cmd: &Cmd) -> Result<T> {
+        cmd.query(&mut *self.connection()?).map_err(okv_err)
+    }
+
+    /// Calls `f` with every page of keys matching a pattern, until it returns `false`.
+    fn scan_pages(&self, pattern: &[u8], mut f: impl FnMut(Vec<Vec<u8>>) -> bool) -> Result<()> {
+        let mut conn = self.connection()?;
+        let mut cursor = 0u64;
+        loop {
+            let (next, page): (u64, Vec<Vec<u8>>) = redis::cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(pattern)
+                .arg("COUNT")
+                .arg(SCAN_COUNT)
+                .query(&mut *conn)
+                .map_err(okv_err)?;
+            cursor = next;
+            if !f(page) || cursor == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Returns all keys matching a pattern.
+    fn scan_keys(&self, pattern: &[u8]) -> Result<Vec<Vec<u8>>> {
+        let mut keys = Vec::new();
+        self.scan_pages(pattern, |page| {
+            keys.extend(page);
+            true
+        })?;
+
+        // keys can be returned more than once
+        keys.sort();
+        keys.dedup();
+        Ok(keys)
+    }
+
+    /// Returns all keys of a database, including the namespace.
+    fn namespace_keys(&self, db: &str) -> Result<Vec<Vec<u8>>> {
+        self.scan_keys(&namespace_pattern(db))
+    }
+}
+
+impl Innerable for Redis {
+    type Inner = redis::Client;
+    fn inner(&self) -> &Self::Inner {
+        &self.client
+    }
+}
+
+impl DatabaseBackend for Redis {
+    type Column = RedisColumn;
+
+    fn create_or_open(env: Env<Self>, db: &str) -> Result<Self::Column> {
+        if db.as_bytes().contains(&NAMESPACE_SEPARATOR) {
+            return Err(Error::Unknown(format!(
+                "database name `{db}` must not contain `{}`",
+                NAMESPACE_SEPARATOR as char
+            )));
+        }
+
+        let mut namespace = db.as_bytes().to_vec();
+        namespace.push(NAMESPACE_SEPARATOR);
+        Ok(RedisColumn { env, namespace })
+    }
+
+    /// Scans the whole keyspace, databases are the namespaces of all keys containing `:`.
+    fn list_databases(&self) -> Result<Vec<String>> {
+        let mut pattern = b"*".to_vec();
+        pattern.extend_from_slice(&[NAMESPACE_SEPARATOR, b'*']);
+
+        let mut names = BTreeSet::new();
+        for key in self.scan_keys(&pattern)? {
+            let Some(end) = key.iter().position(|&b| b == NAMESPACE_SEPARATOR) else {
+                continue;
+            };
+            if let Ok(name) = std::str::from_utf8(&key[..end]) {
+                names.insert(name.to_string());
+            }
+        }
+        Ok(names.into_iter().collect())
+    }
+
+    fn exists(&self, db: &str) -> Result<bool> {
+        // pages can be empty even if there are matching keys
+        let mut exists = false;
+        self.scan_pages(&namespace_pattern(db), |page| {
+            exists = !page.is_empty();
+            !exists
+        })?;
+        Ok(exists)
+    }
+
+    fn drop_database(&self, db: &str) -> Result<()> {
+        for keys in self.namespace_keys(db)?.chunks(SCAN_COUNT) {
+            let mut cmd = redis::cmd("DEL");
+            cmd.arg(keys);
+            self.query::<()>(&cmd)?;
+        }
+        Ok(())
+    }
+
+    /// Renames every key with `RENAME`, which is not atomic for the whole database.
+    fn rename_database(&self, old: &str, new: &str) -> Result<()> {
+        if new.as_bytes().contains(&NAMESPACE_SEPARATOR) {
+            return Err(Error::Unknown(format!(
+                "database name `{new}` must not contain `{}`",
+                NAMESPACE_SEPARATOR as char
+            )));
+        }
+
+        let keys = self.namespace_keys(old)?;
+        for keys in keys.chunks(SCAN_COUNT) {
+            let mut pipe = redis::pipe();
+            for key in keys {
+                let renamed = [new.as_bytes(), &key[old.len()..]].concat();
+                pipe.cmd("RENAME").arg(key).arg(renamed).ignore();
+            }
+            pipe.query::<()>(&mut *self.connection()?)
+                .map_err(okv_err)?;
+        }
+        Ok(())
+    }
+}
+
+/// A connection that is returned to the pool when dropped.
+/// Connections that were closed because of an error are discarded instead.
+struct PooledConnection<'a> {
+    redis: &'a Redis,
+    conn: Option<Connection>,
+}
+
+impl Deref for PooledConnection<'_> {
+    type Target = Connection;
+    fn deref(&self) -> &Self::Target {
+        self.conn
+            .as_ref()
+            .expect("connection is only taken on drop")
+    }
+}
+
+impl DerefMut for PooledConnection<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.conn
+            .as_mut()
+            .expect("connection is only taken on drop")
+    }
+}
+
+impl Drop for PooledConnection<'_> {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take().filter(|conn| conn.is_open()) {
+            self.redis
+                .pool
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(conn);
+        }
+    }
+}
+
+/// The pattern matching all keys of a database.
+fn namespace_pattern(db: &str) -> Vec<u8> {
+    let mut pattern = escape_glob(db.as_bytes());
+    pattern.extend_from_slice(&[NAMESPACE_SEPARATOR, b'*']);
+    pattern
+}
+
+/// Escape all glob special characters, so the pattern matches the bytes literally.
+fn escape_glob(bytes: &[u8]) -> Vec<u8> {
+    let mut escaped = Vec::with_capacity(bytes.len() + 1);
+    for &b in bytes {
+        if matches!(b, b'*' | b'?' | b'[' | b']' | b'\\') {
+            escaped.push(b'\\');
+        }
+        escaped.push(b);
+    }
+    escaped
+}
+
+/// A database stored as a key namespace on a Redis server.
+pub struct RedisColumn {
+    env: Env<Redis>,
+    namespace: Vec<u8>,
+}
+
+impl RedisColumn {
+    fn redis(&self) -> &Redis {
+        self.env.db()
+    }
+
+    /// Returns the Redis key of a key in this database.
+    fn key(&self, key: impl AsRef<[u8]>) -> Vec<u8> {
+        [self.namespace.as_slice(), key.as_ref()].concat()
+    }
+
+    fn scan(&self, prefix: &[u8]) -> ScanIter {
+        let mut pattern = escape_glob(&self.key(prefix));
+        pattern.push(b'*');
+
+        ScanIter {
+            env: self.env.clone(),
+            namespace_len: self.namespace.len(),
+            pattern,
+            cursor: 0,
+            done: false,
+            items: Vec::new().into_iter(),
+        }
+    }
+
+    /// Set the value of a key that expires after the given duration.
+    pub fn set_ex(
+        &self,
+        key: impl AsRef<[u8]>,
+        val: impl AsRef<[u8]>,
+        ttl: Duration,
+    ) -> Result<()> {
+        let mut cmd = redis::cmd("SET");
+        cmd.arg(self.key(key))
+            .arg(val.as_ref())
+            .arg("PX")
+            .arg(ttl.as_millis() as u64);
+        self.redis().query(&cmd)
+    }
+
+    /// Set the time to live of an existing key.
+    /// Returns `false` if the key doesn't exist.
+    pub fn expire(&self, key: impl AsRef<[u8]>, ttl: Duration) -> Result<bool> {
+        let mut cmd = redis::cmd("PEXPIRE");
+        cmd.arg(self.key(key)).arg(ttl.as_millis() as u64);
+        self.redis().query(&cmd)
+    }
+
+    /// Returns the remaining time to live of a key.
+    /// Returns `None` if the key doesn't exist or doesn't expire.
+    pub fn ttl(&self, key: impl AsRef<[u8]>) -> Result<Option<Duration>> {
+        let mut cmd = redis::cmd("PTTL");
+        cmd.arg(self.key(key));
+        let ttl: i64 = self.redis().query(&cmd)?;
+        Ok(u64::try_from(ttl).ok().map(Duration::from_millis))
+    }
+
+    /// Remove the time to live of a key, so it doesn't expire anymore.
+    /// Returns `false` if the key doesn't exist or doesn't expire.
+    pub fn persist(&self, key: impl AsRef<[u8]>) -> Result<bool> {
+        let mut cmd = redis::cmd("PERSIST");
+        cmd.arg(self.key(key));
+        self.redis().query(&cmd)
+    }
+}
+
+impl DBColumn for RedisColumn {
+    fn set(&self, key: impl AsRef<[u8]>, val: impl AsRef<[u8]>) -> Result<()> {
+        let mut cmd = redis::cmd("SET");
+        cmd.arg(self.key(key)).arg(val.as_ref());
+        self.redis().query(&cmd)
+    }
+
+    fn set_nx(&self, key: impl AsRef<[u8]>, val: impl AsRef<[u8]>) -> Result<bool> {
+        let mut cmd = redis::cmd("SET");
+        cmd.arg(self.key(key)).arg(val.as_ref()).arg("NX");
+        let res: Option<()> = self.redis().query(&cmd)?;
+        Ok(res.is_some())
+    }
+
+    fn get(&self, key: impl AsRef<[u8]>) -> Result<Option<Vec<u8>>> {
+        let mut cmd = redis::cmd("GET");
+        cmd.arg(self.key(key));
+        self.redis().query(&cmd)
+    }
+
+    fn get_multi<I>(&self, keys: I) -> Result<Vec<Option<Vec<u8>>>>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let keys: Vec<Vec<u8>> = keys.into_iter().map(|k| self.key(k)).collect();
+        // MGET requires at least one key
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut cmd = redis::cmd("MGET");
+        cmd.arg(keys);
+        self.redis().query(&cmd)
+    }
+
+    fn delete(&self, key: impl AsRef<[u8]>) -> Result<()> {
+        let mut cmd = redis::cmd("DEL");
+        cmd.arg(self.key(key));
+        self.redis().query(&cmd)
+    }
+
+    fn contains(&self, key: impl AsRef<[u8]>) -> Result<bool> {
+        let mut cmd = redis::cmd("EXISTS");
+        cmd.arg(self.key(key));
+        self.redis().query(&cmd)
+    }
+}
+
+impl DBColumnBatch for RedisColumn {
+    /// The pairs are set with a single atomic `MSET`.
+    fn set_multi<I, K, V>(&self, entries: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        let mut cmd = redis::cmd("MSET");
+        let mut empty = true;
+        for (key, val) in entries {
+            cmd.arg(self.key(key)).arg(val.as_ref());
+            empty = false;
+        }
+        // MSET requires at least one pair
+        if empty {
+            return Ok(());
+        }
+        self.redis().query(&cmd)
+    }
+
+    /// The writes are applied in a single `MULTI`/`EXEC` pipeline.
+    fn write_multi<I, K, V>(&self, writes: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (K, Option<V>)>,
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        let mut empty = true;
+        for (key, val) in writes {
+            let key = self.key(key);
+            match val {
+                Some(val) => pipe.cmd("SET").arg(key).arg(val.as_ref()).ignore(),
+                None => pipe.cmd("DEL").arg(key).ignore(),
+            };
+            empty = false;
+        }
+        if empty {
+            return Ok(());
+        }
+        pipe.query(&mut *self.redis().connection()?)
+            .map_err(okv_err)
+    }
+}
+
+impl DBColumnClear for RedisColumn {
+    fn clear(&self) -> Result<()> {
+        // the keys are collected first, deleting keys while scanning could skip keys on some servers
+        let name = &self.namespace[..self.namespace.len() - 1];
+        let name = std::str::from_utf8(name).expect("database names are strings");
+        self.redis().drop_database(name)
+    }
+}
+
+impl DBColumnIterator for RedisColumn {
+    fn iter(&self) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>> {
+        Ok(self.scan(&[]))
+    }
+}
+
+impl DBColumnIteratorPrefix for RedisColumn {
+    fn iter_prefix(
+        &self,
+        prefix: impl AsRef<[u8]>,
+    ) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>> {
+        Ok(self.scan(prefix.as_ref()))
+    }
+}
+
+/// Iterates over the keys matching a pattern with `SCAN`, fetching the values of every page with `MGET`.
+struct ScanIter {
+    env: Env<Redis>,
+    namespace_len: usize,
+    pattern: Vec<u8>,
+    cursor: u64,
+    done: bool,
+    items: std::vec::IntoIter<(Vec<u8>, Vec<u8>)>,
+}
+
+impl ScanIter {
+    fn next_page(&mut self) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let mut conn = self.env.db().connection()?;
+
+        let (cursor, keys): (u64, Vec<Vec<u8>>) = redis::cmd("SCAN")
+            .arg(self.cursor)
+            .arg("MATCH")
+            .arg(&self.pattern)
+            .arg("COUNT")
+            .arg(SCAN_COUNT)
+            .query(&mut *conn)
+            .map_err(okv_err)?;
+        self.cursor = cursor;
+        self.done = cursor == 0;
+
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let vals: Vec<Option<Vec<u8>>> = redis::cmd("MGET")
+            .arg(&keys)
+            .query(&mut *conn)
+            .map_err(okv_err)?;
+
+        // keys that were deleted since the scan have no value
+        let items = keys
+            .into_iter()
+            .zip(vals)
+            .filter_map(|(key, val)| Some((key[self.namespace_len..].to_vec(), val?)))
+            .collect();
+        Ok(items)
+    }
+}
+
+impl Iterator for ScanIter {
+    type Item = Result<(Vec<u8>, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.items.next() {
+                return Some(Ok(item));
+            }
+            if self.done {
+                return None;
+            }
+
+            match self.next_page() {
+                Ok(items) => self.items = items.into_iter(),
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+/// A transaction on a Redis database.
+///
+/// Reads `WATCH` the keys they read on a dedicated connection, and writes are buffered until
+/// they are applied atomically with `MULTI`/`EXEC` on commit. Reads see the buffered writes
+/// of this transaction.
+pub struct RedisTransaction<'a> {
+    column: &'a RedisColumn,
+    conn: RefCell<PooledConnection<'a>>,
+    writes: RefCell<BTreeMap<Vec<u8>, Option<Vec<u8>>>>,
+    // whether keys are watched, so the connection has to be reset before it is reused
+    watching: Cell<bool>,
+}
+
+impl<'a> DBColumnTransaction<'a> for RedisColumn {
+    type Txn = RedisTransaction<'a>;
+
+    fn transaction(&'a self) -> Result<Self::Txn> {
+        Ok(RedisTransaction {
+            column: self,
+            conn: RefCell::new(self.redis().connection()?),
+            writes: RefCell::new(BTreeMap::new()),
+            watching: Cell::new(false),
+        })
+    }
+}
+
+impl<'a> RedisTransaction<'a> {
+    /// `WATCH` the given keys and run a command on the transaction's connection.
+    fn watch_query<T: FromRedisValue>(&self, keys: &[Vec<u8>], cmd: &Cmd) -> Result<T> {
+        let mut conn = self.conn.borrow_mut();
+        if !keys.is_empty() {
+            redis::cmd("WATCH")
+                .arg(keys)
+                .query::<()>(&mut **conn)
+                .map_err(okv_err)?;
+            self.watching.set(true);
+        }
+        cmd.query(&mut **conn).map_err(okv_err)
+    }
+
+    fn unwatch(&self) -> Result<()> {
+        if self.watching.replace(false) {
+            redis::cmd("UNWATCH")
+                .query::<()>(&mut **self.conn.borrow_mut())
+                .map_err(okv_err)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> DBColumn for RedisTransaction<'a> {
+    fn set(&self, key: impl AsRef<[u8]>, val: impl AsRef<[u8]>) -> Result<()> {
+        self.writes
+            .borrow_mut()
+            .insert(key.as_ref().to_vec(), Some(val.as_ref().to_vec()));
+        Ok(())
+    }
+
+    fn get(&self, key: impl AsRef<[u8]>) -> Result<Option<Vec<u8>>> {
+        if let Some(val) = self.writes.borrow().get(key.as_ref()) {
+            return Ok(val.clone());
+        }
+
+        let key = self.column.key(key);
+        let mut cmd = redis::cmd("GET");
+        cmd.arg(&key);
+        self.watch_query(&[key], &cmd)
+    }
+
+    fn get_multi<I>(&self, keys: I) -> Result<Vec<Option<Vec<u8>>>>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let keys: Vec<Vec<u8>> = keys.into_iter().map(|k| k.as_ref().to_vec()).collect();
+        let writes = self.writes.borrow();
+
+        // only fetch the keys that weren't written in this transaction
+        let missing: Vec<Vec<u8>> = keys
+            .iter()
+            .filter(|k| !writes.contains_key(*k))
+            .map(|k| self.column.key(k))
+            .collect();
+
+        let mut remote = match missing.is_empty() {
+            true => Vec::new().into_iter(),
+            false => {
+                let mut cmd = redis::cmd("MGET");
+                cmd.arg(&missing);
+                self.watch_query::<Vec<Option<Vec<u8>>>>(&missing, &cmd)?
+                    .into_iter()
+            }
+        };
+
+        keys.iter()
+            .map(|key| match writes.get(key) {
+                Some(val) => Ok(val.clone()),
+                None => remote.next().ok_or_else(|| {
+                    Error::Unknown("redis returned fewer values than requested".into())
+                }),
+            })
+            .collect()
+    }
+
+    fn delete(&self, key: impl AsRef<[u8]>) -> Result<()> {
+        self.writes.borrow_mut().insert(key.as_ref().to_vec(), None);
+        Ok(())
+    }
+
+    fn contains(&self, key: impl AsRef<[u8]>) -> Result<bool> {
+        if let Some(val) = self.writes.borrow().get(key.as_ref()) {
+            return Ok(val.is_some());
+        }
+
+        let key = self.column.key(key);
+        let mut cmd = redis::cmd("EXISTS");
+        cmd.arg(&key);
+        self.watch_query(&[key], &cmd)
+    }
+}
+
+impl<'a> DBTransaction for RedisTransaction<'a> {
+    fn commit(self) -> Result<()> {
+        let writes = self.writes.take();
+        if writes.is_empty() {
+            return self.unwatch();
+        }
+
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for (key, val) in writes {
+            let key = self.column.key(key);
+            match val {
+                Some(val) => pipe.cmd("SET").arg(key).arg(val).ignore(),
+                None => pipe.cmd("DEL").arg(key).ignore(),
+            };
+        }
+
+        // EXEC also clears all watches, and returns nil if a watched key was modified
+        let res: Option<()> = pipe.query(&mut **self.conn.borrow_mut()).map_err(okv_err)?;
+        self.watching.set(false);
+
+        match res {
+            Some(()) => Ok(()),
+            None => Err(okv_err(redis::RedisError::from((
+                redis::ErrorKind::ExecAbortError,
+                "transaction aborted, a key read in the transaction was modified",
+            )))),
+        }
+    }
+
+    fn rollback(self) -> Result<()> {
+        self.writes.take();
+        self.unwatch()
+    }
+}
+
+impl Drop for RedisTransaction<'_> {
+    fn drop(&mut self) {
+        // a dropped transaction must not leave watches behind on a pooled connection
+        if self.unwatch().is_err() {
+            self.conn.get_mut().conn = None;
+        }
+    }
+}
@@ -0,0 +1,12 @@
+use crate::{RemoteColumn, RemoteTransaction};
+use okv_core::async_fallback;
+use okv_core::backend::DBColumn;
+
+async_fallback!(RemoteColumn);
+
+impl<'a> okv_core::backend_async::DBColumnAsync for RemoteTransaction<'a>
+where
+    RemoteTransaction<'a>: okv_core::backend::DBColumn,
+{
+    okv_core::async_fallback_impl!();
+}
@@ -0,0 +1,59 @@
+//! Serve an OKV environment to `okv_remote` clients.
+//!
+//! ```text
+//! okv-remote-server <listen-address> <backend>
+//!
+//! listen-address: tcp://127.0.0.1:7777 or unix:///tmp/okv.sock
+//! backend:        memory, redb:<path> or rocksdb:<path>
+//! ```
+
+use std::process::ExitCode;
+
+use okv_core::{env::Env, error::Result};
+use okv_remote::Server;
+
+fn serve(listen: &str, backend: &str) -> Result<()> {
+    match backend.split_once(':').unwrap_or((backend, "")) {
+        #[cfg(feature = "memory")]
+        ("memory", _) => Server::new(Env::new(okv_memory::MemDB::new())).listen(listen),
+
+        #[cfg(feature = "redb")]
+        ("redb", path) => {
+            let db = okv_redb::Redb::new(path)
+                .map_err(|e| okv_core::error::Error::DatabaseBackend(Box::new(e)))?;
+            Server::new(Env::new(db)).listen(listen)
+        }
+
+        #[cfg(feature = "rocksdb")]
+        ("rocksdb", path) => {
+            std::fs::create_dir_all(path)
+                .map_err(|e| okv_core::error::Error::Unknown(e.to_string()))?;
+            let db = okv_rocksdb::RocksDb::new(path)
+                .map_err(|e| okv_core::error::Error::DatabaseBackend(Box::new(e)))?;
+            Server::new(Env::new(db)).listen(listen)
+        }
+
+        _ => Err(okv_core::error::Error::Unknown(format!(
+            "unsupported backend: {backend}"
+        ))),
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().collect();
+    let [_, listen, backend] = args.as_slice() else {
+        eprintln!("usage: okv-remote-server <listen-address> <backend>");
+        eprintln!();
+        eprintln!("  listen-address: tcp://127.0.0.1:7777 or unix:///tmp/okv.sock");
+        eprintln!("  backend:        memory, redb:<path> or rocksdb:<path>");
+        return ExitCode::FAILURE;
+    };
+
+    match serve(listen, backend) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
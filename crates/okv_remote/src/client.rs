@@ -0,0 +1,508 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    io::{self, Read, Write},
+    net::TcpStream,
+    sync::Mutex,
+};
+
+use okv_core::{
+    backend::*,
+    env::Env,
+    error::{Error, Result},
+    traits::Innerable,
+};
+
+use crate::{
+    okv_err,
+    protocol::{self, BatchOp, Request, Response},
+    Address, RemoteError,
+};
+
+/// A database backend that forwards all operations to a remote [`Server`](crate::Server).
+///
+/// Connections are pooled and reused between requests.
+pub struct RemoteDb {
+    addr: Address,
+    pool: Mutex<Vec<Connection>>,
+}
+
+impl RemoteDb {
+    /// Connect to a remote database server.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use okv_core::env::Env;
+    /// use okv_remote::RemoteDb;
+    ///
+    /// let backend = RemoteDb::connect("tcp://127.0.0.1:7777").unwrap();
+    /// let env = Env::new(backend);
+    /// ```
+    pub fn connect(addr: &str) -> Result<Self> {
+        let addr = Address::parse(addr).map_err(okv_err)?;
+        let conn = Connection::open(&addr).map_err(okv_err)?;
+
+        Ok(Self {
+            addr,
+            pool: Mutex::new(vec![conn]),
+        })
+    }
+
+    fn checkout(&self) -> Result<Connection> {
+        let conn = self.pool.lock().unwrap_or_else(|e| e.into_inner()).pop();
+        match conn {
+            Some(conn) => Ok(conn),
+            None => Connection::open(&self.addr).map_err(okv_err),
+        }
+    }
+
+    fn checkin(&self, conn: Connection) {
+        self.pool
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(conn);
+    }
+
+    /// Send a request and wait for the response.
+    /// Connections that fail with an IO error are not returned to the pool.
+    pub(crate) fn request(&self, req: &Request) -> Result<Response> {
+        let mut conn = self.checkout()?;
+        let res = conn.request(req).map_err(okv_err)?;
+        self.checkin(conn);
+
+        match res {
+            Response::Error(msg) => Err(okv_err(RemoteError::Server(msg))),
+            Response::Conflict => Err(okv_err(RemoteError::Conflict)),
+            res => Ok(res),
+        }
+    }
+}
+
+impl Innerable for RemoteDb {
+    type Inner = Address;
+    fn inner(&self) -> &Self::Inner {
+        &self.addr
+    }
+}
+
+impl DatabaseBackend for RemoteDb {
+    type Column = RemoteColumn;
+
+    fn create_or_open(env: Env<Self>, db: &str) -> Result<Self::Column> {
+        let req = Request::Open { db: db.to_string() };
+        match env.db().request(&req)? {
+            Response::Unit => Ok(RemoteColumn {
+                env,
+                name: db.to_string(),
+            }),
+            _ => Err(unexpected()),
+        }
+    }
+
+    fn list_databases(&self) -> Result<Vec<String>> {
+        match self.request(&Request::List)? {
+            Response::Names(names) => Ok(names),
+            _ => Err(unexpected()),
+        }
+    }
+
+    fn drop_database(&self, db: &str) -> Result<()> {
+        let req = Request::Drop { db: db.to_string() };
+        match self.request(&req)? {
+            Response::Unit => Ok(()),
+            _ => Err(unexpected()),
+        }
+    }
+
+    fn rename_database(&self, old: &str, new: &str) -> Result<()> {
+        let req = Request::Rename {
+            old: old.to_string(),
+            new: new.to_string(),
+        };
+        match self.request(&req)? {
+            Response::Unit => Ok(()),
+            _ => Err(unexpected()),
+        }
+    }
+}
+
+fn unexpected() -> Error {
+    okv_err(RemoteError::UnexpectedResponse)
+}
+
+enum Stream {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(std::os::unix::net::UnixStream),
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(s) => s.read(buf),
+            #[cfg(unix)]
+            Stream::Unix(s) => s.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(s) => s.write(buf),
+            #[cfg(unix)]
+            Stream::Unix(s) => s.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Tcp(s) => s.flush(),
+            #[cfg(unix)]
+            Stream::Unix(s) => s.flush(),
+        }
+    }
+}
+
+struct Connection(io::BufReader<Stream>);
+
+impl Connection {
+    fn open(addr: &Address) -> io::Result<Self> {
+        let mut stream = match addr {
+            Address::Tcp(addr) => {
+                let stream = TcpStream::connect(addr)?;
+                stream.set_nodelay(true)?;
+                Stream::Tcp(stream)
+            }
+            #[cfg(unix)]
+            Address::Unix(path) => Stream::Unix(std::os::unix::net::UnixStream::connect(path)?),
+        };
+
+        protocol::client_handshake(&mut stream)?;
+        Ok(Self(io::BufReader::new(stream)))
+    }
+
+    fn send(&mut self, req: &Request) -> io::Result<()> {
+        protocol::write_frame(self.0.get_mut(), &req.encode())
+    }
+
+    fn recv(&mut self) -> io::Result<Response> {
+        Response::decode(&protocol::read_frame(&mut self.0)?)
+    }
+
+    fn request(&mut self, req: &Request) -> io::Result<Response> {
+        self.send(req)?;
+        self.recv()
+    }
+}
+
+/// A database on a remote server.
+pub struct RemoteColumn {
+    pub(crate) env: Env<RemoteDb>,
+    pub(crate) name: String,
+}
+
+impl RemoteColumn {
+    fn remote(&self) -> &RemoteDb {
+        self.env.db()
+    }
+
+    fn scan(&self, prefix: Option<Vec<u8>>) -> Result<ScanIter> {
+        let mut conn = self.remote().checkout()?;
+        let req = Request::Scan {
+            db: self.name.clone(),
+            prefix,
+        };
+        conn.send(&req).map_err(okv_err)?;
+
+        Ok(ScanIter {
+            env: self.env.clone(),
+            conn: Some(conn),
+        })
+    }
+}
+
+impl DBColumn for RemoteColumn {
+    fn set(&self, key: impl AsRef<[u8]>, val: impl AsRef<[u8]>) -> Result<()> {
+        let req = Request::Set {
+            db: self.name.clone(),
+            key: key.as_ref().to_vec(),
+            val: val.as_ref().to_vec(),
+        };
+        match self.remote().request(&req)? {
+            Response::Unit => Ok(()),
+            _ => Err(unexpected()),
+        }
+    }
+
+    fn set_nx(&self, key: impl AsRef<[u8]>, val: impl AsRef<[u8]>) -> Result<bool> {
+        let req = Request::SetNx {
+            db: self.name.clone(),
+            key: key.as_ref().to_vec(),
+            val: val.as_ref().to_vec(),
+        };
+        match self.remote().request(&req)? {
+            Response::Bool(res) => Ok(res),
+            _ => Err(unexpected()),
+        }
+    }
+
+    fn get(&self, key: impl AsRef<[u8]>) -> Result<Option<Vec<u8>>> {
+        let req = Request::Get {
+            db: self.name.clone(),
+            key: key.as_ref().to_vec(),
+        };
+        match self.remote().request(&req)? {
+            Response::Value(val) => Ok(val),
+            _ => Err(unexpected()),
+        }
+    }
+
+    fn get_multi<I>(&self, keys: I) -> Result<Vec<Option<Vec<u8>>>>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let req = Request::GetMulti {
+            db: self.name.clone(),
+            keys: keys.into_iter().map(|k| k.as_ref().to_vec()).collect(),
+        };
+        match self.remote().request(&req)? {
+            Response::Values(vals) => Ok(vals),
+            _ => Err(unexpected()),
+        }
+    }
+
+    fn delete(&self, key: impl AsRef<[u8]>) -> Result<()> {
+        let req = Request::Delete {
+            db: self.name.clone(),
+            key: key.as_ref().to_vec(),
+        };
+        match self.remote().request(&req)? {
+            Response::Unit => Ok(()),
+            _ => Err(unexpected()),
+        }
+    }
+
+    fn contains(&self, key: impl AsRef<[u8]>) -> Result<bool> {
+        let req = Request::Contains {
+            db: self.name.clone(),
+            key: key.as_ref().to_vec(),
+        };
+        match self.remote().request(&req)? {
+            Response::Bool(res) => Ok(res),
+            _ => Err(unexpected()),
+        }
+    }
+}
+
+impl RemoteColumn {
+    /// Send a batch to the server, see [`Request::Batch`].
+    fn batch(&self, reads: Vec<(Vec<u8>, Option<Vec<u8>>)>, ops: Vec<BatchOp>) -> Result<()> {
+        if ops.is_empty() {
+            return Ok(());
+        }
+
+        let req = Request::Batch {
+            db: self.name.clone(),
+            reads,
+            ops,
+        };
+        match self.remote().request(&req)? {
+            Response::Unit => Ok(()),
+            _ => Err(unexpected()),
+        }
+    }
+}
+
+impl DBColumnBatch for RemoteColumn {
+    /// The pairs are sent as a single batch, see [`Request::Batch`].
+    fn set_multi<I, K, V>(&self, entries: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        self.write_multi(entries.into_iter().map(|(key, val)| (key, Some(val))))
+    }
+
+    /// The writes are sent as a single batch, see [`Request::Batch`].
+    fn write_multi<I, K, V>(&self, writes: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (K, Option<V>)>,
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        let ops = writes
+            .into_iter()
+            .map(|(key, val)| match val {
+                Some(val) => BatchOp::Set(key.as_ref().to_vec(), val.as_ref().to_vec()),
+                None => BatchOp::Delete(key.as_ref().to_vec()),
+            })
+            .collect();
+        self.batch(Vec::new(), ops)
+    }
+}
+
+impl DBColumnIterator for RemoteColumn {
+    fn iter(&self) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>> {
+        self.scan(None)
+    }
+}
+
+impl DBColumnIteratorPrefix for RemoteColumn {
+    fn iter_prefix(
+        &self,
+        prefix: impl AsRef<[u8]>,
+    ) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>> {
+        self.scan(Some(prefix.as_ref().to_vec()))
+    }
+}
+
+/// Streams the results of a scan from a dedicated connection.
+/// The connection is returned to the pool once the scan has been fully consumed.
+struct ScanIter {
+    env: Env<RemoteDb>,
+    conn: Option<Connection>,
+}
+
+impl Iterator for ScanIter {
+    type Item = Result<(Vec<u8>, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let conn = self.conn.as_mut()?;
+        let res = match conn.recv() {
+            Ok(Response::Item(key, val)) => return Some(Ok((key, val))),
+            Ok(Response::End) => None,
+            Ok(Response::Error(msg)) => Some(Err(okv_err(RemoteError::Server(msg)))),
+            Ok(_) => {
+                // the connection is out of sync, so it can't be reused
+                self.conn = None;
+                return Some(Err(unexpected()));
+            }
+            Err(e) => {
+                self.conn = None;
+                return Some(Err(okv_err(e)));
+            }
+        };
+
+        if let Some(conn) = self.conn.take() {
+            self.env.db().checkin(conn);
+        }
+        res
+    }
+}
+
+/// A transaction on a remote database.
+///
+/// Writes are buffered locally and sent to the server as a single batch on commit.
+/// Reads see the buffered writes of this transaction. On commit, the server checks every
+/// key read in the transaction against its current value and the commit fails with
+/// [`RemoteError::Conflict`] if any of them was modified since. The writes are applied
+/// in a single write batch of the server's backend.
+pub struct RemoteTransaction<'a> {
+    column: &'a RemoteColumn,
+    reads: RefCell<HashMap<Vec<u8>, Option<Vec<u8>>>>,
+    writes: RefCell<BTreeMap<Vec<u8>, Option<Vec<u8>>>>,
+}
+
+impl<'a> DBColumnTransaction<'a> for RemoteColumn {
+    type Txn = RemoteTransaction<'a>;
+
+    fn transaction(&'a self) -> Result<Self::Txn> {
+        Ok(RemoteTransaction {
+            column: self,
+            reads: RefCell::new(HashMap::new()),
+            writes: RefCell::new(BTreeMap::new()),
+        })
+    }
+}
+
+impl<'a> RemoteTransaction<'a> {
+    /// Read keys from the server and remember the values that were read.
+    fn read(&self, keys: &[Vec<u8>]) -> Result<Vec<Option<Vec<u8>>>> {
+        let missing: Vec<&Vec<u8>> = {
+            let reads = self.reads.borrow();
+            keys.iter().filter(|k| !reads.contains_key(*k)).collect()
+        };
+
+        if !missing.is_empty() {
+            let vals = self.column.get_multi(&missing)?;
+            let mut reads = self.reads.borrow_mut();
+            for (key, val) in missing.into_iter().zip(vals) {
+                reads.insert(key.clone(), val);
+            }
+        }
+
+        let reads = self.reads.borrow();
+        Ok(keys.iter().map(|key| reads[key].clone()).collect())
+    }
+}
+
+impl<'a> DBColumn for RemoteTransaction<'a> {
+    fn set(&self, key: impl AsRef<[u8]>, val: impl AsRef<[u8]>) -> Result<()> {
+        self.writes
+            .borrow_mut()
+            .insert(key.as_ref().to_vec(), Some(val.as_ref().to_vec()));
+        Ok(())
+    }
+
+    fn get(&self, key: impl AsRef<[u8]>) -> Result<Option<Vec<u8>>> {
+        self.get_multi([key]).map(|mut vals| vals.remove(0))
+    }
+
+    fn get_multi<I>(&self, keys: I) -> Result<Vec<Option<Vec<u8>>>>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let keys: Vec<Vec<u8>> = keys.into_iter().map(|k| k.as_ref().to_vec()).collect();
+        let writes = self.writes.borrow();
+
+        // only read the keys that weren't written in this transaction
+        let missing: Vec<Vec<u8>> = keys
+            .iter()
+            .filter(|k| !writes.contains_key(*k))
+            .cloned()
+            .collect();
+        let mut remote = self.read(&missing)?.into_iter();
+
+        keys.iter()
+            .map(|key| match writes.get(key) {
+                Some(val) => Ok(val.clone()),
+                None => remote.next().ok_or_else(unexpected),
+            })
+            .collect()
+    }
+
+    fn delete(&self, key: impl AsRef<[u8]>) -> Result<()> {
+        self.writes.borrow_mut().insert(key.as_ref().to_vec(), None);
+        Ok(())
+    }
+
+    fn contains(&self, key: impl AsRef<[u8]>) -> Result<bool> {
+        Ok(self.get(key)?.is_some())
+    }
+}
+
+impl<'a> DBTransaction for RemoteTransaction<'a> {
+    fn commit(self) -> Result<()> {
+        let reads = self.reads.into_inner().into_iter().collect();
+        let ops = self
+            .writes
+            .into_inner()
+            .into_iter()
+            .map(|(key, val)| match val {
+                Some(val) => BatchOp::Set(key, val),
+                None => BatchOp::Delete(key),
+            })
+            .collect();
+
+        self.column.batch(reads, ops)
+    }
+
+    fn rollback(self) -> Result<()> {
+        Ok(())
+    }
+}
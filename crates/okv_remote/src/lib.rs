@@ -0,0 +1,93 @@
+//! Remote database backend for OKV.
+//!
+//! [`Server`] exposes any [`Env`](okv_core::env::Env) over TCP or a Unix socket, and [`RemoteDb`]
+//! is a [`DatabaseBackend`](okv_core::backend::DatabaseBackend) that talks to such a server.
+//! This allows multiple processes to share a single embedded database like RocksDB or redb.
+//!
+//! Addresses are given as `tcp://host:port` or `unix:///path/to/socket`.
+//! An address without a scheme is treated as a TCP address.
+
+use std::{fmt, path::PathBuf};
+
+use okv_core::error::Error;
+use thiserror::Error;
+
+mod r#async;
+mod client;
+pub mod protocol;
+mod server;
+
+pub use client::{RemoteColumn, RemoteDb, RemoteTransaction};
+pub use server::Server;
+
+/// An error that can occur when talking to a remote database.
+#[derive(Error, Debug)]
+pub enum RemoteError {
+    /// An IO error occurred on the connection.
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// The server failed to execute the request.
+    #[error("Server error: {0}")]
+    Server(String),
+
+    /// The server answered with a response that doesn't match the request.
+    #[error("Unexpected response from server")]
+    UnexpectedResponse,
+
+    /// A transaction was not committed, because a key it read has been modified.
+    #[error("Transaction conflict, a key read in the transaction was modified")]
+    Conflict,
+
+    /// The given address could not be parsed.
+    #[error("Invalid address: {0}")]
+    InvalidAddress(String),
+}
+
+pub(crate) fn okv_err(e: impl Into<RemoteError>) -> Error {
+    Error::DatabaseBackend(Box::new(e.into()))
+}
+
+/// The address of a remote database server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Address {
+    /// A TCP address like `127.0.0.1:7777`.
+    Tcp(String),
+
+    /// The path of a Unix domain socket.
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+impl Address {
+    /// Parse an address like `tcp://127.0.0.1:7777` or `unix:///tmp/okv.sock`.
+    pub fn parse(addr: &str) -> Result<Self, RemoteError> {
+        if let Some(addr) = addr.strip_prefix("tcp://") {
+            return Ok(Self::Tcp(addr.to_string()));
+        }
+
+        if let Some(path) = addr.strip_prefix("unix://") {
+            #[cfg(unix)]
+            return Ok(Self::Unix(PathBuf::from(path)));
+            #[cfg(not(unix))]
+            return Err(RemoteError::InvalidAddress(format!(
+                "unix sockets are not supported on this platform: {path}"
+            )));
+        }
+
+        match addr.contains("://") {
+            true => Err(RemoteError::InvalidAddress(addr.to_string())),
+            false => Ok(Self::Tcp(addr.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(addr) => write!(f, "tcp://{addr}"),
+            #[cfg(unix)]
+            Self::Unix(path) => write!(f, "unix://{}", path.display()),
+        }
+    }
+}
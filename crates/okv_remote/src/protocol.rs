@@ -0,0 +1,473 @@
+//! Wire format shared by the client and the server.
+//!
+//! Every message is a frame: a big-endian `u32` length followed by that many bytes.
+//! The first byte of a request frame is the opcode, the first byte of a response
+//! frame is the status. Byte strings inside a frame are again prefixed with a `u32` length.
+//!
+//! A connection starts with a handshake: the client sends [`MAGIC`] followed by
+//! [`PROTOCOL_VERSION`] and the server answers with the same four bytes.
+
+use std::io::{self, Read, Write};
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+
+/// Bytes sent at the start of every connection.
+pub const MAGIC: &[u8; 3] = b"OKV";
+
+/// Version of the wire format. Bumped on every incompatible change.
+pub const PROTOCOL_VERSION: u8 = 2;
+
+/// Frames larger than this are rejected to protect against corrupt length prefixes.
+pub const MAX_FRAME_LEN: u32 = 256 * 1024 * 1024;
+
+mod op {
+    pub(super) const GET: u8 = 0x01;
+    pub(super) const SET: u8 = 0x02;
+    pub(super) const SET_NX: u8 = 0x03;
+    pub(super) const DELETE: u8 = 0x04;
+    pub(super) const CONTAINS: u8 = 0x05;
+    pub(super) const GET_MULTI: u8 = 0x06;
+    pub(super) const SCAN: u8 = 0x07;
+    pub(super) const BATCH: u8 = 0x08;
+    pub(super) const OPEN: u8 = 0x09;
+    pub(super) const LIST: u8 = 0x0a;
+    pub(super) const DROP: u8 = 0x0b;
+    pub(super) const RENAME: u8 = 0x0c;
+}
+
+mod status {
+    pub(super) const OK: u8 = 0x00;
+    pub(super) const ERROR: u8 = 0x01;
+    pub(super) const ITEM: u8 = 0x02;
+    pub(super) const END: u8 = 0x03;
+    pub(super) const CONFLICT: u8 = 0x04;
+}
+
+/// A single write inside a [`Request::Batch`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchOp {
+    /// Set a key to a value.
+    Set(Vec<u8>, Vec<u8>),
+    /// Delete a key.
+    Delete(Vec<u8>),
+}
+
+/// A request sent from the client to the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Request {
+    /// Create or open a database.
+    Open { db: String },
+    /// Get a value by key.
+    Get { db: String, key: Vec<u8> },
+    /// Set a key-value pair.
+    Set {
+        db: String,
+        key: Vec<u8>,
+        val: Vec<u8>,
+    },
+    /// Set a key-value pair if the key does not exist.
+    SetNx {
+        db: String,
+        key: Vec<u8>,
+        val: Vec<u8>,
+    },
+    /// Delete a key-value pair.
+    Delete { db: String, key: Vec<u8> },
+    /// Check if a key exists.
+    Contains { db: String, key: Vec<u8> },
+    /// Get values by keys in batch.
+    GetMulti { db: String, keys: Vec<Vec<u8>> },
+    /// Stream all key-value pairs, optionally restricted to a prefix.
+    /// Answered with any number of [`Response::Item`] frames followed by [`Response::End`].
+    Scan { db: String, prefix: Option<Vec<u8>> },
+    /// Apply a list of writes in a single write batch of the backend, if every key in `reads`
+    /// still has the given value. Answered with [`Response::Conflict`] otherwise.
+    Batch {
+        db: String,
+        reads: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+        ops: Vec<BatchOp>,
+    },
+    /// List the names of all databases.
+    List,
+    /// Delete a database and all of its entries.
+    Drop { db: String },
+    /// Rename a database.
+    Rename { old: String, new: String },
+}
+
+/// A response sent from the server to the client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Response {
+    /// The request succeeded without a value.
+    Unit,
+    /// The request succeeded with a boolean result.
+    Bool(bool),
+    /// The request succeeded with an optional value.
+    Value(Option<Vec<u8>>),
+    /// The request succeeded with a list of optional values.
+    Values(Vec<Option<Vec<u8>>>),
+    /// The request succeeded with a list of database names.
+    Names(Vec<String>),
+    /// A single key-value pair of a scan.
+    Item(Vec<u8>, Vec<u8>),
+    /// The end of a scan.
+    End,
+    /// A batch was not applied, because a key it read has been modified.
+    Conflict,
+    /// The request failed on the server.
+    Error(String),
+}
+
+// Response payload tags, following the status byte of an `OK` frame.
+mod tag {
+    pub(super) const UNIT: u8 = 0x00;
+    pub(super) const BOOL: u8 = 0x01;
+    pub(super) const VALUE: u8 = 0x02;
+    pub(super) const VALUES: u8 = 0x03;
+    pub(super) const NAMES: u8 = 0x04;
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Read a single frame.
+pub fn read_frame(r: &mut impl Read) -> io::Result<Vec<u8>> {
+    let len = r.read_u32::<BigEndian>()?;
+    if len > MAX_FRAME_LEN {
+        return Err(invalid("frame exceeds maximum length"));
+    }
+
+    let mut buf = vec![0; len as usize];
+    r.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+/// Write a single frame.
+pub fn write_frame(w: &mut impl Write, frame: &[u8]) -> io::Result<()> {
+    let len = u32::try_from(frame.len())
+        .ok()
+        .filter(|len| *len <= MAX_FRAME_LEN)
+        .ok_or_else(|| invalid("frame exceeds maximum length"))?;
+
+    // write the frame with a single call to avoid small packets on unbuffered sockets
+    let mut buf = Vec::with_capacity(frame.len() + 4);
+    buf.write_u32::<BigEndian>(len)?;
+    buf.extend_from_slice(frame);
+    w.write_all(&buf)
+}
+
+/// Send the client side of the handshake and wait for the server to confirm it.
+pub fn client_handshake(stream: &mut (impl Read + Write)) -> io::Result<()> {
+    stream.write_all(MAGIC)?;
+    stream.write_u8(PROTOCOL_VERSION)?;
+    stream.flush()?;
+    read_handshake(stream)
+}
+
+/// Wait for the client handshake and confirm it.
+pub fn server_handshake(stream: &mut (impl Read + Write)) -> io::Result<()> {
+    read_handshake(stream)?;
+    stream.write_all(MAGIC)?;
+    stream.write_u8(PROTOCOL_VERSION)?;
+    stream.flush()
+}
+
+fn read_handshake(r: &mut impl Read) -> io::Result<()> {
+    let mut buf = [0; 4];
+    r.read_exact(&mut buf)?;
+    if &buf[..3] != MAGIC {
+        return Err(invalid("not an okv connection"));
+    }
+    if buf[3] != PROTOCOL_VERSION {
+        return Err(invalid("unsupported protocol version"));
+    }
+    Ok(())
+}
+
+fn put_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    buf.write_u32::<BigEndian>(bytes.len() as u32)
+        .expect("writing to a Vec can't fail");
+    buf.extend_from_slice(bytes);
+}
+
+fn put_option(buf: &mut Vec<u8>, bytes: Option<&[u8]>) {
+    match bytes {
+        None => buf.push(0),
+        Some(bytes) => {
+            buf.push(1);
+            put_bytes(buf, bytes);
+        }
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn u8(&mut self) -> io::Result<u8> {
+        self.0.read_u8()
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        self.0.read_u32::<BigEndian>()
+    }
+
+    fn bytes(&mut self) -> io::Result<Vec<u8>> {
+        let len = self.u32()? as usize;
+        if len > self.0.len() {
+            return Err(invalid("truncated frame"));
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes.to_vec())
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        String::from_utf8(self.bytes()?).map_err(|_| invalid("invalid utf-8"))
+    }
+
+    fn option(&mut self) -> io::Result<Option<Vec<u8>>> {
+        match self.u8()? {
+            0 => Ok(None),
+            1 => Ok(Some(self.bytes()?)),
+            _ => Err(invalid("invalid option tag")),
+        }
+    }
+
+    fn finish(self) -> io::Result<()> {
+        match self.0.is_empty() {
+            true => Ok(()),
+            false => Err(invalid("trailing bytes in frame")),
+        }
+    }
+}
+
+impl Request {
+    /// Encode the request as a frame body.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        match self {
+            Request::Open { db } => {
+                buf.push(op::OPEN);
+                put_bytes(&mut buf, db.as_bytes());
+            }
+            Request::Get { db, key } => {
+                buf.push(op::GET);
+                put_bytes(&mut buf, db.as_bytes());
+                put_bytes(&mut buf, key);
+            }
+            Request::Set { db, key, val } => {
+                buf.push(op::SET);
+                put_bytes(&mut buf, db.as_bytes());
+                put_bytes(&mut buf, key);
+                put_bytes(&mut buf, val);
+            }
+            Request::SetNx { db, key, val } => {
+                buf.push(op::SET_NX);
+                put_bytes(&mut buf, db.as_bytes());
+                put_bytes(&mut buf, key);
+                put_bytes(&mut buf, val);
+            }
+            Request::Delete { db, key } => {
+                buf.push(op::DELETE);
+                put_bytes(&mut buf, db.as_bytes());
+                put_bytes(&mut buf, key);
+            }
+            Request::Contains { db, key } => {
+                buf.push(op::CONTAINS);
+                put_bytes(&mut buf, db.as_bytes());
+                put_bytes(&mut buf, key);
+            }
+            Request::GetMulti { db, keys } => {
+                buf.push(op::GET_MULTI);
+                put_bytes(&mut buf, db.as_bytes());
+                buf.write_u32::<BigEndian>(keys.len() as u32)
+                    .expect("writing to a Vec can't fail");
+                for key in keys {
+                    put_bytes(&mut buf, key);
+                }
+            }
+            Request::Scan { db, prefix } => {
+                buf.push(op::SCAN);
+                put_bytes(&mut buf, db.as_bytes());
+                put_option(&mut buf, prefix.as_deref());
+            }
+            Request::Batch { db, reads, ops } => {
+                buf.push(op::BATCH);
+                put_bytes(&mut buf, db.as_bytes());
+                buf.write_u32::<BigEndian>(reads.len() as u32)
+                    .expect("writing to a Vec can't fail");
+                for (key, val) in reads {
+                    put_bytes(&mut buf, key);
+                    put_option(&mut buf, val.as_deref());
+                }
+                buf.write_u32::<BigEndian>(ops.len() as u32)
+                    .expect("writing to a Vec can't fail");
+                for op in ops {
+                    match op {
+                        BatchOp::Set(key, val) => {
+                            buf.push(0);
+                            put_bytes(&mut buf, key);
+                            put_bytes(&mut buf, val);
+                        }
+                        BatchOp::Delete(key) => {
+                            buf.push(1);
+                            put_bytes(&mut buf, key);
+                        }
+                    }
+                }
+            }
+            Request::List => buf.push(op::LIST),
+            Request::Drop { db } => {
+                buf.push(op::DROP);
+                put_bytes(&mut buf, db.as_bytes());
+            }
+            Request::Rename { old, new } => {
+                buf.push(op::RENAME);
+                put_bytes(&mut buf, old.as_bytes());
+                put_bytes(&mut buf, new.as_bytes());
+            }
+        }
+        buf
+    }
+
+    /// Decode a request from a frame body.
+    pub fn decode(frame: &[u8]) -> io::Result<Self> {
+        let mut r = Reader(frame);
+        let req = match r.u8()? {
+            op::OPEN => Request::Open { db: r.string()? },
+            op::GET => Request::Get {
+                db: r.string()?,
+                key: r.bytes()?,
+            },
+            op::SET => Request::Set {
+                db: r.string()?,
+                key: r.bytes()?,
+                val: r.bytes()?,
+            },
+            op::SET_NX => Request::SetNx {
+                db: r.string()?,
+                key: r.bytes()?,
+                val: r.bytes()?,
+            },
+            op::DELETE => Request::Delete {
+                db: r.string()?,
+                key: r.bytes()?,
+            },
+            op::CONTAINS => Request::Contains {
+                db: r.string()?,
+                key: r.bytes()?,
+            },
+            op::GET_MULTI => {
+                let db = r.string()?;
+                let len = r.u32()?;
+                let keys = (0..len).map(|_| r.bytes()).collect::<io::Result<_>>()?;
+                Request::GetMulti { db, keys }
+            }
+            op::SCAN => Request::Scan {
+                db: r.string()?,
+                prefix: r.option()?,
+            },
+            op::BATCH => {
+                let db = r.string()?;
+                let len = r.u32()?;
+                let reads = (0..len)
+                    .map(|_| Ok((r.bytes()?, r.option()?)))
+                    .collect::<io::Result<_>>()?;
+                let len = r.u32()?;
+                let ops = (0..len)
+                    .map(|_| match r.u8()? {
+                        0 => Ok(BatchOp::Set(r.bytes()?, r.bytes()?)),
+                        1 => Ok(BatchOp::Delete(r.bytes()?)),
+                        _ => Err(invalid("unknown batch operation")),
+                    })
+                    .collect::<io::Result<_>>()?;
+                Request::Batch { db, reads, ops }
+            }
+            op::LIST => Request::List,
+            op::DROP => Request::Drop { db: r.string()? },
+            op::RENAME => Request::Rename {
+                old: r.string()?,
+                new: r.string()?,
+            },
+            _ => return Err(invalid("unknown opcode")),
+        };
+
+        r.finish()?;
+        Ok(req)
+    }
+}
+
+impl Response {
+    /// Encode the response as a frame body.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        match self {
+            Response::Unit => buf.extend_from_slice(&[status::OK, tag::UNIT]),
+            Response::Bool(b) => buf.extend_from_slice(&[status::OK, tag::BOOL, *b as u8]),
+            Response::Value(val) => {
+                buf.extend_from_slice(&[status::OK, tag::VALUE]);
+                put_option(&mut buf, val.as_deref());
+            }
+            Response::Values(vals) => {
+                buf.extend_from_slice(&[status::OK, tag::VALUES]);
+                buf.write_u32::<BigEndian>(vals.len() as u32)
+                    .expect("writing to a Vec can't fail");
+                for val in vals {
+                    put_option(&mut buf, val.as_deref());
+                }
+            }
+            Response::Names(names) => {
+                buf.extend_from_slice(&[status::OK, tag::NAMES]);
+                buf.write_u32::<BigEndian>(names.len() as u32)
+                    .expect("writing to a Vec can't fail");
+                for name in names {
+                    put_bytes(&mut buf, name.as_bytes());
+                }
+            }
+            Response::Item(key, val) => {
+                buf.push(status::ITEM);
+                put_bytes(&mut buf, key);
+                put_bytes(&mut buf, val);
+            }
+            Response::End => buf.push(status::END),
+            Response::Conflict => buf.push(status::CONFLICT),
+            Response::Error(msg) => {
+                buf.push(status::ERROR);
+                put_bytes(&mut buf, msg.as_bytes());
+            }
+        }
+        buf
+    }
+
+    /// Decode a response from a frame body.
+    pub fn decode(frame: &[u8]) -> io::Result<Self> {
+        let mut r = Reader(frame);
+        let res = match r.u8()? {
+            status::OK => match r.u8()? {
+                tag::UNIT => Response::Unit,
+                tag::BOOL => Response::Bool(r.u8()? != 0),
+                tag::VALUE => Response::Value(r.option()?),
+                tag::VALUES => {
+                    let len = r.u32()?;
+                    let vals = (0..len).map(|_| r.option()).collect::<io::Result<_>>()?;
+                    Response::Values(vals)
+                }
+                tag::NAMES => {
+                    let len = r.u32()?;
+                    let names = (0..len).map(|_| r.string()).collect::<io::Result<_>>()?;
+                    Response::Names(names)
+                }
+                _ => return Err(invalid("unknown response tag")),
+            },
+            status::ITEM => Response::Item(r.bytes()?, r.bytes()?),
+            status::END => Response::End,
+            status::CONFLICT => Response::Conflict,
+            status::ERROR => Response::Error(r.string()?),
+            _ => return Err(invalid("unknown response status")),
+        };
+
+        r.finish()?;
+        Ok(res)
+    }
+}
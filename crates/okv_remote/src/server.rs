@@ -0,0 +1,251 @@
+use std::{
+    collections::HashMap,
+    io::{self, BufReader, Read, Write},
+    net::TcpListener,
+    sync::{Arc, RwLock},
+};
+
+use okv_core::{
+    backend::*,
+    env::Env,
+    error::{Error, Result},
+};
+
+use crate::{
+    okv_err,
+    protocol::{self, BatchOp, Request, Response},
+    Address,
+};
+
+/// Serves an [`Env`] to [`RemoteDb`](crate::RemoteDb) clients.
+///
+/// Every connection is handled on its own thread.
+/// Batches (committed transactions) and `set_nx` are executed exclusively, so they don't
+/// interleave with writes from other clients of the same server.
+/// A batch first checks the values its transaction read, and is answered with a conflict if
+/// any of them changed. Its writes are then applied with
+/// [`write_multi`](DBColumnBatch::write_multi), so they are atomic if the backend's write
+/// batches are.
+pub struct Server<D: DatabaseBackend>(Arc<ServerInner<D>>);
+
+impl<D: DatabaseBackend> Clone for Server<D> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+struct ServerInner<D: DatabaseBackend> {
+    env: Env<D>,
+    columns: RwLock<HashMap<String, Arc<D::Column>>>,
+    // single writes take a shared lock, batches and set_nx take an exclusive lock
+    write_lock: RwLock<()>,
+}
+
+impl<D> Server<D>
+where
+    D: DatabaseBackend + 'static,
+    D::Column: DBColumn
+        + DBColumnBatch
+        + DBColumnIterator
+        + DBColumnIteratorPrefix
+        + Send
+        + Sync
+        + 'static,
+{
+    /// Create a new server for the given environment.
+    pub fn new(env: Env<D>) -> Self {
+        Self(Arc::new(ServerInner {
+            env,
+            columns: RwLock::new(HashMap::new()),
+            write_lock: RwLock::new(()),
+        }))
+    }
+
+    /// Listen on the given address and serve clients until an error occurs.
+    ///
+    /// See [`Address::parse`] for the supported address formats.
+    pub fn listen(&self, addr: &str) -> Result<()> {
+        match Address::parse(addr).map_err(okv_err)? {
+            Address::Tcp(addr) => self.serve_tcp(TcpListener::bind(addr).map_err(okv_err)?),
+            #[cfg(unix)]
+            Address::Unix(path) => {
+                let listener = std::os::unix::net::UnixListener::bind(path).map_err(okv_err)?;
+                self.serve_unix(listener)
+            }
+        }
+    }
+
+    /// Serve clients connecting to the given TCP listener.
+    pub fn serve_tcp(&self, listener: TcpListener) -> Result<()> {
+        for stream in listener.incoming() {
+            let stream = stream.map_err(okv_err)?;
+            stream.set_nodelay(true).map_err(okv_err)?;
+            self.spawn(stream);
+        }
+        Ok(())
+    }
+
+    /// Serve clients connecting to the given Unix socket listener.
+    #[cfg(unix)]
+    pub fn serve_unix(&self, listener: std::os::unix::net::UnixListener) -> Result<()> {
+        for stream in listener.incoming() {
+            self.spawn(stream.map_err(okv_err)?);
+        }
+        Ok(())
+    }
+
+    fn spawn(&self, stream: impl Read + Write + Send + 'static) {
+        let server = self.clone();
+        std::thread::spawn(move || {
+            // a failing connection only affects its own client
+            let _ = server.handle_connection(stream);
+        });
+    }
+
+    /// Handle a single client connection until it is closed.
+    pub fn handle_connection(&self, mut stream: impl Read + Write) -> io::Result<()> {
+        protocol::server_handshake(&mut stream)?;
+        let mut stream = BufReader::new(stream);
+
+        loop {
+            let frame = match protocol::read_frame(&mut stream) {
+                Ok(frame) => frame,
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+                Err(e) => return Err(e),
+            };
+
+            let req = Request::decode(&frame)?;
+            let out = stream.get_mut();
+            match req {
+                Request::Scan { db, prefix } => self.scan(out, &db, prefix)?,
+                req => {
+                    let res = self
+                        .execute(req)
+                        .unwrap_or_else(|e| Response::Error(e.to_string()));
+                    protocol::write_frame(out, &res.encode())?;
+                }
+            }
+            out.flush()?;
+        }
+    }
+
+    fn column(&self, db: &str) -> Result<Arc<D::Column>> {
+        let columns = self.0.columns.read().unwrap_or_else(|e| e.into_inner());
+        if let Some(col) = columns.get(db) {
+            return Ok(col.clone());
+        }
+        drop(columns);
+
+        let mut columns = self.0.columns.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(col) = columns.get(db) {
+            return Ok(col.clone());
+        }
+
+        let col = Arc::new(D::create_or_open(self.0.env.clone(), db)?);
+        columns.insert(db.to_string(), col.clone());
+        Ok(col)
+    }
+
+    /// Remove a cached column, after its database has been dropped or renamed.
+    fn forget(&self, db: &str) {
+        let mut columns = self.0.columns.write().unwrap_or_else(|e| e.into_inner());
+        columns.remove(db);
+    }
+
+    fn execute(&self, req: Request) -> Result<Response> {
+        let shared = || self.0.write_lock.read().unwrap_or_else(|e| e.into_inner());
+        let exclusive = || self.0.write_lock.write().unwrap_or_else(|e| e.into_inner());
+
+        let res = match req {
+            Request::Open { db } => {
+                self.column(&db)?;
+                Response::Unit
+            }
+            Request::Get { db, key } => Response::Value(self.column(&db)?.get(key)?),
+            Request::Contains { db, key } => Response::Bool(self.column(&db)?.contains(key)?),
+            Request::GetMulti { db, keys } => Response::Values(self.column(&db)?.get_multi(keys)?),
+            Request::Set { db, key, val } => {
+                let col = self.column(&db)?;
+                let _guard = shared();
+                col.set(key, val)?;
+                Response::Unit
+            }
+            Request::Delete { db, key } => {
+                let col = self.column(&db)?;
+                let _guard = shared();
+                col.delete(key)?;
+                Response::Unit
+            }
+            Request::SetNx { db, key, val } => {
+                let col = self.column(&db)?;
+                let _guard = exclusive();
+                Response::Bool(col.set_nx(key, val)?)
+            }
+            Request::Batch { db, reads, ops } => {
+                let col = self.column(&db)?;
+                let _guard = exclusive();
+
+                let (keys, expected): (Vec<_>, Vec<_>) = reads.into_iter().unzip();
+                if !keys.is_empty() && col.get_multi(keys)? != expected {
+                    return Ok(Response::Conflict);
+                }
+
+                col.write_multi(ops.into_iter().map(|op| match op {
+                    BatchOp::Set(key, val) => (key, Some(val)),
+                    BatchOp::Delete(key) => (key, None),
+                }))?;
+                Response::Unit
+            }
+            Request::List => Response::Names(self.0.env.databases()?),
+            Request::Drop { db } => {
+                let _guard = exclusive();
+                self.0.env.drop_database(&db)?;
+                self.forget(&db);
+                Response::Unit
+            }
+            Request::Rename { old, new } => {
+                let _guard = exclusive();
+                self.0.env.rename_database(&old, &new)?;
+                self.forget(&old);
+                self.forget(&new);
+                Response::Unit
+            }
+            Request::Scan { .. } => {
+                return Err(Error::Unknown(
+                    "scan can't be executed as a single request".into(),
+                ))
+            }
+        };
+
+        Ok(res)
+    }
+
+    fn scan(&self, out: &mut impl Write, db: &str, prefix: Option<Vec<u8>>) -> io::Result<()> {
+        let res = self.column(db).and_then(|col| match prefix {
+            Some(prefix) => write_items(out, col.iter_prefix(prefix)?),
+            None => write_items(out, col.iter()?),
+        });
+
+        match res {
+            Ok(Ok(())) => protocol::write_frame(out, &Response::End.encode()),
+            Ok(Err(e)) => Err(e),
+            Err(e) => protocol::write_frame(out, &Response::Error(e.to_string()).encode()),
+        }
+    }
+}
+
+/// Write all items of an iterator as [`Response::Item`] frames.
+/// Database errors end the scan early and are returned in the outer result,
+/// IO errors are returned in the inner result.
+fn write_items(
+    out: &mut impl Write,
+    iter: impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>,
+) -> Result<io::Result<()>> {
+    for item in iter {
+        let (key, val) = item?;
+        if let Err(e) = protocol::write_frame(out, &Response::Item(key, val).encode()) {
+            return Ok(Err(e));
+        }
+    }
+    Ok(Ok(()))
+}
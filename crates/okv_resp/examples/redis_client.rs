@@ -0,0 +1,87 @@
+use std::net::TcpListener;
+
+use eyre::Result;
+use okv_core::env::Env;
+use okv_memory::MemDB;
+use okv_resp::RespServer;
+
+fn main() -> Result<()> {
+    // serve an in-memory database on a random port
+    // usually, this would be a separate process running `okv-resp-server`
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+    let server = RespServer::new(Env::new(MemDB::new()));
+    std::thread::spawn(move || server.serve_tcp(listener));
+
+    // connect with a regular redis client
+    let client = redis::Client::open(format!("redis://{addr}/"))?;
+    let mut con = client.get_connection()?;
+
+    redis::cmd("SET")
+        .arg("hello")
+        .arg("world")
+        .query::<()>(&mut con)?;
+    let val: Option<String> = redis::cmd("GET").arg("hello").query(&mut con)?;
+    assert_eq!(val.as_deref(), Some("world"));
+
+    let set: bool = redis::cmd("SETNX")
+        .arg("hello")
+        .arg("sailor")
+        .query(&mut con)?;
+    assert!(!set);
+
+    let vals: Vec<Option<String>> = redis::cmd("MGET")
+        .arg("hello")
+        .arg("nope")
+        .query(&mut con)?;
+    assert_eq!(vals, vec![Some("world".to_string()), None]);
+
+    for i in 0..25 {
+        redis::cmd("SET")
+            .arg(format!("user:{i}"))
+            .arg(i)
+            .query::<()>(&mut con)?;
+    }
+    let keys: Vec<String> = redis::cmd("SCAN")
+        .cursor_arg(0)
+        .arg("MATCH")
+        .arg("user:*")
+        .clone()
+        .iter(&mut con)?
+        .collect();
+    assert_eq!(keys.len(), 25);
+    // keys are scanned in key order
+    assert!(keys.windows(2).all(|w| w[0] < w[1]));
+
+    // every key is a string
+    let keys: Vec<String> = redis::cmd("SCAN")
+        .cursor_arg(0)
+        .arg("TYPE")
+        .arg("hash")
+        .clone()
+        .iter(&mut con)?
+        .collect();
+    assert!(keys.is_empty());
+
+    // databases map to OKV databases
+    redis::cmd("SELECT").arg("other").query::<()>(&mut con)?;
+    let exists: i64 = redis::cmd("EXISTS").arg("hello").query(&mut con)?;
+    assert_eq!(exists, 0);
+
+    // internal databases can't be selected
+    let res = redis::cmd("SELECT")
+        .arg("__okv_schema")
+        .query::<()>(&mut con);
+    assert!(res.is_err());
+
+    redis::cmd("SELECT").arg("0").query::<()>(&mut con)?;
+    let removed: i64 = redis::cmd("DEL").arg("hello").arg("nope").query(&mut con)?;
+    assert_eq!(removed, 1);
+
+    Ok(())
+}
+
+#[test] // ensure that the example always works
+fn test() -> Result<()> {
+    main()
+}
@@ -0,0 +1,55 @@
+//! Serve an OKV environment to Redis clients.
+//!
+//! ```text
+//! okv-resp-server <listen-address> <backend>
+//!
+//! listen-address: 127.0.0.1:6379 or unix:///tmp/okv.sock
+//! backend:        memory, redb:<path> or rocksdb:<path>
+//! ```
+
+use std::process::ExitCode;
+
+use okv_core::{env::Env, error::Error, error::Result};
+use okv_resp::RespServer;
+
+fn serve(listen: &str, backend: &str) -> Result<()> {
+    match backend.split_once(':').unwrap_or((backend, "")) {
+        #[cfg(feature = "memory")]
+        ("memory", _) => RespServer::new(Env::new(okv_memory::MemDB::new())).listen(listen),
+
+        #[cfg(feature = "redb")]
+        ("redb", path) => {
+            let db = okv_redb::Redb::new(path).map_err(|e| Error::DatabaseBackend(Box::new(e)))?;
+            RespServer::new(Env::new(db)).listen(listen)
+        }
+
+        #[cfg(feature = "rocksdb")]
+        ("rocksdb", path) => {
+            std::fs::create_dir_all(path).map_err(|e| Error::Unknown(e.to_string()))?;
+            let db =
+                okv_rocksdb::RocksDb::new(path).map_err(|e| Error::DatabaseBackend(Box::new(e)))?;
+            RespServer::new(Env::new(db)).listen(listen)
+        }
+
+        _ => Err(Error::Unknown(format!("unsupported backend: {backend}"))),
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().collect();
+    let [_, listen, backend] = args.as_slice() else {
+        eprintln!("usage: okv-resp-server <listen-address> <backend>");
+        eprintln!();
+        eprintln!("  listen-address: 127.0.0.1:6379 or unix:///tmp/okv.sock");
+        eprintln!("  backend:        memory, redb:<path> or rocksdb:<path>");
+        return ExitCode::FAILURE;
+    };
+
+    match serve(listen, backend) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,476 @@
+//! Redis protocol (RESP) front-end for OKV.
+//!
+//! [`RespServer`] exposes any [`Env`] to existing Redis clients and tooling.
+//! Every Redis database maps to an OKV database of the same name: `SELECT 1` opens the
+//! database `"1"`, `SELECT users` opens the database `"users"`. Connections start on database `"0"`.
+//!
+//! Supported commands: `PING`, `ECHO`, `SELECT`, `GET`, `SET` (with `NX`/`XX`), `SETNX`,
+//! `DEL`, `EXISTS`, `MGET`, `SCAN` (with `MATCH`/`COUNT`/`TYPE`), `MULTI`, `EXEC`, `DISCARD`,
+//! `WATCH`, `UNWATCH`, `QUIT`, `CLIENT` and `COMMAND`.
+//!
+//! Queued `MULTI` commands are executed without interleaving writes of other clients.
+//! `WATCH` remembers the current values of the watched keys, and `EXEC` aborts if any of them
+//! has a different value by then. Unlike Redis, a key that was overwritten with the same value
+//! doesn't count as modified.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self, BufReader, BufWriter, Read, Write},
+    net::TcpListener,
+    sync::{Arc, RwLock},
+};
+
+use okv_core::{
+    backend::*,
+    env::Env,
+    error::{Error, Result},
+    schema::SCHEMA_DATABASE,
+};
+
+pub mod resp;
+use resp::Value;
+
+/// The database a new connection starts on.
+pub const DEFAULT_DATABASE: &str = "0";
+
+/// The default number of elements examined by a single `SCAN` call.
+const DEFAULT_SCAN_COUNT: usize = 10;
+
+/// The number of `SCAN` cursors a connection remembers, older cursors of the same
+/// connection become invalid.
+const MAX_SCAN_CURSORS: usize = 4096;
+
+fn io_err(e: io::Error) -> Error {
+    Error::DatabaseBackend(Box::new(e))
+}
+
+/// Serves an [`Env`] to Redis clients.
+///
+/// Every connection is handled on its own thread.
+///
+/// # Examples
+///
+/// ```no_run
+/// use okv_core::env::Env;
+/// use okv_memory::MemDB;
+/// use okv_resp::RespServer;
+///
+/// let server = RespServer::new(Env::new(MemDB::new()));
+/// server.listen("127.0.0.1:6379").unwrap();
+/// ```
+pub struct RespServer<D: DatabaseBackend>(Arc<ServerInner<D>>);
+
+impl<D: DatabaseBackend> Clone for RespServer<D> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+struct ServerInner<D: DatabaseBackend> {
+    env: Env<D>,
+    columns: RwLock<HashMap<String, Arc<D::Column>>>,
+    // `SETNX`, `SET NX`, `SET XX` and `EXEC` check and write in one step
+    write_lock: RwLock<()>,
+}
+
+/// The last key returned for every `SCAN` cursor of a connection. Redis clients expect
+/// cursors to be integers, so the server hands out ids and a scan continues after the key
+/// of its id. Cursors belong to the connection that started the scan, so busy clients can't
+/// evict the scans of others.
+#[derive(Default)]
+struct ScanCursors {
+    last_id: u64,
+    keys: BTreeMap<u64, Vec<u8>>,
+}
+
+impl ScanCursors {
+    /// Remember `key` and return the cursor to continue after it.
+    fn insert(&mut self, key: Vec<u8>) -> u64 {
+        self.last_id += 1;
+        self.keys.insert(self.last_id, key);
+        if self.keys.len() > MAX_SCAN_CURSORS {
+            self.keys.pop_first();
+        }
+        self.last_id
+    }
+}
+
+/// The state of a single client connection.
+struct Session {
+    db: String,
+    // commands queued since `MULTI`
+    queued: Option<Vec<Vec<Vec<u8>>>>,
+    // database, key and value of every key watched since the last `EXEC`
+    watched: Vec<(String, Vec<u8>, Option<Vec<u8>>)>,
+    scan_cursors: ScanCursors,
+}
+
+impl<D> RespServer<D>
+where
+    D: DatabaseBackend + 'static,
+    D::Column: DBColumn + DBColumnPage + Send + Sync + 'static,
+{
+    /// Create a new server for the given environment.
+    pub fn new(env: Env<D>) -> Self {
+        Self(Arc::new(ServerInner {
+            env,
+            columns: RwLock::new(HashMap::new()),
+            write_lock: RwLock::new(()),
+        }))
+    }
+
+    /// Listen on the given address and serve clients until an error occurs.
+    ///
+    /// The address is either a TCP address like `127.0.0.1:6379` or a Unix socket like `unix:///tmp/okv.sock`.
+    pub fn listen(&self, addr: &str) -> Result<()> {
+        #[cfg(unix)]
+        if let Some(path) = addr.strip_prefix("unix://") {
+            let listener = std::os::unix::net::UnixListener::bind(path).map_err(io_err)?;
+            return self.serve_unix(listener);
+        }
+
+        let addr = addr.strip_prefix("tcp://").unwrap_or(addr);
+        self.serve_tcp(TcpListener::bind(addr).map_err(io_err)?)
+    }
+
+    /// Serve clients connecting to the given TCP listener.
+    pub fn serve_tcp(&self, listener: TcpListener) -> Result<()> {
+        for stream in listener.incoming() {
+            let stream = stream.map_err(io_err)?;
+            stream.set_nodelay(true).map_err(io_err)?;
+            let writer = stream.try_clone().map_err(io_err)?;
+            self.spawn(stream, writer);
+        }
+        Ok(())
+    }
+
+    /// Serve clients connecting to the given Unix socket listener.
+    #[cfg(unix)]
+    pub fn serve_unix(&self, listener: std::os::unix::net::UnixListener) -> Result<()> {
+        for stream in listener.incoming() {
+            let stream = stream.map_err(io_err)?;
+            let writer = stream.try_clone().map_err(io_err)?;
+            self.spawn(stream, writer);
+        }
+        Ok(())
+    }
+
+    fn spawn(&self, reader: impl Read + Send + 'static, writer: impl Write + Send + 'static) {
+        let server = self.clone();
+        std::thread::spawn(move || {
+            // a failing connection only affects its own client
+            let _ = server.handle_connection(reader, writer);
+        });
+    }
+
+    /// Handle a single client connection until it is closed.
+    pub fn handle_connection(&self, reader: impl Read, writer: impl Write) -> io::Result<()> {
+        let mut reader = BufReader::new(reader);
+        let mut writer = BufWriter::new(writer);
+        let mut session = Session {
+            db: DEFAULT_DATABASE.to_string(),
+            queued: None,
+            watched: Vec::new(),
+            scan_cursors: ScanCursors::default(),
+        };
+
+        while let Some(args) = resp::read_command(&mut reader)? {
+            if args.is_empty() {
+                continue;
+            }
+
+            let quit = args[0].eq_ignore_ascii_case(b"QUIT");
+            let reply = self.dispatch(&mut session, args).unwrap_or_else(Value::err);
+            reply.write(&mut writer)?;
+
+            // only flush once all pipelined commands have been answered
+            if quit || reader.buffer().is_empty() {
+                writer.flush()?;
+            }
+            if quit {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn column(&self, db: &str) -> Result<Arc<D::Column>> {
+        let columns = self.0.columns.read().unwrap_or_else(|e| e.into_inner());
+        if let Some(col) = columns.get(db) {
+            return Ok(col.clone());
+        }
+        drop(columns);
+
+        let mut columns = self.0.columns.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(col) = columns.get(db) {
+            return Ok(col.clone());
+        }
+
+        let col = Arc::new(D::create_or_open(self.0.env.clone(), db)?);
+        columns.insert(db.to_string(), col.clone());
+        Ok(col)
+    }
+
+    /// Handle the transaction commands and queue everything else while a `MULTI` is open.
+    fn dispatch(&self, session: &mut Session, args: Vec<Vec<u8>>) -> Result<Value> {
+        let name = args[0].to_ascii_uppercase();
+        let in_multi = session.queued.is_some();
+
+        let reply = match name.as_slice() {
+            b"MULTI" if in_multi => Value::err("MULTI calls can not be nested"),
+            b"MULTI" => {
+                session.queued = Some(Vec::new());
+                Value::ok()
+            }
+            b"EXEC" => self.exec(session)?,
+            b"DISCARD" => match session.queued.take() {
+                Some(_) => {
+                    session.watched.clear();
+                    Value::ok()
+                }
+                None => Value::err("DISCARD without MULTI"),
+            },
+            b"WATCH" if in_multi => Value::err("WATCH inside MULTI is not allowed"),
+            b"WATCH" if args.len() < 2 => {
+                Value::err("wrong number of arguments for 'watch' command")
+            }
+            b"WATCH" => {
+                let col = self.column(&session.db)?;
+                for key in &args[1..] {
+                    let val = col.get(key)?;
+                    session.watched.push((session.db.clone(), key.clone(), val));
+                }
+                Value::ok()
+            }
+            b"UNWATCH" if !in_multi => {
+                session.watched.clear();
+                Value::ok()
+            }
+            _ => match &mut session.queued {
+                Some(queued) => {
+                    queued.push(args);
+                    Value::Simple("QUEUED".to_string())
+                }
+                None => self.execute(session, &args, false)?,
+            },
+        };
+
+        Ok(reply)
+    }
+
+    /// Execute the queued commands if none of the watched keys have been modified.
+    fn exec(&self, session: &mut Session) -> Result<Value> {
+        let Some(queued) = session.queued.take() else {
+            return Ok(Value::err("EXEC without MULTI"));
+        };
+        let watched = std::mem::take(&mut session.watched);
+
+        let _guard = self.0.write_lock.write().unwrap_or_else(|e| e.into_inner());
+        for (db, key, val) in watched {
+            if self.column(&db)?.get(&key)? != val {
+                return Ok(Value::Array(None));
+            }
+        }
+
+        let replies = queued
+            .iter()
+            .map(|args| self.execute(session, args, true).unwrap_or_else(Value::err))
+            .collect();
+        Ok(Value::Array(Some(replies)))
+    }
+
+    /// Execute a single command. `locked` is set if the caller already holds the write lock.
+    fn execute(&self, session: &mut Session, args: &[Vec<u8>], locked: bool) -> Result<Value> {
+        let name = String::from_utf8_lossy(&args[0]).to_ascii_lowercase();
+        let args = &args[1..];
+        let arity = |min: usize, max: Option<usize>| {
+            let ok = args.len() >= min && max.map_or(true, |max| args.len() <= max);
+            match ok {
+                true => Ok(()),
+                false => Err(Value::err(format!(
+                    "wrong number of arguments for '{name}' command"
+                ))),
+            }
+        };
+        let shared =
+            || (!locked).then(|| self.0.write_lock.read().unwrap_or_else(|e| e.into_inner()));
+        let exclusive =
+            || (!locked).then(|| self.0.write_lock.write().unwrap_or_else(|e| e.into_inner()));
+
+        macro_rules! arity {
+            ($min:expr, $max:expr) => {
+                if let Err(e) = arity($min, $max) {
+                    return Ok(e);
+                }
+            };
+        }
+
+        let reply = match name.as_str() {
+            "ping" => {
+                arity!(0, Some(1));
+                match args.first() {
+                    Some(msg) => Value::bulk(msg.clone()),
+                    None => Value::Simple("PONG".to_string()),
+                }
+            }
+            "echo" => {
+                arity!(1, Some(1));
+                Value::bulk(args[0].clone())
+            }
+            "quit" => Value::ok(),
+            // clients like to send these on connect, we accept and ignore them
+            "client" => Value::ok(),
+            "command" => Value::Array(Some(vec![])),
+            "select" => {
+                arity!(1, Some(1));
+                let name = String::from_utf8(args[0].clone())
+                    .map_err(|_| Error::Unknown("database name is not valid utf-8".into()))?;
+                if name == SCHEMA_DATABASE {
+                    return Ok(Value::err(format!("database name '{name}' is reserved")));
+                }
+                self.column(&name)?;
+                session.db = name;
+                Value::ok()
+            }
+            "get" => {
+                arity!(1, Some(1));
+                Value::Bulk(self.column(&session.db)?.get(&args[0])?)
+            }
+            "set" => {
+                arity!(2, None);
+                let (mut nx, mut xx) = (false, false);
+                for opt in &args[2..] {
+                    match opt.to_ascii_uppercase().as_slice() {
+                        b"NX" => nx = true,
+                        b"XX" => xx = true,
+                        b"EX" | b"PX" | b"EXAT" | b"PXAT" | b"KEEPTTL" => {
+                            return Ok(Value::err("expiration is not supported"))
+                        }
+                        _ => return Ok(Value::err("syntax error")),
+                    }
+                }
+                if nx && xx {
+                    return Ok(Value::err("syntax error"));
+                }
+
+                let col = self.column(&session.db)?;
+                if nx || xx {
+                    let _guard = exclusive();
+                    if col.contains(&args[0])? == nx {
+                        return Ok(Value::nil());
+                    }
+                    col.set(&args[0], &args[1])?;
+                } else {
+                    let _guard = shared();
+                    col.set(&args[0], &args[1])?;
+                }
+                Value::ok()
+            }
+            "setnx" => {
+                arity!(2, Some(2));
+                let col = self.column(&session.db)?;
+                let _guard = exclusive();
+                Value::Integer(col.set_nx(&args[0], &args[1])? as i64)
+            }
+            "del" => {
+                arity!(1, None);
+                let col = self.column(&session.db)?;
+                let _guard = exclusive();
+                let mut removed = 0;
+                for key in args {
+                    if col.contains(key)? {
+                        col.delete(key)?;
+                        removed += 1;
+                    }
+                }
+                Value::Integer(removed)
+            }
+            "exists" => {
+                arity!(1, None);
+                let col = self.column(&session.db)?;
+                let mut count = 0;
+                for key in args {
+                    count += col.contains(key)? as i64;
+                }
+                Value::Integer(count)
+            }
+            "mget" => {
+                arity!(1, None);
+                let vals = self.column(&session.db)?.get_multi(args)?;
+                Value::Array(Some(vals.into_iter().map(Value::Bulk).collect()))
+            }
+            "scan" => {
+                arity!(1, None);
+                self.scan(&session.db, &mut session.scan_cursors, args)?
+            }
+            _ => Value::err(format!("unknown command '{name}'")),
+        };
+
+        Ok(reply)
+    }
+
+    /// `SCAN cursor [MATCH pattern] [COUNT count]`
+    ///
+    /// Keys are returned in key order, and a cursor stands for the last key returned, so
+    /// keys that exist during the whole scan are returned exactly once, even while other
+    /// clients write. Like Redis, `COUNT` limits the number of examined keys, so a call can
+    /// return fewer keys than requested.
+    fn scan(&self, db: &str, cursors: &mut ScanCursors, args: &[Vec<u8>]) -> Result<Value> {
+        let after = match std::str::from_utf8(&args[0])
+            .ok()
+            .and_then(|c| c.parse().ok())
+        {
+            Some(0) => None,
+            Some(cursor) => match cursors.keys.get(&cursor) {
+                Some(key) => Some(key.clone()),
+                None => return Ok(Value::err("invalid cursor")),
+            },
+            None => return Ok(Value::err("invalid cursor")),
+        };
+
+        let mut pattern = None;
+        let mut count = DEFAULT_SCAN_COUNT;
+        let mut strings = true;
+        for opt in args[1..].chunks(2) {
+            let [name, val] = opt else {
+                return Ok(Value::err("syntax error"));
+            };
+            match name.to_ascii_uppercase().as_slice() {
+                b"MATCH" => pattern = Some(val.as_slice()),
+                b"COUNT" => match std::str::from_utf8(val).ok().and_then(|c| c.parse().ok()) {
+                    Some(c) if c > 0 => count = c,
+                    _ => return Ok(Value::err("value is not an integer or out of range")),
+                },
+                // every key is a string
+                b"TYPE" => strings = val.eq_ignore_ascii_case(b"string"),
+                _ => return Ok(Value::err("syntax error")),
+            }
+        }
+
+        if !strings {
+            return Ok(Value::Array(Some(vec![
+                Value::bulk("0"),
+                Value::Array(Some(vec![])),
+            ])));
+        }
+
+        let prefix = pattern.map(resp::glob_prefix).unwrap_or_default();
+        let page = self.column(db)?.page(after.as_deref(), count, prefix)?;
+
+        // a short page is the last one
+        let next = match (page.len() < count, page.last()) {
+            (false, Some((last, _))) => cursors.insert(last.clone()),
+            _ => 0,
+        };
+        let keys = page
+            .into_iter()
+            .filter(|(key, _)| pattern.map_or(true, |p| resp::glob_match(p, key)))
+            .map(|(key, _)| Value::bulk(key))
+            .collect();
+        Ok(Value::Array(Some(vec![
+            Value::bulk(next.to_string()),
+            Value::Array(Some(keys)),
+        ])))
+    }
+}
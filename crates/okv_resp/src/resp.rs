@@ -0,0 +1,184 @@
+//! Minimal implementation of the RESP2 wire format.
+//!
+//! See <https://redis.io/docs/latest/develop/reference/protocol-spec/>.
+
+use std::io::{self, BufRead, Write};
+
+/// Bulk strings larger than this are rejected, same as the default `proto-max-bulk-len` of Redis.
+const MAX_BULK_LEN: usize = 512 * 1024 * 1024;
+
+/// A RESP2 value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    /// `+OK`
+    Simple(String),
+    /// `-ERR message`
+    Error(String),
+    /// `:1`
+    Integer(i64),
+    /// `$3\r\nfoo` or `$-1` for nil
+    Bulk(Option<Vec<u8>>),
+    /// `*2\r\n...` or `*-1` for nil
+    Array(Option<Vec<Value>>),
+}
+
+impl Value {
+    /// `+OK`
+    pub fn ok() -> Self {
+        Self::Simple("OK".to_string())
+    }
+
+    /// A nil bulk string.
+    pub fn nil() -> Self {
+        Self::Bulk(None)
+    }
+
+    /// A bulk string.
+    pub fn bulk(bytes: impl Into<Vec<u8>>) -> Self {
+        Self::Bulk(Some(bytes.into()))
+    }
+
+    /// An error reply with the generic `ERR` prefix.
+    pub fn err(msg: impl std::fmt::Display) -> Self {
+        Self::Error(format!("ERR {msg}"))
+    }
+
+    /// Write the value in RESP2 format.
+    pub fn write(&self, w: &mut impl Write) -> io::Result<()> {
+        match self {
+            Value::Simple(s) => write!(w, "+{s}\r\n"),
+            Value::Error(e) => write!(w, "-{e}\r\n"),
+            Value::Integer(i) => write!(w, ":{i}\r\n"),
+            Value::Bulk(None) => w.write_all(b"$-1\r\n"),
+            Value::Bulk(Some(bytes)) => {
+                write!(w, "${}\r\n", bytes.len())?;
+                w.write_all(bytes)?;
+                w.write_all(b"\r\n")
+            }
+            Value::Array(None) => w.write_all(b"*-1\r\n"),
+            Value::Array(Some(items)) => {
+                write!(w, "*{}\r\n", items.len())?;
+                items.iter().try_for_each(|item| item.write(w))
+            }
+        }
+    }
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Read a line without the trailing `\r\n`. Returns `None` on EOF.
+fn read_line(r: &mut impl BufRead) -> io::Result<Option<Vec<u8>>> {
+    let mut line = Vec::new();
+    if r.read_until(b'\n', &mut line)? == 0 {
+        return Ok(None);
+    }
+
+    match line.ends_with(b"\r\n") {
+        true => line.truncate(line.len() - 2),
+        false => return Err(invalid("line is not terminated by CRLF")),
+    }
+    Ok(Some(line))
+}
+
+fn parse_len(bytes: &[u8]) -> io::Result<i64> {
+    std::str::from_utf8(bytes)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| invalid("invalid length"))
+}
+
+/// Read a command sent by a client.
+///
+/// Clients send commands as arrays of bulk strings, but simple inline commands
+/// (like `PING` typed into telnet) are supported as well.
+/// Returns `None` when the client closed the connection.
+pub fn read_command(r: &mut impl BufRead) -> io::Result<Option<Vec<Vec<u8>>>> {
+    let Some(line) = read_line(r)? else {
+        return Ok(None);
+    };
+
+    let Some(len) = line.strip_prefix(b"*") else {
+        let args = line
+            .split(|b| b.is_ascii_whitespace())
+            .filter(|arg| !arg.is_empty())
+            .map(<[u8]>::to_vec)
+            .collect();
+        return Ok(Some(args));
+    };
+
+    let len = parse_len(len)?;
+    let mut args = Vec::with_capacity(len.clamp(0, 1024) as usize);
+    for _ in 0..len {
+        let line = read_line(r)?.ok_or_else(|| invalid("unexpected end of command"))?;
+        let len = line
+            .strip_prefix(b"$")
+            .ok_or_else(|| invalid("expected bulk string"))?;
+        let len = usize::try_from(parse_len(len)?).map_err(|_| invalid("invalid bulk length"))?;
+        if len > MAX_BULK_LEN {
+            return Err(invalid("bulk string exceeds maximum length"));
+        }
+
+        let mut arg = vec![0; len + 2];
+        r.read_exact(&mut arg)?;
+        if !arg.ends_with(b"\r\n") {
+            return Err(invalid("bulk string is not terminated by CRLF"));
+        }
+        arg.truncate(len);
+        args.push(arg);
+    }
+
+    Ok(Some(args))
+}
+
+/// Match a key against a Redis glob-style pattern (`*`, `?`, `[abc]`, `[^a-z]` and `\` escapes).
+pub fn glob_match(pattern: &[u8], key: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => key.is_empty(),
+        Some((b'*', rest)) => (0..=key.len()).any(|i| glob_match(rest, &key[i..])),
+        Some((b'?', rest)) => !key.is_empty() && glob_match(rest, &key[1..]),
+        Some((b'[', rest)) => {
+            let Some((&c, key_rest)) = key.split_first() else {
+                return false;
+            };
+            let (negate, rest) = match rest.split_first() {
+                Some((b'^', rest)) => (true, rest),
+                _ => (false, rest),
+            };
+
+            let mut i = 0;
+            let mut matched = false;
+            while i < rest.len() && rest[i] != b']' {
+                if rest[i] == b'\\' && i + 1 < rest.len() {
+                    matched |= rest[i + 1] == c;
+                    i += 2;
+                } else if i + 2 < rest.len() && rest[i + 1] == b'-' && rest[i + 2] != b']' {
+                    let (lo, hi) = (rest[i].min(rest[i + 2]), rest[i].max(rest[i + 2]));
+                    matched |= (lo..=hi).contains(&c);
+                    i += 3;
+                } else {
+                    matched |= rest[i] == c;
+                    i += 1;
+                }
+            }
+
+            // an unterminated class matches literally up to the end of the pattern
+            let rest = rest.get(i + 1..).unwrap_or_default();
+            matched != negate && glob_match(rest, key_rest)
+        }
+        Some((b'\\', rest)) if !rest.is_empty() => {
+            key.first() == Some(&rest[0]) && glob_match(&rest[1..], &key[1..])
+        }
+        Some((&p, rest)) => key.first() == Some(&p) && glob_match(rest, &key[1..]),
+    }
+}
+
+/// Returns the literal prefix of a pattern, i.e. everything before the first special character.
+pub fn glob_prefix(pattern: &[u8]) -> &[u8] {
+    let end = pattern
+        .iter()
+        .position(|b| matches!(b, b'*' | b'?' | b'[' | b'\\'))
+        .unwrap_or(pattern.len());
+    &pattern[..end]
+}
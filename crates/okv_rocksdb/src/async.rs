@@ -1,5 +1,6 @@
 use crate::{
     tx::RocksDBTransaction, RocksDbColumn, RocksDbOptimisticColumn, RocksDbPessimisticColumn,
+    RocksDbReadOnlyColumn, RocksDbSecondaryColumn,
 };
 use okv_core::backend::DBColumn;
 use okv_core::{async_fallback, async_fallback_impl, backend_async::DBColumnAsync};
@@ -7,6 +8,8 @@ use okv_core::{async_fallback, async_fallback_impl, backend_async::DBColumnAsync
 async_fallback!(RocksDbOptimisticColumn);
 async_fallback!(RocksDbPessimisticColumn);
 async_fallback!(RocksDbColumn);
+async_fallback!(RocksDbReadOnlyColumn);
+async_fallback!(RocksDbSecondaryColumn);
 
 impl<'a, DB> DBColumnAsync for RocksDBTransaction<'a, DB>
 where
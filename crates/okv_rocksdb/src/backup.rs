@@ -0,0 +1,71 @@
+//! Restore and manage incremental backups.
+//!
+//! Backups are created with `create_backup` of [`RocksDb`](crate::RocksDb) and
+//! [`RocksDbOptimistic`](crate::RocksDbOptimistic). A backup directory holds any number of
+//! backups, files that are shared between backups are only stored once.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use okv_core::env::Env;
+//! use okv_rocksdb::{backup, RocksDb};
+//!
+//! let env = Env::new(RocksDb::new("database/example-backup").unwrap());
+//! let id = env.db().create_backup("database/backups").unwrap();
+//! backup::purge("database/backups", 5).unwrap();
+//! drop(env);
+//!
+//! // restore the backup into a new directory, which is opened like any other database
+//! backup::restore("database/backups", "database/example-restored", Some(id)).unwrap();
+//! let env = Env::new(RocksDb::new("database/example-restored").unwrap());
+//! ```
+
+use std::path::Path;
+
+use okv_core::error::Result;
+pub use rocksdb::backup::BackupEngineInfo;
+use rocksdb::backup::{BackupEngine, BackupEngineOptions, RestoreOptions};
+
+use crate::okv_err;
+
+pub(crate) fn open_engine(backup_dir: impl AsRef<Path>) -> Result<BackupEngine> {
+    let opts = BackupEngineOptions::new(backup_dir).map_err(okv_err)?;
+    let env = rocksdb::Env::new().map_err(okv_err)?;
+    BackupEngine::open(&opts, &env).map_err(okv_err)
+}
+
+/// Restore a backup into the directory `db_dir`, the latest backup if `backup_id` is
+/// `None`. The database in `db_dir` must not be open, its files are replaced.
+pub fn restore(
+    backup_dir: impl AsRef<Path>,
+    db_dir: impl AsRef<Path>,
+    backup_id: Option<u32>,
+) -> Result<()> {
+    let mut engine = open_engine(backup_dir)?;
+    let db_dir = db_dir.as_ref();
+    let opts = RestoreOptions::default();
+    match backup_id {
+        Some(id) => engine.restore_from_backup(db_dir, db_dir, &opts, id),
+        None => engine.restore_from_latest_backup(db_dir, db_dir, &opts),
+    }
+    .map_err(okv_err)
+}
+
+/// Returns the backups in a backup directory, oldest first.
+pub fn list(backup_dir: impl AsRef<Path>) -> Result<Vec<BackupEngineInfo>> {
+    Ok(open_engine(backup_dir)?.get_backup_info())
+}
+
+/// Check that the files of a backup exist and have the expected sizes.
+pub fn verify(backup_dir: impl AsRef<Path>, backup_id: u32) -> Result<()> {
+    open_engine(backup_dir)?
+        .verify_backup(backup_id)
+        .map_err(okv_err)
+}
+
+/// Delete all but the latest `keep` backups.
+pub fn purge(backup_dir: impl AsRef<Path>, keep: usize) -> Result<()> {
+    open_engine(backup_dir)?
+        .purge_old_backups(keep)
+        .map_err(okv_err)
+}
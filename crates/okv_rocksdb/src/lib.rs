@@ -1,21 +1,53 @@
 use okv_core::{backend::*, env::Env, error::*, traits::*};
 use rocksdb::{BoundColumnFamily, DBPinnableSlice, OptimisticTransactionDB, TransactionDB, DB};
-use std::sync::Arc;
+use std::{ops::Bound, sync::Arc};
 
 mod r#async;
+pub mod backup;
 mod normal;
 mod optimistic;
+mod options;
 mod pessimistic;
+mod read_only;
+mod secondary;
 
 mod tx;
 pub use normal::*;
 pub use optimistic::*;
+pub use options::ColumnOptions;
 pub use pessimistic::*;
+pub use read_only::*;
+pub use secondary::*;
 
 pub(crate) fn okv_err(e: rocksdb::Error) -> Error {
     Error::DatabaseBackend(Box::new(e))
 }
 
+/// Read the keys of a raw iterator until the first key that doesn't start with `prefix`.
+/// Only the keys are copied, unlike the iterators that return `(key, value)` boxes.
+pub(crate) fn raw_keys<D: rocksdb::DBAccess>(
+    mut iter: rocksdb::DBRawIteratorWithThreadMode<'_, D>,
+    prefix: Vec<u8>,
+) -> impl Iterator<Item = Result<Vec<u8>>> + '_ {
+    let mut done = false;
+    std::iter::from_fn(move || {
+        if done {
+            return None;
+        }
+        match iter.key() {
+            Some(key) if key.starts_with(&prefix) => {
+                let key = key.to_vec();
+                iter.next();
+                Some(Ok(key))
+            }
+            _ => {
+                done = true;
+                iter.status().err().map(|e| Err(okv_err(e)))
+            }
+        }
+    })
+}
+
 /// A bound column family handle for RocksDB.
 pub struct BoundCFHandle<'a>(Arc<rocksdb::BoundColumnFamily<'a>>);
 
@@ -63,6 +95,9 @@ trait RocksDbImpl: Sized {
     /// Create a new RocksDb instance with a custom configuration.
     /// Note that rocksdb requires that all databases (column families) are opened at startup.
     /// To get all column families, use `list_databases`.
+    ///
+    /// Column families are opened with the options they were created with, see
+    /// [`ColumnOptions`]. `config` is used for column families without stored options.
     fn new_with_config(
         config: Self::RocksdbOptions,
         connect_str: &str,
@@ -70,6 +105,15 @@ trait RocksDbImpl: Sized {
     ) -> Result<Self, rocksdb::Error>;
 }
 
+/// Read options for scans that cross prefixes. Column families created with
+/// [`ColumnOptions::prefix_scan`] have a prefix extractor, and their iterators may skip keys
+/// with another prefix than the key they seeked to, unless they seek in total order.
+fn total_order() -> rocksdb::ReadOptions {
+    let mut opts = rocksdb::ReadOptions::default();
+    opts.set_total_order_seek(true);
+    opts
+}
+
 macro_rules! implement_column_traits {
     ($name:ident) => {
         impl DBColumnDelete for $name {
@@ -99,14 +143,102 @@ macro_rules! implement_column_traits {
             fn iter(&self) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>> {
                 let iter = self
                     .db()
-                    .iterator_cf(self.cf_handle(), rocksdb::IteratorMode::Start)
+                    .iterator_cf_opt(
+                        self.cf_handle(),
+                        total_order(),
+                        rocksdb::IteratorMode::Start,
+                    )
+                    .map(|v| match v {
+                        Ok((k, v)) => Ok((k.to_vec(), v.to_vec())),
+                        Err(e) => Err(okv_err(e)),
+                    });
+
+                Ok(iter)
+            }
+
+            fn keys(&self) -> Result<impl Iterator<Item = Result<Vec<u8>>>> {
+                let mut iter = self
+                    .db()
+                    .raw_iterator_cf_opt(self.cf_handle(), total_order());
+                iter.seek_to_first();
+                Ok(raw_keys(iter, Vec::new()))
+            }
+        }
+
+        impl DBColumnIteratorRef for $name {
+            fn for_each_ref(
+                &self,
+                prefix: &[u8],
+                mut f: impl FnMut(&[u8], &[u8]) -> Result<bool>,
+            ) -> Result<()> {
+                let mut iter = self.db().raw_iterator_cf(self.cf_handle());
+                iter.seek(prefix);
+                while let (Some(k), Some(v)) = (iter.key(), iter.value()) {
+                    if !k.starts_with(prefix) || !f(k, v)? {
+                        return Ok(());
+                    }
+                    iter.next();
+                }
+                iter.status().map_err(okv_err)
+            }
+        }
+
+        impl DBColumnIteratorRange for $name {
+            fn iter_range(
+                &self,
+                range: KeyRange,
+            ) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>> {
+                let mode = rocksdb::IteratorMode::From(&range.start, rocksdb::Direction::Forward);
+                let iter = self
+                    .db()
+                    .iterator_cf_opt(self.cf_handle(), total_order(), mode)
                     .map(|v| match v {
                         Ok((k, v)) => Ok((k.to_vec(), v.to_vec())),
                         Err(e) => Err(okv_err(e)),
+                    })
+                    .take_while(move |item| match (item, &range.end) {
+                        (Ok((k, _)), Some(end)) => k < end,
+                        _ => true,
                     });
 
                 Ok(iter)
             }
+
+            split_ranges_impl!($name);
+        }
+
+        impl DBColumnPage for $name {
+            fn page(
+                &self,
+                after: Option<&[u8]>,
+                limit: usize,
+                prefix: &[u8],
+            ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+                let mut iter = self
+                    .db()
+                    .raw_iterator_cf_opt(self.cf_handle(), total_order());
+                match page_start(after, prefix) {
+                    Bound::Excluded(after) => {
+                        iter.seek(after);
+                        if iter.key() == Some(after) {
+                            iter.next();
+                        }
+                    }
+                    Bound::Included(start) => iter.seek(start),
+                    Bound::Unbounded => iter.seek_to_first(),
+                }
+
+                let mut entries = Vec::new();
+                while let (Some(k), Some(v)) = (iter.key(), iter.value()) {
+                    if entries.len() == limit || !k.starts_with(prefix) {
+                        break;
+                    }
+                    entries.push((k.to_vec(), v.to_vec()));
+                    iter.next();
+                }
+                iter.status().map_err(okv_err)?;
+                Ok(entries)
+            }
         }
 
         impl DBColumnIteratorPrefix for $name {
@@ -127,6 +259,15 @@ macro_rules! implement_column_traits {
 
                 Ok(iter)
             }
+
+            fn keys_prefix(
+                &self,
+                prefix: impl AsRef<[u8]>,
+            ) -> Result<impl Iterator<Item = Result<Vec<u8>>>> {
+                let mut iter = self.db().raw_iterator_cf(self.cf_handle());
+                iter.seek(prefix.as_ref());
+                Ok(raw_keys(iter, prefix.as_ref().to_vec()))
+            }
         }
 
         impl DBColumn for $name {
@@ -187,15 +328,24 @@ macro_rules! implement_column {
             }
 
             pub fn try_new(env: Env<$name>, name: String) -> Result<Self> {
+                Self::try_new_with(env, name, &rocksdb::Options::default())
+            }
+
+            /// Open the column family, or create it with `cf_opts` if it doesn't exist.
+            pub fn try_new_with(
+                env: Env<$name>,
+                name: String,
+                cf_opts: &rocksdb::Options,
+            ) -> Result<Self> {
                 let inner = $col_inner::try_new(env, |env| {
                     let handle = if let Some(handle) = env.db().db.cf_handle(&name) {
                         handle
                     } else {
-                        let cf_opts = rocksdb::Options::default();
                         env.db()
                             .db
-                            .create_cf(name.clone(), &cf_opts)
+                            .create_cf(name.clone(), cf_opts)
                             .map_err(okv_err)?;
+                        env.db().cf_options.insert(&name, cf_opts);
                         env.db()
                             .db
                             .cf_handle(&name)
@@ -220,6 +370,86 @@ macro_rules! implement_backend {
             fn create_or_open(env: Env<$name>, name: &str) -> Result<Self::Column> {
                 $col::try_new(env, name.to_owned())
             }
+
+            /// The `default` column family always exists and is not listed.
+            fn list_databases(&self) -> Result<Vec<String>> {
+                let cfs =
+                    DB::list_cf(&rocksdb::Options::default(), self.db.path()).map_err(okv_err)?;
+                Ok(cfs
+                    .into_iter()
+                    .filter(|cf| cf != rocksdb::DEFAULT_COLUMN_FAMILY_NAME)
+                    .collect())
+            }
+
+            fn exists(&self, db: &str) -> Result<bool> {
+                Ok(self.db.cf_handle(db).is_some())
+            }
+
+            fn drop_database(&self, db: &str) -> Result<()> {
+                self.db.drop_cf(db).map_err(okv_err)?;
+                self.cf_options.remove(db);
+                Ok(())
+            }
+
+            compact_impl!($name);
+
+            /// Reads all entries with checksum verification, RocksDB can't repair its files.
+            fn check_integrity(&self) -> Result<bool> {
+                let names = self.list_databases()?;
+                let names = names.iter().map(String::as_str);
+                for name in names.chain([rocksdb::DEFAULT_COLUMN_FAMILY_NAME]) {
+                    let Some(cf) = self.db.cf_handle(name) else {
+                        continue;
+                    };
+                    let mut opts = total_order();
+                    opts.set_verify_checksums(true);
+                    opts.fill_cache(false);
+
+                    let mut iter = self.db.raw_iterator_cf_opt(&cf, opts);
+                    iter.seek_to_first();
+                    while iter.valid() {
+                        iter.next();
+                    }
+                    iter.status().map_err(okv_err)?;
+                }
+                Ok(true)
+            }
+
+            /// Column families can't be renamed, the entries are copied to a new column
+            /// family instead. This is not atomic.
+            ///
+            /// The new column family is created with the options of the old one if it was
+            /// created since the backend was opened, otherwise with the options the backend
+            /// was opened with, RocksDB doesn't expose the options of an open column family.
+            fn rename_database(&self, old: &str, new: &str) -> Result<()> {
+                let not_found = |db: &str| Error::DatabaseNotFound { db: db.to_owned() };
+                let src = self.db.cf_handle(old).ok_or_else(|| not_found(old))?;
+                let opts = self.cf_options.get(old);
+                self.db.create_cf(new, &opts).map_err(okv_err)?;
+                self.cf_options.insert(new, &opts);
+                let dst = self.db.cf_handle(new).ok_or_else(|| not_found(new))?;
+
+                let mode = rocksdb::IteratorMode::Start;
+                for item in self.db.iterator_cf_opt(&src, total_order(), mode) {
+                    let (key, val) = item.map_err(okv_err)?;
+                    self.db.put_cf(&dst, key, val).map_err(okv_err)?;
+                }
+
+                drop((src, dst));
+                self.drop_database(old)
+            }
+        }
+
+        impl DatabaseBackendOptions for $name {
+            type ColumnOptions = ColumnOptions;
+
+            fn create_or_open_with(
+                env: Env<$name>,
+                name: &str,
+                options: ColumnOptions,
+            ) -> Result<Self::Column> {
+                $col::try_new_with(env, name.to_owned(), options.options())
+            }
         }
 
         impl<'a> Innerable for $name {
@@ -231,11 +461,251 @@ macro_rules! implement_backend {
     };
 }
 
+/// Split the keys of a column family at the first keys of its files, so that every range
+/// contains files of roughly the same total size. Keys that are only in the memtable are not
+/// counted, `None` if the column family has no files yet.
+pub(crate) fn split_at_files(
+    files: Vec<rocksdb::LiveFile>,
+    cf: &str,
+    n: usize,
+) -> Option<Vec<KeyRange>> {
+    let mut files: Vec<_> = files
+        .into_iter()
+        .filter(|file| file.column_family_name == cf)
+        .filter_map(|file| Some((file.start_key?, file.size as u64)))
+        .collect();
+    if files.is_empty() {
+        return None;
+    }
+    files.sort_unstable();
+
+    let total: u64 = files.iter().map(|(_, size)| size).sum();
+    let step = total / n as u64;
+    let mut bounds = Vec::new();
+    let mut size_before = 0;
+    for (start, size) in files {
+        if bounds.len() + 1 < n && size_before >= step * (bounds.len() as u64 + 1) {
+            bounds.push(start);
+        }
+        size_before += size;
+    }
+    Some(split_at_keys(bounds))
+}
+
+/// RocksDB and optimistic transaction databases list their files, so their ranges are split
+/// without reading any keys. Pessimistic transaction databases don't and use the default.
+macro_rules! split_ranges_impl {
+    (RocksDbPessimisticColumn) => {};
+    ($name:ident) => {
+        fn split_ranges(&self, n: usize) -> Result<Vec<KeyRange>> {
+            if n <= 1 {
+                return Ok(vec![KeyRange::default()]);
+            }
+            let files = self.db().live_files().map_err(okv_err)?;
+            match split_at_files(files, &self.name, n) {
+                Some(ranges) => Ok(ranges),
+                // nothing was flushed yet, so the column is small enough to read its keys
+                None => {
+                    let keys = self.keys()?.collect::<Result<Vec<_>>>()?;
+                    let step = keys.len().div_ceil(n).max(1);
+                    Ok(split_at_keys(keys.into_iter().skip(step).step_by(step)))
+                }
+            }
+        }
+    };
+}
+
+/// Pessimistic transaction databases don't expose manual compaction, and read-only and
+/// secondary instances can't write compacted files.
+macro_rules! compact_impl {
+    (RocksDbPessimistic) => {};
+    (RocksDbReadOnly) => {};
+    (RocksDbSecondary) => {};
+    ($name:ident) => {
+        fn compact(&self) -> Result<()> {
+            let names = self.list_databases()?;
+            let names = names.iter().map(String::as_str);
+            for name in names.chain([rocksdb::DEFAULT_COLUMN_FAMILY_NAME]) {
+                if let Some(cf) = self.db.cf_handle(name) {
+                    self.db.compact_range_cf(&cf, None::<&[u8]>, None::<&[u8]>);
+                }
+            }
+            Ok(())
+        }
+    };
+}
+
+/// Column families that support compaction, see `compact_impl`.
+macro_rules! compact_column_impl {
+    ($name:ident) => {
+        impl DBColumnCompact for $name {
+            fn compact_range(&self, range: KeyRange) -> Result<()> {
+                let start = Some(range.start).filter(|start| !start.is_empty());
+                self.db()
+                    .compact_range_cf(self.cf_handle(), start, range.end);
+                Ok(())
+            }
+        }
+    };
+}
+
+/// Properties reported by [`DBColumnStats`], the first three also fill the common fields.
+const STATS_PROPERTIES: [&rocksdb::properties::PropName; 6] = [
+    rocksdb::properties::ESTIMATE_NUM_KEYS,
+    rocksdb::properties::TOTAL_SST_FILES_SIZE,
+    rocksdb::properties::CUR_SIZE_ALL_MEM_TABLES,
+    rocksdb::properties::LIVE_SST_FILES_SIZE,
+    rocksdb::properties::ESTIMATE_LIVE_DATA_SIZE,
+    rocksdb::properties::ESTIMATE_PENDING_COMPACTION_BYTES,
+];
+
+/// RocksDB and optimistic transaction databases read the stats from the column family
+/// properties. Pessimistic transaction databases don't expose properties, so their keys are
+/// counted and the sizes are unknown.
+macro_rules! stats_impl {
+    (RocksDbPessimisticColumn) => {
+        impl DBColumnStats for RocksDbPessimisticColumn {
+            fn stats(&self) -> Result<ColumnStats> {
+                Ok(ColumnStats::new(KeyCount::Exact(self.count()?)))
+            }
+        }
+    };
+    ($name:ident) => {
+        impl DBColumnStats for $name {
+            fn stats(&self) -> Result<ColumnStats> {
+                let mut values = Vec::with_capacity(STATS_PROPERTIES.len());
+                for name in STATS_PROPERTIES {
+                    let value = self
+                        .db()
+                        .property_int_value_cf(self.cf_handle(), name)
+                        .map_err(okv_err)?;
+                    values.push(value);
+                }
+
+                let mut stats = ColumnStats::new(KeyCount::Estimate(values[0].unwrap_or(0)));
+                stats.disk_bytes = values[1];
+                stats.memory_bytes = values[2];
+                stats.properties = STATS_PROPERTIES
+                    .iter()
+                    .zip(values)
+                    .filter_map(|(name, value)| Some((name.to_string(), value?.to_string())))
+                    .collect();
+                Ok(stats)
+            }
+        }
+    };
+}
+
+/// Checkpoints and backups read the files of a running database, which is supported by
+/// RocksDB and optimistic transaction databases.
+macro_rules! implement_snapshots {
+    ($name:ident) => {
+        impl $name {
+            /// Create a checkpoint, a consistent copy of all databases, in the directory
+            /// `path`, which must not exist. Files are hard linked if `path` is on the same
+            /// filesystem, so checkpoints are cheap. The checkpoint is opened like any other
+            /// RocksDB database, e.g. with [`RocksDbReadOnly`].
+            pub fn create_checkpoint(&self, path: impl AsRef<std::path::Path>) -> Result<()> {
+                let checkpoint = rocksdb::checkpoint::Checkpoint::new(&self.db).map_err(okv_err)?;
+                checkpoint.create_checkpoint(path).map_err(okv_err)
+            }
+
+            /// Create a backup of all databases in `backup_dir` and return its id. The
+            /// memtables are flushed first. Backups are incremental, only files that are not
+            /// in an earlier backup in the directory are copied. See [`backup`] to restore
+            /// and delete backups.
+            pub fn create_backup(&self, backup_dir: impl AsRef<std::path::Path>) -> Result<u32> {
+                let mut engine = backup::open_engine(backup_dir)?;
+                engine
+                    .create_new_backup_flush(&self.db, true)
+                    .map_err(okv_err)?;
+                let latest = engine
+                    .get_backup_info()
+                    .into_iter()
+                    .map(|info| info.backup_id);
+                Ok(latest.max().unwrap_or_default())
+            }
+        }
+    };
+}
+
+/// Transaction databases don't support range tombstones, so the keys in the range are
+/// deleted one by one in a single batch.
+macro_rules! implement_delete_range_batch {
+    ($name:ident) => {
+        impl DBColumnDeleteRange for $name {
+            fn delete_range(&self, start: &[u8], end: Option<&[u8]>) -> Result<Option<u64>> {
+                let mut batch = rocksdb::WriteBatchWithTransaction::<true>::default();
+                let mut iter = self
+                    .db()
+                    .raw_iterator_cf_opt(self.cf_handle(), total_order());
+                iter.seek(start);
+
+                let mut deleted = 0;
+                while let Some(key) = iter.key().filter(|key| end.map_or(true, |end| *key < end)) {
+                    batch.delete_cf(self.cf_handle(), key);
+                    deleted += 1;
+                    iter.next();
+                }
+                iter.status().map_err(okv_err)?;
+
+                self.db().write(batch).map_err(okv_err)?;
+                Ok(Some(deleted))
+            }
+        }
+    };
+}
+
+/// Writes key-value pairs in a single write batch, transaction databases take batches of
+/// transactions.
+macro_rules! implement_write_batch {
+    ($name:ident, $transaction:literal) => {
+        impl DBColumnBatch for $name {
+            fn set_multi<I, K, V>(&self, entries: I) -> Result<()>
+            where
+                I: IntoIterator<Item = (K, V)>,
+                K: AsRef<[u8]>,
+                V: AsRef<[u8]>,
+            {
+                let mut batch = rocksdb::WriteBatchWithTransaction::<$transaction>::default();
+                for (key, val) in entries {
+                    batch.put_cf(self.cf_handle(), key, val);
+                }
+                self.db().write(batch).map_err(okv_err)
+            }
+
+            fn write_multi<I, K, V>(&self, writes: I) -> Result<()>
+            where
+                I: IntoIterator<Item = (K, Option<V>)>,
+                K: AsRef<[u8]>,
+                V: AsRef<[u8]>,
+            {
+                let mut batch = rocksdb::WriteBatchWithTransaction::<$transaction>::default();
+                for (key, val) in writes {
+                    match val {
+                        Some(val) => batch.put_cf(self.cf_handle(), key, val),
+                        None => batch.delete_cf(self.cf_handle(), key),
+                    }
+                }
+                self.db().write(batch).map_err(okv_err)
+            }
+        }
+    };
+}
+
 implement_column_traits!(RocksDbColumn);
+implement_write_batch!(RocksDbColumn, false);
+stats_impl!(RocksDbColumn);
+compact_column_impl!(RocksDbColumn);
 implement_column!(RocksDb, RocksDbColumn, RocksDbColumnInner, DB);
 implement_backend!(RocksDb, RocksDbColumn, DB);
+implement_snapshots!(RocksDb);
 
 implement_column_traits!(RocksDbOptimisticColumn);
+implement_write_batch!(RocksDbOptimisticColumn, true);
+stats_impl!(RocksDbOptimisticColumn);
+compact_column_impl!(RocksDbOptimisticColumn);
+implement_delete_range_batch!(RocksDbOptimisticColumn);
 implement_backend!(
     RocksDbOptimistic,
     RocksDbOptimisticColumn,
@@ -247,8 +717,12 @@ implement_column!(
     RocksDbOptimisticColumnInner,
     OptimisticTransactionDB
 );
+implement_snapshots!(RocksDbOptimistic);
 
 implement_column_traits!(RocksDbPessimisticColumn);
+implement_write_batch!(RocksDbPessimisticColumn, true);
+stats_impl!(RocksDbPessimisticColumn);
+implement_delete_range_batch!(RocksDbPessimisticColumn);
 implement_backend!(RocksDbPessimistic, RocksDbPessimisticColumn, TransactionDB);
 implement_column!(
     RocksDbPessimistic,
@@ -256,3 +730,25 @@ implement_column!(
     RocksDbPessimisticColumnInner,
     TransactionDB
 );
+
+implement_column_traits!(RocksDbReadOnlyColumn);
+implement_write_batch!(RocksDbReadOnlyColumn, false);
+stats_impl!(RocksDbReadOnlyColumn);
+implement_backend!(RocksDbReadOnly, RocksDbReadOnlyColumn, DB);
+implement_column!(
+    RocksDbReadOnly,
+    RocksDbReadOnlyColumn,
+    RocksDbReadOnlyColumnInner,
+    DB
+);
+
+implement_column_traits!(RocksDbSecondaryColumn);
+implement_write_batch!(RocksDbSecondaryColumn, false);
+stats_impl!(RocksDbSecondaryColumn);
+implement_backend!(RocksDbSecondary, RocksDbSecondaryColumn, DB);
+implement_column!(
+    RocksDbSecondary,
+    RocksDbSecondaryColumn,
+    RocksDbSecondaryColumnInner,
+    DB
+);
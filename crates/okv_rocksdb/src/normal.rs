@@ -1,14 +1,21 @@
-use crate::okv_err;
+use crate::{okv_err, total_order};
 
 use super::{BoundCFHandle, RocksDbImpl};
+use crate::options::{column_descriptors, CfOptions};
 use inherent::inherent;
-use okv_core::{backend::DBColumnRefBatch, env::Env, error::Result, traits::Flushable};
+use okv_core::{
+    backend::{DBColumnDeleteRange, DBColumnRefBatch},
+    env::Env,
+    error::Result,
+    traits::Flushable,
+};
 use rocksdb::DBPinnableSlice;
 use self_cell::self_cell;
 
 /// A RocksDB database backend.
 pub struct RocksDb {
     pub(crate) db: rocksdb::DB,
+    pub(crate) cf_options: CfOptions,
 }
 
 impl Flushable for RocksDb {
@@ -41,6 +48,30 @@ impl Flushable for RocksDbColumn {
     }
 }
 
+impl DBColumnDeleteRange for RocksDbColumn {
+    /// Writes a range tombstone, so the number of deleted keys is not known.
+    fn delete_range(&self, start: &[u8], end: Option<&[u8]>) -> Result<Option<u64>> {
+        let mut batch = rocksdb::WriteBatch::default();
+        match end {
+            Some(end) => batch.delete_range_cf(self.cf_handle(), start, end),
+            // range tombstones need an end, so the last key is deleted separately
+            None => {
+                let mut iter = self
+                    .db()
+                    .raw_iterator_cf_opt(self.cf_handle(), total_order());
+                iter.seek_to_last();
+                if let Some(last) = iter.key().filter(|last| *last >= start) {
+                    batch.delete_range_cf(self.cf_handle(), start, last);
+                    batch.delete_cf(self.cf_handle(), last);
+                }
+                iter.status().map_err(okv_err)?;
+            }
+        }
+        self.db().write(batch).map_err(okv_err)?;
+        Ok(None)
+    }
+}
+
 #[inherent]
 impl RocksDbImpl for RocksDb {
     type RocksdbOptions = rocksdb::Options;
@@ -53,8 +84,12 @@ impl RocksDbImpl for RocksDb {
         cfs: &[String],
     ) -> Result<Self, rocksdb::Error> {
         config.create_if_missing(true);
-        let db = rocksdb::DB::open_cf(&config, connect_str, cfs.iter())?;
-        Ok(Self { db })
+        let cfs = column_descriptors(&config, connect_str, cfs)?;
+        let db = rocksdb::DB::open_cf_descriptors(&config, connect_str, cfs)?;
+        Ok(Self {
+            db,
+            cf_options: CfOptions::new(&config),
+        })
     }
 }
 
@@ -1,4 +1,5 @@
 use super::{BoundCFHandle, RocksDbImpl};
+use crate::options::{column_descriptors, CfOptions};
 use crate::{okv_err, Env, Flushable, Result};
 use inherent::inherent;
 use self_cell::self_cell;
@@ -6,6 +7,7 @@ use self_cell::self_cell;
 /// A RocksDB database backend with optimistic transactions.
 pub struct RocksDbOptimistic {
     pub(crate) db: rocksdb::OptimisticTransactionDB,
+    pub(crate) cf_options: CfOptions,
 }
 
 impl Flushable for RocksDbOptimistic {
@@ -43,7 +45,11 @@ impl RocksDbImpl for RocksDbOptimistic {
         cfs: &[String],
     ) -> Result<Self, rocksdb::Error> {
         config.create_if_missing(true);
-        let db = rocksdb::OptimisticTransactionDB::open_cf(&config, connect_str, cfs)?;
-        Ok(Self { db })
+        let cfs = column_descriptors(&config, connect_str, cfs)?;
+        let db = rocksdb::OptimisticTransactionDB::open_cf_descriptors(&config, connect_str, cfs)?;
+        Ok(Self {
+            db,
+            cf_options: CfOptions::new(&config),
+        })
     }
 }
@@ -0,0 +1,157 @@
+use std::{collections::HashMap, path::Path, sync::Mutex};
+
+use rocksdb::{
+    BlockBasedOptions, Cache, ColumnFamilyDescriptor, DBCompressionType, SliceTransform,
+};
+
+/// The block cache of column families whose options are loaded from the OPTIONS file,
+/// cache sizes are not persisted.
+const LOADED_BLOCK_CACHE_SIZE: usize = 32 << 20;
+
+/// The options of a database (column family), passed to
+/// [`Env::open_with`](okv_core::env::Env::open_with).
+///
+/// RocksDB stores the options of every column family in its OPTIONS file, and they are
+/// loaded again when the backend is opened, so a database keeps the options it was created
+/// with. Only block cache sizes are not restored, loaded column families share one cache.
+///
+/// # Examples
+///
+/// ```no_run
+/// use okv_core::env::Env;
+/// use okv_rocksdb::{ColumnOptions, RocksDb};
+///
+/// let env = Env::new(RocksDb::new("database/example-options").unwrap());
+/// // keys start with a 4 byte tenant id, scans only read the files of one tenant
+/// let db = env
+///     .open_with::<&[u8], &str>("events", ColumnOptions::prefix_scan(4))
+///     .unwrap();
+/// ```
+#[derive(Clone, Default)]
+pub struct ColumnOptions(rocksdb::Options);
+
+impl ColumnOptions {
+    /// Options for lookups of single keys: a bloom filter on whole keys, in the files and
+    /// in the memtable, a hash index in every block and a block cache of `block_cache_mb`
+    /// megabytes.
+    pub fn point_lookup(block_cache_mb: u64) -> Self {
+        let mut opts = rocksdb::Options::default();
+        opts.optimize_for_point_lookup(block_cache_mb);
+        Self(opts)
+    }
+
+    /// Options for scans of keys that start with the same `prefix_len` bytes: a bloom
+    /// filter on the prefixes, in the files and in the memtable. Shorter keys are not
+    /// added to the filters. Scans that can cross prefixes, like iterating over all keys,
+    /// key ranges and pages, seek in total order and don't use the filters.
+    pub fn prefix_scan(prefix_len: usize) -> Self {
+        let mut opts = rocksdb::Options::default();
+        opts.set_prefix_extractor(SliceTransform::create_fixed_prefix(prefix_len));
+        opts.set_memtable_prefix_bloom_ratio(0.1);
+        let mut table = BlockBasedOptions::default();
+        table.set_bloom_filter(10.0, false);
+        table.set_whole_key_filtering(false);
+        opts.set_block_based_table_factory(&table);
+        Self(opts)
+    }
+
+    /// Options for many writes: larger and more memtables, which are merged before they
+    /// are flushed, and level compaction tuned for a 512 MB memtable budget.
+    pub fn write_heavy() -> Self {
+        let mut opts = rocksdb::Options::default();
+        opts.optimize_level_style_compaction(512 << 20);
+        opts.set_write_buffer_size(128 << 20);
+        opts.set_max_write_buffer_number(4);
+        opts.set_min_write_buffer_number_to_merge(2);
+        Self(opts)
+    }
+
+    /// Options for large, rarely changed values: LZ4 for new files and Zstandard for the
+    /// last level, which holds most of the data. Requires the `compression` feature.
+    pub fn compressed() -> Self {
+        let mut opts = rocksdb::Options::default();
+        opts.set_compression_type(DBCompressionType::Lz4);
+        opts.set_bottommost_compression_type(DBCompressionType::Zstd);
+        Self(opts)
+    }
+
+    /// Returns the RocksDB options.
+    pub fn options(&self) -> &rocksdb::Options {
+        &self.0
+    }
+
+    /// Returns the RocksDB options, e.g. to change a profile.
+    pub fn options_mut(&mut self) -> &mut rocksdb::Options {
+        &mut self.0
+    }
+}
+
+impl From<rocksdb::Options> for ColumnOptions {
+    fn from(opts: rocksdb::Options) -> Self {
+        Self(opts)
+    }
+}
+
+/// Descriptors of the column families `cfs`, with the options from the latest OPTIONS file
+/// of the database at `path`, or `config` for column families without persisted options.
+pub(crate) fn column_descriptors(
+    config: &rocksdb::Options,
+    path: impl AsRef<Path>,
+    cfs: &[String],
+) -> Result<Vec<ColumnFamilyDescriptor>, rocksdb::Error> {
+    // a new database has no OPTIONS file yet
+    let mut loaded = match path.as_ref().join("CURRENT").exists() {
+        true => {
+            let cache = Cache::new_lru_cache(LOADED_BLOCK_CACHE_SIZE);
+            let env = rocksdb::Env::new()?;
+            rocksdb::Options::load_latest(path, env, true, cache)?.1
+        }
+        false => Vec::new(),
+    };
+    loaded.retain(|cf| cfs.iter().any(|name| name == cf.name()));
+
+    let missing: Vec<_> = cfs
+        .iter()
+        .filter(|name| !loaded.iter().any(|cf| cf.name() == name.as_str()))
+        .map(|name| ColumnFamilyDescriptor::new(name, config.clone()))
+        .collect();
+    loaded.extend(missing);
+    Ok(loaded)
+}
+
+/// The options of the column families created by a backend, so a renamed database can be
+/// created with the options of its source.
+///
+/// RocksDB doesn't expose the options of an open column family, so column families that
+/// were created before the backend was opened fall back to the options the backend was
+/// opened with.
+pub(crate) struct CfOptions {
+    config: rocksdb::Options,
+    created: Mutex<HashMap<String, rocksdb::Options>>,
+}
+
+impl CfOptions {
+    pub(crate) fn new(config: &rocksdb::Options) -> Self {
+        Self {
+            config: config.clone(),
+            created: Mutex::default(),
+        }
+    }
+
+    /// Record the options a column family was created with.
+    pub(crate) fn insert(&self, cf: &str, opts: &rocksdb::Options) {
+        let mut created = self.created.lock().unwrap_or_else(|e| e.into_inner());
+        created.insert(cf.to_owned(), opts.clone());
+    }
+
+    /// Returns the options a column family was created with.
+    pub(crate) fn get(&self, cf: &str) -> rocksdb::Options {
+        let created = self.created.lock().unwrap_or_else(|e| e.into_inner());
+        created.get(cf).unwrap_or(&self.config).clone()
+    }
+
+    pub(crate) fn remove(&self, cf: &str) {
+        let mut created = self.created.lock().unwrap_or_else(|e| e.into_inner());
+        created.remove(cf);
+    }
+}
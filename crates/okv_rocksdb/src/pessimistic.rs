@@ -1,4 +1,5 @@
 use super::{BoundCFHandle, RocksDbImpl};
+use crate::options::{column_descriptors, CfOptions};
 use crate::{Env, Result};
 use inherent::inherent;
 use self_cell::self_cell;
@@ -6,6 +7,7 @@ use self_cell::self_cell;
 /// A RocksDB database backend with pessimistic transactions.
 pub struct RocksDbPessimistic {
     pub(crate) db: rocksdb::TransactionDB,
+    pub(crate) cf_options: CfOptions,
 }
 
 /// A RocksDB database column family.
@@ -36,7 +38,12 @@ impl RocksDbImpl for RocksDbPessimistic {
         cfs: &[String],
     ) -> Result<Self, rocksdb::Error> {
         config.0.create_if_missing(true);
-        let db = rocksdb::TransactionDB::open_cf(&config.0, &config.1, connect_str, cfs)?;
-        Ok(Self { db })
+        let cfs = column_descriptors(&config.0, connect_str, cfs)?;
+        let db =
+            rocksdb::TransactionDB::open_cf_descriptors(&config.0, &config.1, connect_str, cfs)?;
+        Ok(Self {
+            db,
+            cf_options: CfOptions::new(&config.0),
+        })
     }
 }
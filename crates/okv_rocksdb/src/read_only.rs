@@ -0,0 +1,52 @@
+use super::{BoundCFHandle, RocksDbImpl};
+use crate::options::{column_descriptors, CfOptions};
+use crate::{Env, Result};
+use inherent::inherent;
+use self_cell::self_cell;
+
+/// A RocksDB database opened read-only.
+///
+/// The database can be opened read-only by any number of processes, including while a
+/// primary instance writes to it. Writes of the primary after opening are not visible, see
+/// [`RocksDbSecondary`](crate::RocksDbSecondary) to follow the primary. Writes and
+/// creating databases return an error.
+pub struct RocksDbReadOnly {
+    pub(crate) db: rocksdb::DB,
+    pub(crate) cf_options: CfOptions,
+}
+
+/// A RocksDB database column family, opened read-only.
+pub struct RocksDbReadOnlyColumn {
+    pub(crate) name: String,
+    pub(crate) inner: RocksDbReadOnlyColumnInner,
+}
+
+self_cell!(
+    /// A RocksDB database column family.
+    pub(crate) struct RocksDbReadOnlyColumnInner {
+        owner: Env<RocksDbReadOnly>,
+
+        #[covariant]
+        dependent: BoundCFHandle,
+    }
+);
+
+#[inherent]
+impl RocksDbImpl for RocksDbReadOnly {
+    type RocksdbOptions = rocksdb::Options;
+    pub fn new(connect_str: &str) -> Result<Self, rocksdb::Error>;
+    pub fn list_databases(connect_str: &str) -> Result<Option<Vec<String>>, rocksdb::Error>;
+
+    pub fn new_with_config(
+        config: rocksdb::Options,
+        connect_str: &str,
+        cfs: &[String],
+    ) -> Result<Self, rocksdb::Error> {
+        let cfs = column_descriptors(&config, connect_str, cfs)?;
+        let db = rocksdb::DB::open_cf_descriptors_read_only(&config, connect_str, cfs, false)?;
+        Ok(Self {
+            db,
+            cf_options: CfOptions::new(&config),
+        })
+    }
+}
@@ -0,0 +1,63 @@
+use super::BoundCFHandle;
+use crate::options::{column_descriptors, CfOptions};
+use crate::{okv_err, Env, Result, RocksDbReadOnly};
+use self_cell::self_cell;
+
+/// A RocksDB secondary instance, which reads the files of a primary instance.
+///
+/// Unlike [`RocksDbReadOnly`], a secondary instance can follow the
+/// writes of the primary with [`RocksDbSecondary::try_catch_up_with_primary`], e.g. for
+/// analytics jobs that shouldn't slow down the writer. It keeps its own logs in a separate
+/// directory. Databases created by the primary after opening are not visible.
+pub struct RocksDbSecondary {
+    pub(crate) db: rocksdb::DB,
+    pub(crate) cf_options: CfOptions,
+}
+
+/// A RocksDB database column family of a secondary instance.
+pub struct RocksDbSecondaryColumn {
+    pub(crate) name: String,
+    pub(crate) inner: RocksDbSecondaryColumnInner,
+}
+
+self_cell!(
+    /// A RocksDB database column family.
+    pub(crate) struct RocksDbSecondaryColumnInner {
+        owner: Env<RocksDbSecondary>,
+
+        #[covariant]
+        dependent: BoundCFHandle,
+    }
+);
+
+impl RocksDbSecondary {
+    /// Open a secondary instance of the database at `primary` with all its column families,
+    /// storing its logs in `secondary`.
+    pub fn new(primary: &str, secondary: &str) -> Result<Self, rocksdb::Error> {
+        let cfs = RocksDbReadOnly::list_databases(primary)?.unwrap_or_default();
+        Self::new_with_config(rocksdb::Options::default(), primary, secondary, &cfs)
+    }
+
+    /// Open a secondary instance with a custom configuration.
+    /// RocksDB requires secondary instances to keep all files open, so `max_open_files` is
+    /// set to `-1`.
+    pub fn new_with_config(
+        mut config: rocksdb::Options,
+        primary: &str,
+        secondary: &str,
+        cfs: &[String],
+    ) -> Result<Self, rocksdb::Error> {
+        config.set_max_open_files(-1);
+        let cfs = column_descriptors(&config, primary, cfs)?;
+        let db = rocksdb::DB::open_cf_descriptors_as_secondary(&config, primary, secondary, cfs)?;
+        Ok(Self {
+            db,
+            cf_options: CfOptions::new(&config),
+        })
+    }
+
+    /// Read the latest writes of the primary instance.
+    pub fn try_catch_up_with_primary(&self) -> Result<()> {
+        self.db.try_catch_up_with_primary().map_err(okv_err)
+    }
+}
@@ -5,7 +5,7 @@ use rocksdb::{DBPinnableSlice, OptimisticTransactionDB, TransactionDB};
 use okv_core::backend::*;
 use okv_core::error::Result;
 
-use crate::okv_err;
+use crate::{okv_err, raw_keys, total_order};
 
 use super::{RocksDbOptimisticColumn, RocksDbPessimisticColumn};
 
@@ -105,11 +105,31 @@ impl<'a, DB> DBColumnRef<'a> for RocksDBTransaction<'a, DB> {
     }
 }
 
+impl<'a, DB> DBColumnDeleteRange for RocksDBTransaction<'a, DB> {
+    fn delete_range(&self, start: &[u8], end: Option<&[u8]>) -> Result<Option<u64>> {
+        let mut iter = self.tx.raw_iterator_cf_opt(&self.cf_handle, total_order());
+        iter.seek(start);
+
+        // collect the keys first, deleting them while iterating would change the iterator
+        let mut keys = Vec::new();
+        while let Some(key) = iter.key().filter(|key| end.map_or(true, |end| *key < end)) {
+            keys.push(key.to_vec());
+            iter.next();
+        }
+        iter.status().map_err(okv_err)?;
+
+        for key in &keys {
+            self.tx.delete_cf(&self.cf_handle, key).map_err(okv_err)?;
+        }
+        Ok(Some(keys.len() as u64))
+    }
+}
+
 impl<'a, DB> DBColumnIterator for RocksDBTransaction<'a, DB> {
     fn iter(&self) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>> {
         let iter = self
             .tx
-            .iterator_cf(&self.cf_handle, rocksdb::IteratorMode::Start)
+            .iterator_cf_opt(&self.cf_handle, total_order(), rocksdb::IteratorMode::Start)
             .map(|v| match v {
                 Ok((k, v)) => Ok((k.to_vec(), v.to_vec())),
                 Err(e) => Err(okv_err(e)),
@@ -117,6 +137,12 @@ impl<'a, DB> DBColumnIterator for RocksDBTransaction<'a, DB> {
 
         Ok(iter)
     }
+
+    fn keys(&self) -> Result<impl Iterator<Item = Result<Vec<u8>>>> {
+        let mut iter = self.tx.raw_iterator_cf_opt(&self.cf_handle, total_order());
+        iter.seek_to_first();
+        Ok(raw_keys(iter, Vec::new()))
+    }
 }
 
 impl<'a, DB> DBColumnIteratorPrefix for RocksDBTransaction<'a, DB> {
@@ -137,4 +163,13 @@ impl<'a, DB> DBColumnIteratorPrefix for RocksDBTransaction<'a, DB> {
 
         Ok(iter)
     }
+
+    fn keys_prefix(
+        &self,
+        prefix: impl AsRef<[u8]>,
+    ) -> Result<impl Iterator<Item = Result<Vec<u8>>>> {
+        let mut iter = self.tx.raw_iterator_cf(&self.cf_handle);
+        iter.seek(prefix.as_ref());
+        Ok(raw_keys(iter, prefix.as_ref().to_vec()))
+    }
 }
@@ -0,0 +1,12 @@
+use crate::{SledColumn, SledTransaction};
+use okv_core::async_fallback;
+use okv_core::backend::DBColumn;
+
+async_fallback!(SledColumn);
+
+impl<'a> okv_core::backend_async::DBColumnAsync for SledTransaction<'a>
+where
+    SledTransaction<'a>: okv_core::backend::DBColumn,
+{
+    okv_core::async_fallback_impl!();
+}
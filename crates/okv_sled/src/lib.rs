@@ -0,0 +1,350 @@
+//! Pure Rust database backend for OKV, based on [sled].
+//!
+//! Every OKV database is a sled tree in a single sled database.
+
+use std::{ops::Bound, path::Path};
+
+use okv_core::{
+    backend::*,
+    env::Env,
+    error::{Error, Result},
+    traits::{Flushable, Innerable},
+};
+
+pub use sled;
+use sled::IVec;
+
+mod r#async;
+mod tx;
+
+pub use tx::{SledTransaction, TransactionConflict};
+
+pub(crate) fn okv_err(e: impl std::error::Error + Send + Sync + 'static) -> Error {
+    Error::DatabaseBackend(Box::new(e))
+}
+
+/// A sled database backend.
+pub struct Sled {
+    db: sled::Db,
+}
+
+impl Sled {
+    /// Open or create a database in the given directory with the default configuration.
+    pub fn new(path: impl AsRef<Path>) -> Result<Self, sled::Error> {
+        Ok(Self {
+            db: sled::open(path)?,
+        })
+    }
+
+    /// Open or create a database with the given configuration.
+    pub fn new_with_config(config: sled::Config) -> Result<Self, sled::Error> {
+        Ok(Self { db: config.open()? })
+    }
+}
+
+impl Innerable for Sled {
+    type Inner = sled::Db;
+    fn inner(&self) -> &Self::Inner {
+        &self.db
+    }
+}
+
+impl Flushable for Sled {
+    fn flush(&self) -> Result<()> {
+        self.db.flush().map_err(okv_err)?;
+        Ok(())
+    }
+}
+
+impl DatabaseBackend for Sled {
+    type Column = SledColumn;
+
+    fn create_or_open(env: Env<Self>, db: &str) -> Result<Self::Column> {
+        let tree = env.db().db.open_tree(db).map_err(okv_err)?;
+        Ok(SledColumn {
+            env,
+            name: db.to_string(),
+            tree,
+        })
+    }
+
+    fn list_databases(&self) -> Result<Vec<String>> {
+        let default = self.db.name();
+        self.db
+            .tree_names()
+            .into_iter()
+            .filter(|name| *name != default)
+            .map(|name| {
+                String::from_utf8(name.to_vec())
+                    .map_err(|_| Error::Unknown("database name is not valid utf-8".into()))
+            })
+            .collect()
+    }
+
+    fn drop_database(&self, db: &str) -> Result<()> {
+        self.db.drop_tree(db).map_err(okv_err)?;
+        Ok(())
+    }
+
+    /// Copies all entries to a new tree and drops the old one, which is not atomic.
+    fn rename_database(&self, old: &str, new: &str) -> Result<()> {
+        let source = self.db.open_tree(old).map_err(okv_err)?;
+        let target = self.db.open_tree(new).map_err(okv_err)?;
+        for item in source.iter() {
+            let (key, val) = item.map_err(okv_err)?;
+            target.insert(key, val).map_err(okv_err)?;
+        }
+        self.drop_database(old)
+    }
+}
+
+/// A sled tree.
+pub struct SledColumn {
+    env: Env<Sled>,
+    name: String,
+    pub(crate) tree: sled::Tree,
+}
+
+impl SledColumn {
+    /// Returns the underlying sled tree.
+    pub fn tree(&self) -> &sled::Tree {
+        &self.tree
+    }
+}
+
+impl Flushable for SledColumn {
+    /// Flush all trees of the database, sled doesn't support flushing a single tree.
+    fn flush(&self) -> Result<()> {
+        self.tree.flush().map_err(okv_err)?;
+        Ok(())
+    }
+}
+
+impl DBColumn for SledColumn {
+    fn set(&self, key: impl AsRef<[u8]>, val: impl AsRef<[u8]>) -> Result<()> {
+        self.tree
+            .insert(key.as_ref(), val.as_ref())
+            .map_err(okv_err)?;
+        Ok(())
+    }
+
+    fn set_nx(&self, key: impl AsRef<[u8]>, val: impl AsRef<[u8]>) -> Result<bool> {
+        let res = self
+            .tree
+            .compare_and_swap(key.as_ref(), None::<&[u8]>, Some(val.as_ref()))
+            .map_err(okv_err)?;
+        Ok(res.is_ok())
+    }
+
+    fn get(&self, key: impl AsRef<[u8]>) -> Result<Option<Vec<u8>>> {
+        let res = self.tree.get(key).map_err(okv_err)?;
+        Ok(res.map(|v| v.to_vec()))
+    }
+
+    fn get_multi<I>(&self, keys: I) -> Result<Vec<Option<Vec<u8>>>>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        keys.into_iter().map(|key| self.get(key)).collect()
+    }
+
+    fn delete(&self, key: impl AsRef<[u8]>) -> Result<()> {
+        self.tree.remove(key).map_err(okv_err)?;
+        Ok(())
+    }
+
+    fn contains(&self, key: impl AsRef<[u8]>) -> Result<bool> {
+        self.tree.contains_key(key).map_err(okv_err)
+    }
+}
+
+impl DBColumnClear for SledColumn {
+    fn clear(&self) -> Result<()> {
+        self.tree.clear().map_err(okv_err)
+    }
+}
+
+impl DBColumnBatch for SledColumn {
+    /// The pairs are set in a single atomic batch.
+    fn set_multi<I, K, V>(&self, entries: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        let mut batch = sled::Batch::default();
+        for (key, val) in entries {
+            batch.insert(key.as_ref(), val.as_ref());
+        }
+        self.tree.apply_batch(batch).map_err(okv_err)
+    }
+
+    /// The writes are applied in a single atomic batch.
+    fn write_multi<I, K, V>(&self, writes: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (K, Option<V>)>,
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        let mut batch = sled::Batch::default();
+        for (key, val) in writes {
+            match val {
+                Some(val) => batch.insert(key.as_ref(), val.as_ref()),
+                None => batch.remove(key.as_ref()),
+            }
+        }
+        self.tree.apply_batch(batch).map_err(okv_err)
+    }
+}
+
+impl DBColumnDeleteRange for SledColumn {
+    /// The keys are deleted in a single atomic batch.
+    fn delete_range(&self, start: &[u8], end: Option<&[u8]>) -> Result<Option<u64>> {
+        let end = end.map_or(Bound::Unbounded, Bound::Excluded);
+        let mut batch = sled::Batch::default();
+        let mut deleted = 0;
+        for key in self
+            .tree
+            .range::<&[u8], _>((Bound::Included(start), end))
+            .keys()
+        {
+            batch.remove(key.map_err(okv_err)?);
+            deleted += 1;
+        }
+        self.tree.apply_batch(batch).map_err(okv_err)?;
+        Ok(Some(deleted))
+    }
+}
+
+impl DBColumnDelete for SledColumn {
+    fn delete_db(&self) -> Result<()> {
+        self.env.db().db.drop_tree(&self.name).map_err(okv_err)?;
+        Ok(())
+    }
+}
+
+impl<'c> DBColumnRef<'c> for SledColumn {
+    /// Values are reference counted, so they can be returned without copying them.
+    type Ref = IVec;
+
+    fn get_ref(&'c self, key: impl AsRef<[u8]>) -> Result<Option<Self::Ref>> {
+        self.tree.get(key).map_err(okv_err)
+    }
+}
+
+impl<'c> DBColumnRefBatch<'c> for SledColumn {
+    type Ref = IVec;
+
+    fn get_multi_ref<'a, K, I>(&'c self, keys: I) -> Result<Vec<Option<Self::Ref>>>
+    where
+        K: AsRef<[u8]> + 'a + ?Sized,
+        I: IntoIterator<Item = &'a K>,
+    {
+        keys.into_iter()
+            .map(|key| self.tree.get(key).map_err(okv_err))
+            .collect()
+    }
+}
+
+fn to_item(item: sled::Result<(IVec, IVec)>) -> Result<(Vec<u8>, Vec<u8>)> {
+    let (k, v) = item.map_err(okv_err)?;
+    Ok((k.to_vec(), v.to_vec()))
+}
+
+fn to_key(key: sled::Result<IVec>) -> Result<Vec<u8>> {
+    Ok(key.map_err(okv_err)?.to_vec())
+}
+
+impl DBColumnIterator for SledColumn {
+    fn iter(&self) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>> {
+        Ok(self.tree.iter().map(to_item))
+    }
+
+    fn keys(&self) -> Result<impl Iterator<Item = Result<Vec<u8>>>> {
+        Ok(self.tree.iter().keys().map(to_key))
+    }
+
+    fn count(&self) -> Result<u64> {
+        Ok(self.tree.len() as u64)
+    }
+}
+
+impl DBColumnStats for SledColumn {
+    /// sled only knows the size of the whole database, so it is reported in the
+    /// `size_on_disk` property instead of the disk size of the tree.
+    fn stats(&self) -> Result<ColumnStats> {
+        let size_on_disk = self.env.db().db.size_on_disk().map_err(okv_err)?;
+        let mut stats = ColumnStats::new(KeyCount::Exact(self.tree.len() as u64));
+        stats
+            .properties
+            .insert("size_on_disk".to_string(), size_on_disk.to_string());
+        Ok(stats)
+    }
+}
+
+impl DBColumnIteratorRef for SledColumn {
+    /// Values are reference counted, so they are visited without copying them.
+    fn for_each_ref(
+        &self,
+        prefix: &[u8],
+        mut f: impl FnMut(&[u8], &[u8]) -> Result<bool>,
+    ) -> Result<()> {
+        for item in self.tree.scan_prefix(prefix) {
+            let (k, v) = item.map_err(okv_err)?;
+            if !f(&k, &v)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl DBColumnIteratorRange for SledColumn {
+    fn iter_range(
+        &self,
+        range: KeyRange,
+    ) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>> {
+        let end = range.end.map_or(Bound::Unbounded, Bound::Excluded);
+        Ok(self
+            .tree
+            .range((Bound::Included(range.start), end))
+            .map(to_item))
+    }
+}
+
+impl DBColumnPage for SledColumn {
+    fn page(
+        &self,
+        after: Option<&[u8]>,
+        limit: usize,
+        prefix: &[u8],
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let range = (page_start(after, prefix), Bound::Unbounded);
+        self.tree
+            .range::<&[u8], _>(range)
+            .map(to_item)
+            .take_while(|item| match item {
+                Ok((k, _)) => k.starts_with(prefix),
+                Err(_) => true,
+            })
+            .take(limit)
+            .collect()
+    }
+}
+
+impl DBColumnIteratorPrefix for SledColumn {
+    fn iter_prefix(
+        &self,
+        prefix: impl AsRef<[u8]>,
+    ) -> Result<impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>> {
+        Ok(self.tree.scan_prefix(prefix).map(to_item))
+    }
+
+    fn keys_prefix(
+        &self,
+        prefix: impl AsRef<[u8]>,
+    ) -> Result<impl Iterator<Item = Result<Vec<u8>>>> {
+        Ok(self.tree.scan_prefix(prefix).keys().map(to_key))
+    }
+}
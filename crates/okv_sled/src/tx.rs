@@ -0,0 +1,136 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+use okv_core::{
+    backend::{DBColumn, DBColumnTransaction, DBTransaction},
+    error::Result,
+};
+use sled::{
+    transaction::{abort, TransactionError},
+    IVec,
+};
+
+use crate::{okv_err, SledColumn};
+
+/// The error returned when committing a [`SledTransaction`] whose reads are outdated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransactionConflict;
+
+impl fmt::Display for TransactionConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "transaction conflict, a key read in the transaction was modified"
+        )
+    }
+}
+
+impl std::error::Error for TransactionConflict {}
+
+/// An optimistic transaction on a sled tree.
+///
+/// Writes are buffered until the transaction is committed, and reads see the buffered
+/// writes of this transaction. On commit, every key read in the transaction is checked
+/// against its current value and the commit fails with [`TransactionConflict`] if
+/// any of them was modified since.
+pub struct SledTransaction<'a> {
+    column: &'a SledColumn,
+    reads: RefCell<HashMap<Vec<u8>, Option<IVec>>>,
+    writes: RefCell<BTreeMap<Vec<u8>, Option<Vec<u8>>>>,
+}
+
+impl<'a> DBColumnTransaction<'a> for SledColumn {
+    type Txn = SledTransaction<'a>;
+
+    fn transaction(&'a self) -> Result<Self::Txn> {
+        Ok(SledTransaction {
+            column: self,
+            reads: RefCell::new(HashMap::new()),
+            writes: RefCell::new(BTreeMap::new()),
+        })
+    }
+}
+
+impl<'a> SledTransaction<'a> {
+    /// Read a key from the tree and remember the value that was read.
+    fn read(&self, key: &[u8]) -> Result<Option<IVec>> {
+        if let Some(val) = self.reads.borrow().get(key) {
+            return Ok(val.clone());
+        }
+
+        let val = self.column.tree.get(key).map_err(okv_err)?;
+        self.reads.borrow_mut().insert(key.to_vec(), val.clone());
+        Ok(val)
+    }
+}
+
+impl<'a> DBColumn for SledTransaction<'a> {
+    fn set(&self, key: impl AsRef<[u8]>, val: impl AsRef<[u8]>) -> Result<()> {
+        self.writes
+            .borrow_mut()
+            .insert(key.as_ref().to_vec(), Some(val.as_ref().to_vec()));
+        Ok(())
+    }
+
+    fn get(&self, key: impl AsRef<[u8]>) -> Result<Option<Vec<u8>>> {
+        if let Some(val) = self.writes.borrow().get(key.as_ref()) {
+            return Ok(val.clone());
+        }
+        Ok(self.read(key.as_ref())?.map(|v| v.to_vec()))
+    }
+
+    fn get_multi<I>(&self, keys: I) -> Result<Vec<Option<Vec<u8>>>>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        keys.into_iter().map(|key| self.get(key)).collect()
+    }
+
+    fn delete(&self, key: impl AsRef<[u8]>) -> Result<()> {
+        self.writes.borrow_mut().insert(key.as_ref().to_vec(), None);
+        Ok(())
+    }
+
+    fn contains(&self, key: impl AsRef<[u8]>) -> Result<bool> {
+        if let Some(val) = self.writes.borrow().get(key.as_ref()) {
+            return Ok(val.is_some());
+        }
+        Ok(self.read(key.as_ref())?.is_some())
+    }
+}
+
+impl<'a> DBTransaction for SledTransaction<'a> {
+    fn commit(self) -> Result<()> {
+        let reads = self.reads.into_inner();
+        let writes = self.writes.into_inner();
+        if writes.is_empty() {
+            return Ok(());
+        }
+
+        let res = self.column.tree.transaction(|tree| {
+            for (key, val) in &reads {
+                if tree.get(key)? != *val {
+                    return abort(TransactionConflict);
+                }
+            }
+
+            for (key, val) in &writes {
+                match val {
+                    Some(val) => tree.insert(key.as_slice(), val.as_slice())?,
+                    None => tree.remove(key.as_slice())?,
+                };
+            }
+            Ok(())
+        });
+
+        res.map_err(|e: TransactionError<TransactionConflict>| okv_err(e))
+    }
+
+    fn rollback(self) -> Result<()> {
+        Ok(())
+    }
+}
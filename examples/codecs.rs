@@ -0,0 +1,74 @@
+use eyre::Result;
+use okv::backend::memory::MemDB;
+use okv::types::prost::Prost;
+use okv::types::rkyv::Rkyv;
+use okv::types::serde::{SerdeBincode, SerdeCbor, SerdePostcard};
+use okv::{Env, TypeName};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Person {
+    name: String,
+    age: u32,
+}
+
+impl TypeName for Person {
+    const TYPE_NAME: &'static str = "person";
+}
+
+#[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Debug, PartialEq)]
+#[archive(check_bytes)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+impl TypeName for Point {
+    const TYPE_NAME: &'static str = "point";
+}
+
+fn main() -> Result<()> {
+    let env = Env::new(MemDB::new());
+    let john = Person {
+        name: "John Doe".to_string(),
+        age: 42,
+    };
+
+    // the serde codecs all store the same type in different formats
+    let db = env.open::<&str, SerdeBincode<Person>>("bincode")?;
+    db.set("john", &john)?;
+    assert_eq!(db.get("john")?.as_ref(), Some(&john));
+
+    let db = env.open::<&str, SerdePostcard<Person>>("postcard")?;
+    db.set("john", &john)?;
+    assert_eq!(db.get("john")?.as_ref(), Some(&john));
+
+    let db = env.open::<&str, SerdeCbor<Person>>("cbor")?;
+    db.set("john", &john)?;
+    assert_eq!(db.get("john")?.as_ref(), Some(&john));
+
+    // protobuf messages, usually generated by prost-build
+    let db = env.open::<&str, Prost<String>>("prost")?;
+    db.set("greeting", &"hello".to_string())?;
+    assert_eq!(db.get("greeting")?, Some("hello".to_string()));
+
+    // rkyv archives are read without copying them if the backend returns aligned bytes,
+    // otherwise they are copied into an aligned buffer first
+    let db = env.open::<&str, Rkyv<Point>>("rkyv")?;
+    db.set("origin", &Point { x: 0, y: 0 })?;
+    db.set("corner", &Point { x: 3, y: 4 })?;
+    let corner = db.get_ref("corner")?.expect("value exists");
+    let archived = corner.deserialize()?;
+    assert_eq!((archived.x, archived.y), (3, 4));
+
+    // or copied and deserialized, which works on every backend
+    assert_eq!(db.get("origin")?, Some(Point { x: 0, y: 0 }));
+
+    Ok(())
+}
+
+#[test] // ensure that the example always works
+fn test() -> Result<()> {
+    main()
+}
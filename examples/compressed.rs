@@ -0,0 +1,91 @@
+use std::sync::OnceLock;
+
+use eyre::Result;
+use okv::backend::memory::MemDB;
+use okv::types::compressed::{
+    train_zstd_dictionary, Compressed, Lz4, Snappy, Zstd, ZstdDict, ZstdDictionary,
+};
+use okv::types::serde::SerdeJson;
+use okv::{Env, TypeName};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct User {
+    name: String,
+    email: String,
+    roles: Vec<String>,
+}
+
+impl TypeName for User {
+    const TYPE_NAME: &'static str = "user";
+}
+
+fn user(i: usize) -> User {
+    User {
+        name: format!("user {i}"),
+        email: format!("user{i}@example.com"),
+        roles: vec!["reader".into(), "writer".into()],
+    }
+}
+
+// a dictionary trained on the stored users, usually loaded from a file
+static DICTIONARY: OnceLock<Vec<u8>> = OnceLock::new();
+
+struct Users;
+
+impl ZstdDictionary for Users {
+    fn dictionary() -> &'static [u8] {
+        DICTIONARY.get().expect("dictionary is trained")
+    }
+}
+
+fn main() -> Result<()> {
+    let env = Env::new(MemDB::new());
+
+    // values written without compression
+    let plain = env.open::<u64, SerdeJson<User>>("users")?;
+    for i in 0..1000 {
+        plain.set(&(i as u64), &user(i))?;
+    }
+
+    // stay readable when the database is opened compressed
+    let db = env.open::<u64, Compressed<SerdeJson<User>, Lz4>>("users")?;
+    assert_eq!(db.get(&1)?, Some(user(1)));
+    db.set(&1000, &user(1000))?;
+
+    // values are decoded with the algorithm they were written with
+    let db = env.open::<u64, Compressed<SerdeJson<User>, Snappy>>("users")?;
+    assert_eq!(db.get(&1000)?, Some(user(1000)));
+    let db = env.open::<u64, Compressed<SerdeJson<User>, Zstd>>("users")?;
+    assert_eq!(db.get(&1000)?, Some(user(1000)));
+
+    // train a dictionary on the stored values and rewrite them with it
+    let samples = plain
+        .iter_raw()?
+        .map(|item| item.map(|(_, val)| val))
+        .collect::<Result<Vec<_>, okv::Error>>()?;
+    DICTIONARY.get_or_init(|| train_zstd_dictionary(&samples, 1024).expect("enough samples"));
+
+    // keys are encoded as little endian integers
+    let key = 42u64.to_le_bytes();
+    let json = plain.get_raw(key)?.expect("user exists");
+
+    let db = env.open::<u64, Compressed<SerdeJson<User>, ZstdDict<Users>>>("users")?;
+    for i in 0..=1000 {
+        let user = db.get(&(i as u64))?.expect("user exists");
+        db.set(&(i as u64), &user)?;
+    }
+    assert_eq!(db.get(&42)?, Some(user(42)));
+
+    // small values compress much better with a dictionary
+    let compressed = db.get_raw(key)?.expect("user exists");
+    assert!(compressed.len() < json.len() / 2);
+
+    Ok(())
+}
+
+#[test] // ensure that the example always works
+fn test() -> Result<()> {
+    main()
+}
@@ -0,0 +1,44 @@
+use eyre::Result;
+use okv::backend::memory::MemDB;
+use okv::Env;
+
+fn main() -> Result<()> {
+    let env = Env::new(MemDB::new());
+    let users = env.open::<&str, &str>("users")?;
+    users.set("alice", "admin")?;
+    users.set("bob", "guest")?;
+    let settings = env.open::<&str, &str>("settings")?;
+    settings.set("theme", "dark")?;
+
+    // export all databases, the dump can be imported into any other backend
+    std::fs::create_dir_all("database")?;
+    let path = "database/example-dump.okvdump";
+    let stats = env.export(std::fs::File::create(path)?)?;
+    assert_eq!((stats.databases, stats.entries), (2, 3));
+
+    let copy = Env::new(MemDB::new());
+    copy.import(std::fs::File::open(path)?)?;
+    let users = copy.open::<&str, &str>("users")?;
+    assert_eq!(users.get("bob")?, Some("guest".to_string()));
+    let settings = copy.open::<&str, &str>("settings")?;
+    assert_eq!(settings.get("theme")?, Some("dark".to_string()));
+
+    // a single database can be imported under a different name
+    let mut dump = Vec::new();
+    settings.export(&mut dump)?;
+    let renamed = copy.open::<&str, &str>("preferences")?;
+    renamed.import(dump.as_slice())?;
+    assert_eq!(renamed.get("theme")?, Some("dark".to_string()));
+
+    // corrupted dumps are rejected
+    let last = dump.len() - 1;
+    dump[last] ^= 0xff;
+    assert!(renamed.import(dump.as_slice()).is_err());
+
+    Ok(())
+}
+
+#[test] // ensure that the example always works
+fn test() -> Result<()> {
+    main()
+}
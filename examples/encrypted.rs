@@ -0,0 +1,90 @@
+use eyre::Result;
+use okv::backend::encrypted::{EncryptedBackend, Key, Keyring};
+use okv::backend::memory::MemDB;
+use okv::{DBCommonIterPrefix, Env};
+
+fn main() -> Result<()> {
+    std::fs::create_dir_all("database")?;
+    let path = "database/example-encrypted.okv";
+    for file in [path, "database/example-encrypted.okv.wal"] {
+        let _ = std::fs::remove_file(file);
+    }
+
+    {
+        // values are encrypted before they are written to the log
+        let keys = Keyring::new(1, Key::aes_256_gcm(&[1; 32]));
+        let env = Env::new(EncryptedBackend::new(MemDB::open(path)?, keys));
+        let db = env.open::<&str, &str>("emails")?;
+        db.set("alice", "alice@example.com")?;
+        db.set("bob", "bob@example.com")?;
+    }
+
+    {
+        // without the keys, only the keys are readable
+        let env = Env::new(MemDB::open(path)?);
+        let db = env.open::<&str, Vec<u8>>("emails")?;
+        let val = db.get("alice")?.expect("value exists");
+        assert!(!val.windows(5).any(|w| w == b"alice"));
+    }
+
+    {
+        // rotate to a new key, the old key is still needed to read existing values
+        let keys = Keyring::new(2, Key::chacha20_poly1305(&[2; 32]))
+            .with_key(1, Key::aes_256_gcm(&[1; 32]));
+        let env = Env::new(EncryptedBackend::new(MemDB::open(path)?, keys));
+        let db = env.open::<&str, &str>("emails")?;
+        assert_eq!(db.get("alice")?, Some("alice@example.com".to_string()));
+
+        // re-encrypt everything with the new key
+        assert_eq!(env.db().rotate("emails")?, 2);
+        assert_eq!(env.db().rotate("emails")?, 0);
+    }
+
+    {
+        // afterwards the old key can be removed
+        let keys = Keyring::new(2, Key::chacha20_poly1305(&[2; 32]));
+        let env = Env::new(EncryptedBackend::new(MemDB::open(path)?, keys));
+        let db = env.open::<&str, &str>("emails")?;
+        assert_eq!(db.get("bob")?, Some("bob@example.com".to_string()));
+    }
+
+    {
+        // values are bound to their database, a copied value can't be decrypted
+        let memdb = MemDB::new();
+        let keys = Keyring::new(1, Key::aes_256_gcm(&[4; 32]));
+        let env = Env::new(EncryptedBackend::new(memdb.clone(), keys));
+        env.open::<&str, &str>("emails")?
+            .set("carol", "carol@example.com")?;
+
+        let raw = Env::new(memdb);
+        let val = raw.open::<&str, Vec<u8>>("emails")?.get("carol")?;
+        raw.open::<&str, Vec<u8>>("copy")?
+            .set("carol", &val.expect("value exists"))?;
+        assert!(env.open::<&str, &str>("copy")?.get("carol").is_err());
+
+        // renaming a database encrypts the values again
+        env.rename_database("emails", "contacts")?;
+        let db = env.open::<&str, &str>("contacts")?;
+        assert_eq!(db.get("carol")?, Some("carol@example.com".to_string()));
+    }
+
+    {
+        // keys can be encrypted too, but this can't be enabled for existing databases
+        let keys = Keyring::new(1, Key::aes_256_gcm(&[3; 32]));
+        let env = Env::new(EncryptedBackend::new(MemDB::new(), keys).encrypt_keys());
+        let db = env.open::<&str, &str>("secrets")?;
+        db.set("password", "hunter2")?;
+        assert_eq!(db.get("password")?, Some("hunter2".to_string()));
+
+        // encrypted keys are not ordered, so prefixes can't be found
+        assert!(db.iter_prefix_raw("pass").is_err());
+        assert_eq!(db.iter()?.count(), 1);
+    }
+
+    Ok(())
+}
+
+#[test] // ensure that the example always works
+fn test() -> Result<()> {
+    main()
+}
@@ -0,0 +1,49 @@
+use eyre::Result;
+use okv::backend::heed::Heed;
+use okv::Env;
+
+fn main() -> Result<()> {
+    // initialize the storage backend, the directory is created if it doesn't exist
+    let heed = Heed::new("database/example-heed")?;
+    let env = Env::new(heed);
+
+    let db = env.open::<&str, &str>("test")?;
+    db.set("hello", "world")?;
+
+    // values are borrowed from the memory map while the returned reference is alive
+    let val = db.get_ref("hello")?.expect("value exists");
+    assert_eq!(val.deserialize()?, "world");
+
+    // transactions see their own writes before they are committed
+    let tx = db.transaction()?;
+    tx.set("foo", "bar")?;
+    assert_eq!(tx.get("foo")?, Some("bar".to_string()));
+    tx.commit()?;
+
+    // keys can be deleted by range or prefix, also inside a transaction
+    let tx = db.transaction()?;
+    tx.set("tmp/1", "a")?;
+    tx.set("tmp/2", "b")?;
+    assert_eq!(tx.delete_prefix("tmp/")?, Some(2));
+    tx.commit()?;
+    assert_eq!(db.delete_range("a".."b")?, Some(0));
+
+    let items = db.iter()?.collect::<Result<Vec<_>, _>>()?;
+    assert!(items.len() >= 2);
+
+    // an empty prefix or start matches every key
+    let tx = db.transaction()?;
+    assert_eq!(tx.delete_prefix("")?, Some(items.len() as u64));
+    tx.commit()?;
+    assert!(db.iter()?.next().is_none());
+
+    db.set("hello", "world")?;
+    assert_eq!(db.delete_range_raw(b"", None)?, Some(1));
+
+    Ok(())
+}
+
+#[test] // ensure that the example always works
+fn test() -> Result<()> {
+    main()
+}
@@ -0,0 +1,79 @@
+use eyre::Result;
+use okv::backend::memory::MemDB;
+use okv::Env;
+
+fn main() -> Result<()> {
+    std::fs::create_dir_all("database")?;
+    let path = "database/example-memory.okv";
+    let _ = std::fs::remove_file(path);
+    let _ = std::fs::remove_file("database/example-memory.okv.wal");
+
+    {
+        // all writes are appended to `example-memory.okv.wal`
+        let env = Env::new(MemDB::open(path)?);
+        let db = env.open::<&str, &str>("test")?;
+        db.set("hello", "world")?;
+        db.set("foo", "bar")?;
+        db.delete("foo")?;
+
+        // range deletes are logged as a single record
+        db.set("tenant/1", "a")?;
+        db.set("tenant/2", "b")?;
+        assert_eq!(db.delete_prefix("tenant/")?, Some(2));
+    }
+
+    {
+        // the log is replayed when the database is opened again
+        let env = Env::new(MemDB::open(path)?);
+        let db = env.open::<&str, &str>("test")?;
+        assert_eq!(db.get("hello")?, Some("world".to_string()));
+        assert_eq!(db.get("foo")?, None);
+        assert_eq!(db.get("tenant/1")?, None);
+
+        env.rename_database("test", "renamed")?;
+        env.open::<&str, &str>("test")?.set("new", "db")?;
+        let log = std::fs::read("database/example-memory.okv.wal")?;
+
+        // flushing writes a snapshot of all databases and starts a new log
+        env.open::<&str, &str>("renamed")?.flush()?;
+
+        // a log left over from a crash before it was restarted is not replayed again,
+        // which would rename the new "test" database as well, replacing "renamed"
+        std::fs::write("database/example-memory.okv.wal", log)?;
+    }
+
+    let env = Env::new(MemDB::open(path)?);
+    let db = env.open::<&str, &str>("renamed")?;
+    assert_eq!(db.get("hello")?, Some("world".to_string()));
+    assert_eq!(db.get("new")?, None);
+    let db = env.open::<&str, &str>("test")?;
+    assert_eq!(db.get("new")?, Some("db".to_string()));
+
+    // creating a database is logged, so empty databases exist after a restart
+    env.open::<&str, &str>("empty")?;
+    db.set("last", "write")?;
+    drop((db, env));
+    let log = std::fs::read("database/example-memory.okv.wal")?;
+
+    // a record at the end of the log that was cut short by a crash is discarded
+    std::fs::write("database/example-memory.okv.wal", &log[..log.len() - 1])?;
+    let env = Env::new(MemDB::open(path)?);
+    assert!(env.exists("empty")?);
+    assert_eq!(env.open::<&str, &str>("test")?.get("last")?, None);
+    drop(env);
+
+    // a damaged record before the end of the log fails to open, instead of discarding
+    // the records after it
+    let mut damaged = log.clone();
+    let pos = damaged.windows(5).position(|w| w == b"empty").unwrap();
+    damaged[pos] = b'E';
+    std::fs::write("database/example-memory.okv.wal", damaged)?;
+    assert!(MemDB::open(path).is_err());
+
+    Ok(())
+}
+
+#[test] // ensure that the example always works
+fn test() -> Result<()> {
+    main()
+}
@@ -0,0 +1,79 @@
+use eyre::Result;
+use okv::backend::{memory::MemDB, sled::Sled};
+use okv::migrate::{Checkpoint, DatabaseCheckpoint, MigrateOptions};
+use okv::Env;
+
+fn main() -> Result<()> {
+    let src = Env::new(MemDB::new());
+    let users = src.open::<&str, &str>("users")?;
+    for i in 0..25 {
+        users.set(&format!("user{i}"), "active")?;
+    }
+    let settings = src.open::<&str, &str>("settings")?;
+    settings.set("theme", "dark")?;
+
+    let dst = Env::new(Sled::new("database/example-migrate")?);
+
+    // copy a single database first, keeping the latest checkpoint
+    let mut checkpoint = Checkpoint::default();
+    let mut batches = 0;
+    okv::migrate(
+        &src,
+        &dst,
+        MigrateOptions {
+            batch_size: 10,
+            databases: Some(vec!["users".to_string()]),
+            progress: Some(Box::new(|progress| {
+                batches += 1;
+                checkpoint = progress.checkpoint.clone();
+            })),
+            ..Default::default()
+        },
+    )?;
+    // three batches (10, 10 and 5 entries) and the completed database
+    assert_eq!(batches, 4);
+    assert!(checkpoint.is_complete("users"));
+
+    // resuming from the checkpoint skips databases that are already complete
+    let stats = okv::migrate(
+        &src,
+        &dst,
+        MigrateOptions {
+            checkpoint,
+            ..Default::default()
+        },
+    )?;
+    assert_eq!((stats.databases, stats.entries), (1, 1));
+
+    let users = dst.open::<&str, String>("users")?;
+    assert_eq!(users.iter()?.count(), 25);
+    let settings = dst.open::<&str, &str>("settings")?;
+    assert_eq!(settings.get("theme")?, Some("dark".to_string()));
+
+    // resuming after a key that was deleted from the source since is an error
+    let mut checkpoint = Checkpoint::default();
+    checkpoint.databases.insert(
+        "users".to_string(),
+        DatabaseCheckpoint {
+            last_key: Some(b"deleted".to_vec()),
+            entries: 10,
+            complete: false,
+        },
+    );
+    let res = okv::migrate(
+        &src,
+        &dst,
+        MigrateOptions {
+            checkpoint,
+            ..Default::default()
+        },
+    );
+    assert!(matches!(res, Err(okv::Error::CheckpointKeyMissing { .. })));
+
+    Ok(())
+}
+
+#[test] // ensure that the example always works
+fn test() -> Result<()> {
+    main()
+}
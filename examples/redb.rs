@@ -0,0 +1,62 @@
+use eyre::Result;
+use okv::backend::redb::Redb;
+use okv::types::lazy::LazyDecode;
+use okv::{DBCommonIterPrefix, Env};
+
+fn main() -> Result<()> {
+    // initialize the storage backend
+    std::fs::create_dir_all("database")?;
+    let redb = Redb::new("database/example-redb.redb")?;
+    let env = Env::new(redb);
+
+    let db = env.open::<&str, &str>("test")?;
+    db.set("hello", "world")?;
+    db.set("foo", "bar")?;
+
+    // compaction needs exclusive access, so it fails while transactions are open
+    env.compact()?;
+    assert!(env.check_integrity()?);
+
+    // values are read without copying them, the read transaction stays open until
+    // the value is dropped
+    let val = db.get_ref("hello")?.expect("value exists");
+    db.set("hello", "sailor")?;
+    assert_eq!(val.deserialize()?, "world");
+
+    // all values of a batch are read in the same transaction
+    let vals = db.get_multi_ref(["hello", "foo", "missing"])?;
+    let vals = vals
+        .iter()
+        .map(|val| val.as_ref().map(|val| val.deserialize()).transpose())
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(vals, vec![Some("sailor"), Some("bar"), None]);
+
+    // visit entries without copying them, values are only decoded when they are needed
+    let lazy = env.open::<&str, LazyDecode<&str>>("test")?;
+    let mut found = Vec::new();
+    lazy.for_each_prefix_ref("fo", |entry| {
+        found.push(entry.value()?.decode()?.to_string());
+        Ok(true)
+    })?;
+    assert_eq!(found, vec!["bar"]);
+
+    // keys are read without the values
+    let keys = db
+        .keys_prefix::<&str>("fo")?
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(keys, vec!["foo"]);
+    assert_eq!(db.count()?, 2);
+
+    // redb counts the keys exactly and reports the size of the table
+    let stats = db.stats()?;
+    assert_eq!(stats.keys.get(), 2);
+    assert!(stats.disk_bytes.is_some_and(|bytes| bytes > 0));
+    assert!(stats.properties.contains_key("tree_height"));
+
+    Ok(())
+}
+
+#[test] // ensure that the example always works
+fn test() -> Result<()> {
+    main()
+}
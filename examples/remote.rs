@@ -0,0 +1,47 @@
+use std::net::TcpListener;
+
+use eyre::Result;
+use okv::backend::memory::MemDB;
+use okv::backend::remote::{RemoteDb, Server};
+use okv::Env;
+
+fn main() -> Result<()> {
+    // serve an in-memory database on a random port
+    // usually, this would be a separate process running `okv-remote-server`
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = format!("tcp://{}", listener.local_addr()?);
+    let server = Server::new(Env::new(MemDB::new()));
+    std::thread::spawn(move || server.serve_tcp(listener));
+
+    // connect to the server and use it like any other backend
+    let env = Env::new(RemoteDb::connect(&addr)?);
+    let db = env.open::<&str, &str>("test")?;
+
+    db.set_nx("hello", "world")?;
+    assert_eq!(db.get("hello")?, Some("world".to_string()));
+
+    // writes in a transaction are sent to the server on commit
+    let tx = db.transaction()?;
+    tx.set("foo", "bar")?;
+    assert_eq!(tx.get("foo")?, Some("bar".to_string()));
+    assert_eq!(db.get("foo")?, None);
+    tx.commit()?;
+
+    // a transaction fails to commit if a key it read was modified in the meantime
+    let tx = db.transaction()?;
+    assert_eq!(tx.get("foo")?, Some("bar".to_string()));
+    db.set("foo", "baz")?;
+    tx.set("foo", "qux")?;
+    assert!(tx.commit().is_err());
+    assert_eq!(db.get("foo")?, Some("baz".to_string()));
+
+    let items = db.iter()?.collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(items.len(), 2);
+
+    Ok(())
+}
+
+#[test] // ensure that the example always works
+fn test() -> Result<()> {
+    main()
+}
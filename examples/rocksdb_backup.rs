@@ -0,0 +1,56 @@
+use eyre::Result;
+use okv::backend::rocksdb::{backup, RocksDb, RocksDbReadOnly, RocksDbSecondary};
+use okv::Env;
+
+fn main() -> Result<()> {
+    // start from scratch, checkpoints and restores need directories that don't exist
+    let _ = std::fs::remove_dir_all("database/example-rocksdb-backup");
+    std::fs::create_dir_all("database/example-rocksdb-backup")?;
+
+    let env = Env::new(RocksDb::new("database/example-rocksdb-backup/primary")?);
+    let db = env.open::<&str, &str>("test")?;
+    db.set("hello", "world")?;
+
+    // a checkpoint is a complete copy of the database, opened read-only here
+    env.db()
+        .create_checkpoint("database/example-rocksdb-backup/checkpoint")?;
+    let copy = Env::new(RocksDbReadOnly::new(
+        "database/example-rocksdb-backup/checkpoint",
+    )?);
+    let copy_db = copy.open::<&str, String>("test")?;
+    assert_eq!(copy_db.get("hello")?, Some("world".to_string()));
+
+    // a secondary instance follows the writes of the primary
+    let secondary = Env::new(RocksDbSecondary::new(
+        "database/example-rocksdb-backup/primary",
+        "database/example-rocksdb-backup/secondary",
+    )?);
+    let secondary_db = secondary.open::<&str, String>("test")?;
+    db.set("hello", "sailor")?;
+    secondary.db().try_catch_up_with_primary()?;
+    assert_eq!(secondary_db.get("hello")?, Some("sailor".to_string()));
+
+    // backups are incremental and can be restored into a new directory
+    let id = env
+        .db()
+        .create_backup("database/example-rocksdb-backup/backups")?;
+    assert_eq!(
+        backup::list("database/example-rocksdb-backup/backups")?.len(),
+        1
+    );
+    backup::restore(
+        "database/example-rocksdb-backup/backups",
+        "database/example-rocksdb-backup/restored",
+        Some(id),
+    )?;
+    let restored = Env::new(RocksDb::new("database/example-rocksdb-backup/restored")?);
+    let restored_db = restored.open::<&str, String>("test")?;
+    assert_eq!(restored_db.get("hello")?, Some("sailor".to_string()));
+
+    Ok(())
+}
+
+#[test] // ensure that the example always works
+fn test() -> Result<()> {
+    main()
+}
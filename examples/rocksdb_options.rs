@@ -0,0 +1,57 @@
+use eyre::Result;
+use okv::backend::rocksdb::{ColumnOptions, RocksDb};
+use okv::{Env, KeyRange};
+
+/// The keys of decoded entries.
+fn keys(items: &[(Vec<u8>, String)]) -> Vec<&[u8]> {
+    items.iter().map(|(key, _)| key.as_slice()).collect()
+}
+
+fn main() -> Result<()> {
+    // start from scratch, the filters are written when the column family is flushed
+    let _ = std::fs::remove_dir_all("database/example-rocksdb-options");
+    std::fs::create_dir_all("database/example-rocksdb-options")?;
+    let env = Env::new(RocksDb::new("database/example-rocksdb-options")?);
+
+    // keys start with a 4 byte tenant id, scans of one tenant use the prefix filters
+    let db = env.open_with::<&[u8], &str>("events", ColumnOptions::prefix_scan(4))?;
+    let events: [(&[u8], &str); 4] = [
+        (b"acme:1", "login"),
+        (b"acme:2", "logout"),
+        (b"bolt:1", "login"),
+        (b"cyan:1", "login"),
+    ];
+    for (key, val) in events {
+        db.set(key, val)?;
+    }
+    db.flush()?;
+
+    let page = db.page(None, 10, b"acme")?;
+    assert_eq!(keys(&page.items), [b"acme:1", b"acme:2"]);
+
+    // scans that cross prefixes still see every key
+    let page = db.page(None, 3, [])?;
+    assert_eq!(keys(&page.items), [b"acme:1", b"acme:2", b"bolt:1"]);
+    let page = db.page(page.next, 3, [])?;
+    assert_eq!(keys(&page.items), [b"cyan:1"]);
+
+    let range = KeyRange {
+        start: b"acme:2".to_vec(),
+        end: Some(b"cyan".to_vec()),
+    };
+    let items = db.iter_range(range)?.collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(keys(&items), [b"acme:2", b"bolt:1"]);
+
+    let mut count = 0;
+    for range in db.split_ranges(2)? {
+        count += db.iter_range(range)?.count();
+    }
+    assert_eq!(count, events.len());
+
+    Ok(())
+}
+
+#[test] // ensure that the example always works
+fn test() -> Result<()> {
+    main()
+}
@@ -1,7 +1,7 @@
 use eyre::Result;
 use okv::backend::memory::MemDB;
 use okv::types::serde::SerdeJson;
-use okv::Env;
+use okv::{Env, TypeName};
 
 use serde::{Deserialize, Serialize};
 
@@ -11,6 +11,10 @@ struct Person {
     age: u32,
 }
 
+impl TypeName for Person {
+    const TYPE_NAME: &'static str = "person";
+}
+
 fn main() -> Result<()> {
     // initialize the storage backend
     let memdb = MemDB::new();
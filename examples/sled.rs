@@ -0,0 +1,50 @@
+use eyre::Result;
+use okv::backend::sled::Sled;
+use okv::Env;
+
+fn main() -> Result<()> {
+    // initialize the storage backend
+    let sled = Sled::new("database/example-sled")?;
+    let env = Env::new(sled);
+
+    let db = env.open::<&str, &str>("test")?;
+    db.set("hello", "world")?;
+    assert_eq!(db.get("hello")?, Some("world".to_string()));
+
+    // values are reference counted and can be read without copying them
+    let val = db.get_ref("hello")?.expect("value exists");
+    assert_eq!(val.deserialize()?, "world");
+
+    // transactions fail to commit if a key they read was modified in the meantime
+    let tx = db.transaction()?;
+    assert_eq!(tx.get("hello")?, Some("world".to_string()));
+    db.set("hello", "sailor")?;
+    tx.set("hello", "there")?;
+    assert!(tx.commit().is_err());
+    assert_eq!(db.get("hello")?, Some("sailor".to_string()));
+
+    // scans can be split into pages, the cursor continues after the last key of a page
+    for i in 1..=5 {
+        db.set(&format!("page/{i}"), "x")?;
+    }
+    let (mut cursor, mut pages) = (None, 0);
+    loop {
+        let page = db.page(cursor, 2, "page/")?;
+        pages += 1;
+        match page.next {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+    assert_eq!(pages, 3);
+
+    db.flush()?;
+    db.delete_db()?;
+
+    Ok(())
+}
+
+#[test] // ensure that the example always works
+fn test() -> Result<()> {
+    main()
+}
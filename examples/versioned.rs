@@ -0,0 +1,119 @@
+use eyre::Result;
+use okv::backend::memory::MemDB;
+use okv::types::serde::{Initial, Migrate, SerdeJson, Versioned};
+use okv::{Env, TypeName};
+
+use serde::{Deserialize, Serialize};
+
+// the first version of a person, as it was stored before
+#[derive(Serialize, Deserialize)]
+struct PersonV1 {
+    name: String,
+}
+
+// every version has the same name, so migrating keeps the recorded schema
+impl TypeName for PersonV1 {
+    const TYPE_NAME: &'static str = "person";
+}
+
+impl Migrate for PersonV1 {
+    const VERSION: u32 = 1;
+    type Previous = Initial;
+    fn migrate(previous: Initial) -> Self {
+        match previous {}
+    }
+}
+
+// the current version, which added an age
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Person {
+    name: String,
+    age: Option<u32>,
+}
+
+impl TypeName for Person {
+    const TYPE_NAME: &'static str = "person";
+}
+
+impl Migrate for Person {
+    const VERSION: u32 = 2;
+    type Previous = PersonV1;
+    fn migrate(previous: PersonV1) -> Self {
+        Self {
+            name: previous.name,
+            age: None,
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    let env = Env::with_schema_registry(MemDB::new());
+
+    // write some values with the old version
+    let old = env.open::<&str, Versioned<SerdeJson<PersonV1>>>("people")?;
+    for name in ["alice", "bob", "carol"] {
+        old.set(name, &PersonV1 { name: name.into() })?;
+    }
+
+    // old values are migrated when they are read
+    let db = env.open::<&str, Versioned<SerdeJson<Person>>>("people")?;
+    let alice = db.get("alice")?;
+    assert_eq!(
+        alice,
+        Some(Person {
+            name: "alice".into(),
+            age: None
+        })
+    );
+
+    // new values are written with the current version
+    db.set(
+        "dave",
+        &Person {
+            name: "dave".into(),
+            age: Some(42),
+        },
+    )?;
+
+    // rewrite all old values, so they don't have to be migrated on every read
+    assert_eq!(db.migrate_all(2)?, 3);
+    assert_eq!(db.migrate_all(2)?, 0);
+
+    // reading with the old version fails now
+    assert!(old.get("alice").is_err());
+
+    // values written before the type was versioned are adopted as the first version
+    let legacy = env.open::<&str, SerdeJson<PersonV1>>("legacy")?;
+    legacy.set(
+        "erin",
+        &PersonV1 {
+            name: "erin".into(),
+        },
+    )?;
+    // a value that was already tagged by an interrupted call is skipped
+    legacy.set_raw("frank", b"\x01\x00\x00\x00{\"name\":\"frank\"}")?;
+    let db = legacy
+        .into_versioned()?
+        .as_type::<&str, Versioned<SerdeJson<Person>>>()?;
+    assert_eq!(
+        db.get("erin")?,
+        Some(Person {
+            name: "erin".into(),
+            age: None
+        })
+    );
+    assert_eq!(
+        db.get("frank")?,
+        Some(Person {
+            name: "frank".into(),
+            age: None
+        })
+    );
+
+    Ok(())
+}
+
+#[test] // ensure that the example always works
+fn test() -> Result<()> {
+    main()
+}
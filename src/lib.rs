@@ -21,6 +21,12 @@ pub use okv_core::db::Database;
 #[doc(inline)]
 pub use okv_core::db::DatabaseTransaction;
 
+#[doc(inline)]
+pub use okv_core::db::{Cursor, Page};
+
+#[doc(inline)]
+pub use okv_core::backend::{ColumnStats, KeyCount, KeyRange};
+
 #[doc(inline)]
 pub use okv_core::env::Env;
 
@@ -31,11 +37,31 @@ pub use okv_core::error::{DecodeError, EncodeError, Error};
 /// Serialization types
 pub use okv_core::types;
 
+#[doc(inline)]
+/// Backend independent export and import
+pub use okv_core::dump;
+
+#[doc(inline)]
+pub use okv_core::migrate::{self, migrate};
+
+#[doc(inline)]
+/// Registry of the codecs of every database
+pub use okv_core::schema;
+
+#[doc(inline)]
+pub use okv_core::traits::{CodecId, TypeName};
+
 pub mod backend {
     //! Database backends
     //!
     //! * [`rocksdb`] - In-memory database backend (requires `memory` feature)
     //! * [`memory`] - RocksDB database backend (requires `rocksdb` feature). Based on <https://crates.io/crates/rocksdb>.
+    //! * [`remote`] - Client for a database served by another process over TCP or a Unix socket (requires `remote` feature).
+    //! * [`redis`] - Redis database backend, every database is a key namespace (requires `redis` feature). Based on <https://crates.io/crates/redis>.
+    //! * [`redb`] - Pure Rust embedded database backend. Based on <https://crates.io/crates/redb>.
+    //! * [`heed`] - LMDB database backend (requires `heed` feature). Based on <https://crates.io/crates/heed>.
+    //! * [`sled`] - Pure Rust database backend (requires `sled` feature). Based on <https://crates.io/crates/sled>.
+    //! * [`encrypted`] - Encrypts the values of another backend (requires `encrypted` feature).
     // //! * [`sqlite`] - Sqlite database backend (requires `sqlite` feature). Based on <https://crates.io/crates/rusqlite>.
 
     #[cfg(feature = "rocksdb")]
@@ -50,6 +76,29 @@ pub mod backend {
     #[doc(inline)]
     pub use okv_cloudflare as cloudflare;
 
+    #[cfg(feature = "remote")]
+    #[doc(inline)]
+    pub use okv_remote as remote;
+
+    #[cfg(feature = "redis")]
+    #[doc(inline)]
+    pub use okv_redis as redis;
+
+    #[doc(inline)]
+    pub use okv_redb as redb;
+
+    #[cfg(feature = "heed")]
+    #[doc(inline)]
+    pub use okv_heed as heed;
+
+    #[cfg(feature = "sled")]
+    #[doc(inline)]
+    pub use okv_sled as sled;
+
+    #[cfg(feature = "encrypted")]
+    #[doc(inline)]
+    pub use okv_encrypted as encrypted;
+
     // TODO
     // #[cfg(feature = "sqlite")]
     // #[doc(inline)]
@@ -58,8 +107,8 @@ pub mod backend {
 
 #[doc(inline)]
 pub use okv_core::traits::{
-    DBCommon, DBCommonClear, DBCommonDelete, DBCommonIter, DBCommonIterPrefix, DBCommonRef,
-    DBCommonRefBatch,
+    DBCommon, DBCommonClear, DBCommonDelete, DBCommonDeleteRange, DBCommonIter, DBCommonIterPrefix,
+    DBCommonRef, DBCommonRefBatch,
 };
 
 #[doc(inline)]